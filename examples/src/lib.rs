@@ -2,6 +2,7 @@
 mod tests {
     use smoke::generator::num;
     use smoke::property;
+    use smoke::Generator;
     use smoke_macros::smoketest;
 
     #[smoketest{a: num::<u32>()}]
@@ -11,6 +12,24 @@ mod tests {
 
     #[smoketest{a: num::<u32>(), b: num::<u32>() }]
     fn test2(a: u32, b: u32) {
-        property::equal(a + b, b + a)
+        property::equal(a.wrapping_add(b), b.wrapping_add(a))
+    }
+
+    #[smoketest{a: num::<u8>(), b: num::<u8>(), c: num::<u8>() }]
+    fn test3(a: u8, b: u8, c: u8) {
+        property::equal(a ^ b ^ c, c ^ b ^ a)
+    }
+
+    #[smoketest{a: num::<u8>(), b: num::<u8>(), c: num::<u8>(), d: num::<u8>() }]
+    fn test4(a: u8, b: u8, c: u8, d: u8) {
+        property::equal(a ^ b ^ c ^ d, d ^ c ^ b ^ a)
+    }
+
+    #[derive(Debug)]
+    struct Handle(u32);
+
+    #[smoketest{a: num::<u32>().map(Handle), b: num::<u32>() }]
+    fn test_not_clone(a: Handle, b: u32) {
+        property::equal(a.0 ^ b ^ b, a.0)
     }
 }
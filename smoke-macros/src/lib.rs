@@ -36,8 +36,8 @@ pub fn smoketest(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = syn::parse_macro_input!(args as Args);
     let name = ast.sig.ident;
 
-    if args.vars.len() > 3 {
-        panic!("cannot generate macro with more than 3 arguments")
+    if args.vars.len() > 6 {
+        panic!("cannot generate macro with more than 6 arguments")
     }
 
     if ast.sig.variadic.is_some() {
@@ -54,15 +54,14 @@ pub fn smoketest(args: TokenStream, input: TokenStream) -> TokenStream {
                     _ => panic!("function argument not supported"),
                 };
 
-                if arg.0.to_string() != arg_ident.to_string() {
+                if arg.0 != arg_ident {
                     panic!(
                         "function argument {}: '{}' doesn't match expected generator '{}'",
                         i, arg.0, arg_ident
                     );
                 }
 
-                let ty = t.ty.clone();
-                fnargs.push((arg_ident, ty, arg.1));
+                fnargs.push((arg_ident, arg.1));
             }
         }
     }
@@ -74,19 +73,19 @@ pub fn smoketest(args: TokenStream, input: TokenStream) -> TokenStream {
     let (forall_body, ensure_body) = if nb_args == 1 {
         let fnarg = &fnargs[0];
         let arg_name = &fnarg.0;
-        let body = &fnarg.2;
+        let body = &fnarg.1;
         let forall_body = quote! { #body };
         let ensure_body = quote! { |#arg_name| #property_body };
         (forall_body, ensure_body)
     } else {
-        let product_ident = quote::format_ident!("product{}", nb_args);
-        let generators = fnargs.iter().map(|x| &x.2).collect::<Vec<_>>();
+        let tuple_ident = quote::format_ident!("tuple{}", nb_args);
+        let generators = fnargs.iter().map(|x| &x.1).collect::<Vec<_>>();
         let arg_names = fnargs.iter().map(|x| &x.0).collect::<Vec<_>>();
-        let tupler = quote! { | #(#arg_names),* | ( #(#arg_names),* ) };
         let forall_body = quote! {
-            ::smoke::generator::#product_ident ( #(#generators),* , #tupler)
+            ::smoke::generator::#tuple_ident ( #(#generators),* )
         };
-        let ensure_body = quote! { | (#(#arg_names),* )| #property_body };
+        // the tuple is destructured by value into the function arguments
+        let ensure_body = quote! { |( #(#arg_names),* )| #property_body };
 
         (forall_body, ensure_body)
     };
@@ -95,7 +94,7 @@ pub fn smoketest(args: TokenStream, input: TokenStream) -> TokenStream {
         #[test]
         fn #name() {
             use ::smoke::Testable;
-            ::smoke::run(|ctx| ::smoke::forall(#forall_body).ensure(#ensure_body).run(ctx))
+            ::smoke::run(|ctx| ::smoke::forall(#forall_body).ensure(#ensure_body).run(ctx));
         }
     };
     TokenStream::from(tokens)
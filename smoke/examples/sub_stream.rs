@@ -0,0 +1,67 @@
+//! Cost of the sub-stream derivation on a trivial property
+//!
+//! Runs 10M iterations of `num::<u64>()`, which draws directly from the random
+//! generator of the test, and of the same generator forced to use a sub-stream per
+//! iteration as before `Generator::needs_sub_stream`, both as tests and as bare
+//! generation loops:
+//!
+//! ```text
+//! cargo run --release --example sub_stream
+//! ```
+
+use smoke::{forall, generator::num, property::equal, Context, Generator, Testable, R};
+use std::time::Instant;
+
+const NB_TESTS: u64 = 10_000_000;
+
+/// A generator using a derived sub-stream per iteration
+struct SubStream<G>(G);
+
+impl<G: Generator> Generator for SubStream<G> {
+    type Item = G::Item;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        self.0.gen(r)
+    }
+}
+
+fn time<G>(name: &str, ctx: &Context, generator: G)
+where
+    G: Generator<Item = u64>,
+{
+    let start = Instant::now();
+    let results = forall(generator).ensure(|n| equal(n, n)).test(ctx);
+    assert_eq!(results.nb_success, NB_TESTS as usize);
+    println!("{:>12}: {:.2}s", name, start.elapsed().as_secs_f64());
+}
+
+fn time_generation<G>(name: &str, generator: G)
+where
+    G: Generator<Item = u64>,
+{
+    let mut r = R::from_seed(smoke::Seed::from(1));
+    let start = Instant::now();
+    let mut sum = 0u64;
+    for _ in 0..NB_TESTS {
+        let n = if generator.needs_sub_stream() {
+            generator.gen(&mut r.sub())
+        } else {
+            generator.gen(&mut r)
+        };
+        sum = sum.wrapping_add(n);
+    }
+    println!(
+        "{:>12}: {:.2}s (generation only, {:x})",
+        name,
+        start.elapsed().as_secs_f64(),
+        sum
+    );
+}
+
+fn main() {
+    let mut ctx = Context::new();
+    ctx.set_nb_tests(NB_TESTS);
+    time("sub-stream", &ctx, SubStream(num::<u64>()));
+    time("direct", &ctx, num::<u64>());
+    time_generation("sub-stream", SubStream(num::<u64>()));
+    time_generation("direct", num::<u64>());
+}
@@ -0,0 +1,369 @@
+//! Configuration of the test runs
+//!
+//! All the environment variables driving the runs are parsed and validated in a
+//! single place, `RunConfig::from_env`, which reports all the invalid variables at
+//! once instead of failing on the first one.
+//!
+//! | variable               | value                                              |
+//! |------------------------|----------------------------------------------------|
+//! | SMOKE_SEED             | seed of the run, as printed by the failures        |
+//! | SMOKE_NB_TESTS         | number of tests of each property, greater than 0   |
+//! | SMOKE_SOAK             | number of soak passes of `run`, greater than 0     |
+//! | SMOKE_VERBOSE          | set to any value to report the timings             |
+//! | SMOKE_PRINT_SEED       | set to any value to print the seed of all the runs |
+//! | SMOKE_EMIT_REPRO       | set to any value to emit reproduction tests        |
+//! | SMOKE_NO_PANIC_CATCH   | set to any value to let the panics unwind          |
+//! | SMOKE_MAX_FAILURES     | failures kept by each property, greater than 0     |
+//! | SMOKE_UPDATE_SNAPSHOTS | set to any value to write the snapshots            |
+//!
+//! SMOKE_UPDATE_SNAPSHOTS writes the snapshots of `property::matches_snapshot`
+//! instead of comparing with them. The snapshot properties have no context, so
+//! they read the configuration of the environment when created, instead of the
+//! configuration of their run.
+//!
+//! The size strategy, the heartbeat and the shared generators are only set
+//! programmatically, on the `Context`. The runs have no fail fast mode, output
+//! format or artifact directory: all the properties are run and reported as text,
+//! and the `TestResults` of a run can be exported by the caller (e.g. with
+//! `Elements::to_json` for the failure trees).
+
+use super::rand::Seed;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+pub(crate) const DEFAULT_NB_TESTS: u64 = 1_000;
+
+pub(crate) const ENV_SEED: &str = "SMOKE_SEED";
+pub(crate) const ENV_NB_TESTS: &str = "SMOKE_NB_TESTS";
+pub(crate) const ENV_SOAK: &str = "SMOKE_SOAK";
+pub(crate) const ENV_VERBOSE: &str = "SMOKE_VERBOSE";
+pub(crate) const ENV_PRINT_SEED: &str = "SMOKE_PRINT_SEED";
+pub(crate) const ENV_EMIT_REPRO: &str = "SMOKE_EMIT_REPRO";
+pub(crate) const ENV_NO_PANIC_CATCH: &str = "SMOKE_NO_PANIC_CATCH";
+pub(crate) const ENV_MAX_FAILURES: &str = "SMOKE_MAX_FAILURES";
+pub(crate) const ENV_UPDATE_SNAPSHOTS: &str = "SMOKE_UPDATE_SNAPSHOTS";
+
+#[cfg(test)]
+thread_local! {
+    /// Environment variables read by this thread, to check the reads in tests
+    pub(crate) static ENV_READS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn env_var(key: &str) -> Option<String> {
+    #[cfg(test)]
+    ENV_READS.with(|reads| reads.borrow_mut().push(key.to_string()));
+    std::env::var(key).ok()
+}
+
+/// Whether the snapshots are written instead of compared with, according to the
+/// configuration of the environment
+///
+/// An invalid configuration doesn't update the snapshots, the run reporting it.
+pub(crate) fn update_snapshots() -> bool {
+    RunConfig::from_env().is_ok_and(|config| config.update_snapshots())
+}
+
+/// An environment variable with an invalid value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidVar {
+    /// Name of the variable
+    pub name: &'static str,
+    /// Value of the variable
+    pub value: String,
+    /// Description of the accepted values
+    pub expected: &'static str,
+}
+
+impl fmt::Display for InvalidVar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid {}={:?}: expecting {}",
+            self.name, self.value, self.expected
+        )
+    }
+}
+
+/// All the invalid variables of a configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub invalid: Vec<InvalidVar>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid smoke configuration:")?;
+        for invalid in self.invalid.iter() {
+            write!(f, "\n  {}", invalid)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Parser of the values of a variable, and description of the accepted values
+struct ValueSpec<T> {
+    parse: fn(&str) -> Option<T>,
+    expected: &'static str,
+}
+
+impl<T> ValueSpec<T> {
+    /// Parse the variable `name` if set, recording it as invalid if it can't be parsed
+    fn parse<F>(&self, lookup: F, name: &'static str, invalid: &mut Vec<InvalidVar>) -> Option<T>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let value = lookup(name)?;
+        let parsed = (self.parse)(&value);
+        if parsed.is_none() {
+            invalid.push(InvalidVar {
+                name,
+                value,
+                expected: self.expected,
+            });
+        }
+        parsed
+    }
+}
+
+const SEED: ValueSpec<Seed> = ValueSpec {
+    parse: |v| v.parse().ok(),
+    expected: "4 hexadecimal values separated by -",
+};
+
+const COUNT: ValueSpec<u64> = ValueSpec {
+    parse: |v| v.parse().ok().filter(|n| *n > 0),
+    expected: "an integer greater than 0",
+};
+
+/// Configuration of a test run, from the environment or set programmatically
+///
+/// ```
+/// use smoke::{Context, RunConfig, Seed};
+///
+/// let mut config = RunConfig::new();
+/// config.set_seed(Some(Seed::from(0x1234)));
+/// config.set_nb_tests(50);
+/// let ctx = Context::from_config(&config);
+/// assert_eq!(ctx.nb_tests(), 50);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunConfig {
+    seed: Option<Seed>,
+    nb_tests: u64,
+    soak: u64,
+    verbose: bool,
+    print_seed: bool,
+    emit_repro: bool,
+    catch_panics: bool,
+    max_failures: Option<usize>,
+    update_snapshots: bool,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunConfig {
+    /// The default configuration, ignoring the environment
+    pub fn new() -> Self {
+        RunConfig {
+            seed: None,
+            nb_tests: DEFAULT_NB_TESTS,
+            soak: 1,
+            verbose: false,
+            print_seed: false,
+            emit_repro: false,
+            catch_panics: true,
+            max_failures: None,
+            update_snapshots: false,
+        }
+    }
+
+    /// Parse the configuration from the environment variables of the process
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_lookup(env_var)
+    }
+
+    /// Parse the configuration from the given variables instead of the environment
+    ///
+    /// ```
+    /// use smoke::RunConfig;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("SMOKE_NB_TESTS".to_string(), "0".to_string());
+    /// vars.insert("SMOKE_SEED".to_string(), "not a seed".to_string());
+    /// let error = RunConfig::from_map(&vars).unwrap_err();
+    /// assert_eq!(error.invalid.len(), 2);
+    /// ```
+    pub fn from_map(vars: &HashMap<String, String>) -> Result<Self, ConfigError> {
+        Self::from_lookup(|key| vars.get(key).cloned())
+    }
+
+    fn from_lookup<F: Fn(&str) -> Option<String>>(lookup: F) -> Result<Self, ConfigError> {
+        let mut invalid = Vec::new();
+        let seed = SEED.parse(&lookup, ENV_SEED, &mut invalid);
+        let nb_tests = COUNT.parse(&lookup, ENV_NB_TESTS, &mut invalid);
+        let soak = COUNT.parse(&lookup, ENV_SOAK, &mut invalid);
+        let max_failures = COUNT.parse(&lookup, ENV_MAX_FAILURES, &mut invalid);
+        if !invalid.is_empty() {
+            return Err(ConfigError { invalid });
+        }
+        Ok(RunConfig {
+            seed,
+            nb_tests: nb_tests.unwrap_or(DEFAULT_NB_TESTS),
+            soak: soak.unwrap_or(1),
+            verbose: lookup(ENV_VERBOSE).is_some(),
+            print_seed: lookup(ENV_PRINT_SEED).is_some(),
+            emit_repro: lookup(ENV_EMIT_REPRO).is_some(),
+            catch_panics: lookup(ENV_NO_PANIC_CATCH).is_none(),
+            max_failures: max_failures.map(|n| usize::try_from(n).unwrap_or(usize::MAX)),
+            update_snapshots: lookup(ENV_UPDATE_SNAPSHOTS).is_some(),
+        })
+    }
+
+    /// Seed of the run, or None to use a random seed shared by all the runs of the
+    /// process
+    pub fn seed(&self) -> Option<Seed> {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: Option<Seed>) {
+        self.seed = seed;
+    }
+
+    pub fn nb_tests(&self) -> u64 {
+        self.nb_tests
+    }
+
+    /// Set the number of tests of each property, which must be greater than 0
+    pub fn set_nb_tests(&mut self, nb_tests: u64) {
+        assert!(nb_tests > 0, "the number of tests must be greater than 0");
+        self.nb_tests = nb_tests;
+    }
+
+    pub fn soak(&self) -> u64 {
+        self.soak
+    }
+
+    /// Set the number of passes of `run`, each with a seed derived from the seed of
+    /// the run, which must be greater than 0
+    pub fn set_soak(&mut self, soak: u64) {
+        assert!(soak > 0, "the number of soak passes must be greater than 0");
+        self.soak = soak;
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn print_seed(&self) -> bool {
+        self.print_seed
+    }
+
+    pub fn set_print_seed(&mut self, print_seed: bool) {
+        self.print_seed = print_seed;
+    }
+
+    pub fn emit_repro(&self) -> bool {
+        self.emit_repro
+    }
+
+    pub fn set_emit_repro(&mut self, emit_repro: bool) {
+        self.emit_repro = emit_repro;
+    }
+
+    pub fn catch_panics(&self) -> bool {
+        self.catch_panics
+    }
+
+    pub fn set_catch_panics(&mut self, catch_panics: bool) {
+        self.catch_panics = catch_panics;
+    }
+
+    /// Maximum number of failures kept in the results of each property, or None
+    /// to keep all of them
+    pub fn max_failures(&self) -> Option<usize> {
+        self.max_failures
+    }
+
+    pub fn set_max_failures(&mut self, max_failures: Option<usize>) {
+        self.max_failures = max_failures;
+    }
+
+    pub fn update_snapshots(&self) -> bool {
+        self.update_snapshots
+    }
+
+    pub fn set_update_snapshots(&mut self, update_snapshots: bool) {
+        self.update_snapshots = update_snapshots;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn config_defaults() {
+        assert_eq!(RunConfig::from_map(&vars(&[])), Ok(RunConfig::new()));
+    }
+
+    #[test]
+    fn config_parsed() {
+        let config = RunConfig::from_map(&vars(&[
+            (ENV_SEED, "00000000-00000000-00000000-00001234"),
+            (ENV_NB_TESTS, "42"),
+            (ENV_SOAK, "3"),
+            (ENV_VERBOSE, ""),
+            (ENV_NO_PANIC_CATCH, "1"),
+            (ENV_MAX_FAILURES, "5"),
+            (ENV_UPDATE_SNAPSHOTS, "1"),
+        ]))
+        .unwrap();
+        assert_eq!(config.seed(), Some(Seed::from(0x1234)));
+        assert_eq!(config.nb_tests(), 42);
+        assert_eq!(config.soak(), 3);
+        assert!(config.verbose());
+        assert!(!config.print_seed());
+        assert!(!config.catch_panics());
+        assert_eq!(config.max_failures(), Some(5));
+        assert!(config.update_snapshots());
+    }
+
+    #[test]
+    fn config_all_invalid_reported() {
+        let error = RunConfig::from_map(&vars(&[
+            (ENV_SEED, "1234"),
+            (ENV_NB_TESTS, "0"),
+            (ENV_SOAK, "many"),
+            (ENV_MAX_FAILURES, "-1"),
+        ]))
+        .unwrap_err();
+        let names: Vec<&str> = error.invalid.iter().map(|i| i.name).collect();
+        assert_eq!(
+            names,
+            vec![ENV_SEED, ENV_NB_TESTS, ENV_SOAK, ENV_MAX_FAILURES]
+        );
+        assert_eq!(
+            error.to_string(),
+            "invalid smoke configuration:\n  invalid SMOKE_SEED=\"1234\": expecting 4 hexadecimal values separated by -\n  invalid SMOKE_NB_TESTS=\"0\": expecting an integer greater than 0\n  invalid SMOKE_SOAK=\"many\": expecting an integer greater than 0\n  invalid SMOKE_MAX_FAILURES=\"-1\": expecting an integer greater than 0"
+        );
+    }
+}
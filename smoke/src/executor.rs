@@ -0,0 +1,203 @@
+//! Execution of the futures of the async properties
+//!
+//! The async properties are driven to completion on the testing thread by an
+//! `Executor`, which is by default the minimal `BlockOn` executor. Runtimes
+//! with their own reactor (e.g. tokio) can be used by implementing `Executor`
+//! with their own `block_on`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, JoinHandle, Thread};
+use std::time::{Duration, Instant};
+
+/// Drive futures to completion on the current thread
+pub trait Executor {
+    /// Run `future` to completion, blocking the current thread
+    fn block_on<F: Future>(&self, future: F) -> F::Output;
+}
+
+impl<E: Executor + ?Sized> Executor for &E {
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        (**self).block_on(future)
+    }
+}
+
+/// The minimal executor, parking the current thread until the future is woken
+///
+/// This is enough for the futures only waiting on other threads (channels, timers
+/// threads, ...), but not for the futures relying on the reactor of a runtime.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockOn;
+
+impl Executor for BlockOn {
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        block_on(future)
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+/// Run `future` to completion on the current thread, parking it whilst the
+/// future is pending
+///
+/// ```
+/// use smoke::executor::block_on;
+///
+/// assert_eq!(block_on(async { 1 + 1 }), 2);
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Future completing with `None` when its deadline is reached before the inner future
+///
+/// A timer thread wakes the future at the deadline, so that the deadline is honored
+/// by any executor, even when the inner future is never woken. The timer thread is
+/// cancelled and joined when the future is dropped.
+pub(crate) struct Deadline<F> {
+    future: Pin<Box<F>>,
+    deadline: Instant,
+    timer: Option<Timer>,
+}
+
+impl<F> Deadline<F> {
+    pub(crate) fn new(future: F, timeout: Duration) -> Self {
+        Deadline {
+            future: Box::pin(future),
+            deadline: Instant::now() + timeout,
+            timer: None,
+        }
+    }
+}
+
+impl<F: Future> Future for Deadline<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(None);
+        }
+        if self.timer.is_none() {
+            self.timer = Some(Timer::start(self.deadline, cx.waker().clone()));
+        }
+        Poll::Pending
+    }
+}
+
+/// Thread waking a waker at a deadline, unless cancelled before
+struct Timer {
+    cancelled: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Timer {
+    fn start(deadline: Instant, waker: Waker) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread_cancelled = cancelled.clone();
+        let thread = thread::spawn(move || loop {
+            if thread_cancelled.load(Ordering::SeqCst) {
+                return;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return waker.wake();
+            }
+            // spurious wake-ups only cause one more check
+            thread::park_timeout(deadline - now);
+        });
+        Timer {
+            cancelled,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Future pending on its first poll, waking itself from another thread
+    struct WakeFromThread {
+        woken: bool,
+    }
+
+    impl Future for WakeFromThread {
+        type Output = u32;
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<u32> {
+            if self.woken {
+                Poll::Ready(42)
+            } else {
+                self.woken = true;
+                let waker = cx.waker().clone();
+                thread::spawn(move || waker.wake());
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn block_on_pending() {
+        assert_eq!(block_on(WakeFromThread { woken: false }), 42);
+    }
+
+    #[test]
+    fn deadline() {
+        let never = Deadline::new(std::future::pending::<u32>(), Duration::from_millis(10));
+        assert_eq!(block_on(never), None);
+        let woken = Deadline::new(WakeFromThread { woken: false }, Duration::from_secs(60));
+        assert_eq!(block_on(woken), Some(42));
+    }
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn deadline_timer_cancelled_on_drop() {
+        let noop = Arc::new(NoopWaker);
+        let waker = Waker::from(noop.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut pending = Box::pin(Deadline::new(
+            std::future::pending::<u32>(),
+            Duration::from_secs(3600),
+        ));
+        assert_eq!(pending.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(pending.as_mut().poll(&mut cx), Poll::Pending);
+        // the timer thread holds a clone of the waker until it is joined
+        assert_eq!(Arc::strong_count(&noop), 3);
+        drop(pending);
+        assert_eq!(Arc::strong_count(&noop), 2);
+    }
+}
@@ -1,4 +1,46 @@
-use super::super::rand::R;
+use super::super::rand::{Seed, R};
+use super::product::{product2, tuple2, Product2, Tuple2};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Implement the generation methods of `Generator` with the inherent methods
+/// `generate` (and `try_generate` for the fallible combinators), receiving the index
+/// of the test iteration when generating through `gen_indexed`
+///
+/// The combinators forward the index to their inner generators with `gen_at`, so
+/// that the examples of the inner generators (see `Generator::with_examples`) are
+/// still generated first.
+macro_rules! indexed_generation {
+    () => {
+        indexed_generation!(@gen);
+        fn try_gen_indexed(
+            &self,
+            idx: u64,
+            r: &mut R,
+        ) -> Result<Self::Item, $crate::generator::GenError> {
+            Ok(self.generate(Some(idx), r))
+        }
+    };
+    (@gen) => {
+        fn gen(&self, r: &mut R) -> Self::Item {
+            self.generate(None, r)
+        }
+        fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+            self.generate(Some(idx), r)
+        }
+    };
+    (fallible) => {
+        indexed_generation!(@gen);
+        fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+            self.try_generate(None, r)
+        }
+        fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+            self.try_generate(Some(idx), r)
+        }
+    };
+}
+pub(crate) use indexed_generation;
 
 /// Generator for an Item
 ///
@@ -10,6 +52,77 @@ pub trait Generator {
     /// Generate the next item
     fn gen(&self, r: &mut R) -> Self::Item;
 
+    /// Try to generate the next item, returning an error instead of panicking when
+    /// the generation is not possible (e.g. an exhausted `such_that` filter)
+    ///
+    /// By default the generation is infallible. Combinators of generators forward
+    /// the errors of the generators they are built from.
+    ///
+    /// ```
+    /// use smoke::{Generator, R, Seed, generator::{range, GenError}};
+    ///
+    /// let never = range(0u32..10).such_that(|n| *n > 10);
+    /// let mut r = R::from_seed(Seed::from(1));
+    /// assert_eq!(never.try_gen(&mut r), Err(GenError::Exhausted));
+    /// ```
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        Ok(self.gen(r))
+    }
+
+    /// Generate the item of the iteration `idx` of a test
+    ///
+    /// The tests generate their inputs through this method, so that a generator can
+    /// depend on the iteration index. By default the index is ignored by the leaf
+    /// generators, and the combinators forward it to their inner generators.
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        let _ = idx;
+        self.gen(r)
+    }
+
+    /// Fallible version of `gen_indexed`, see `try_gen`
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        let _ = idx;
+        self.try_gen(r)
+    }
+
+    /// Whether this generator needs a sub-stream derived from the random generator
+    /// of its parent, instead of drawing directly from it
+    ///
+    /// The sub-streams keep the values of a generator independent from the number
+    /// of values drawn by its siblings, which matters for the generators drawing a
+    /// variable number of values. The simple leaf generators (`num`, `range`,
+    /// `constant`, `one_of`) draw a fixed number of values and opt out, saving the
+    /// derivation in the tests and in the products of generators. The wrappers of
+    /// a generator (e.g. `map`, `such_that`, `with_examples`) follow the generator
+    /// they wrap.
+    fn needs_sub_stream(&self) -> bool {
+        true
+    }
+
+    /// Generate the examples in order for the first iterations of a test, before
+    /// falling back to this generator
+    ///
+    /// This makes sure that the critical inputs are always tested first, and
+    /// deterministically. The random inputs of the next iterations are the same
+    /// as without the examples.
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{num, range, vector}};
+    ///
+    /// let vectors = vector(range(0..16), num::<u8>()).with_examples(vec![vec![], vec![0xff; 64]]);
+    /// let numbers = num::<u32>().with_examples(vec![0, 1, u32::MAX]);
+    /// ```
+    fn with_examples(self, examples: Vec<Self::Item>) -> WithExamples<Self>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        WithExamples {
+            generator: self,
+            examples,
+        }
+    }
+
     /// Map the output of a generator through a function
     ///
     /// ```
@@ -25,6 +138,47 @@ pub trait Generator {
         Map { generator: self, f }
     }
 
+    /// Map the output of a generator through a function that also uses randomness
+    ///
+    /// The function receives a pseudo random generator derived from the one used to
+    /// generate the item. This is the escape hatch between `map` and writing a full
+    /// custom `Generator`, for post-processing that needs some randomness:
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{num, range, vector}};
+    ///
+    /// // corrupt one random byte of a non empty vector
+    /// let corrupted = vector(range(1usize..64), num::<u8>()).map_rng(|mut v, r| {
+    ///     let idx = r.num_range(0, v.len() - 1);
+    ///     v[idx] ^= 0xff;
+    ///     v
+    /// });
+    /// ```
+    fn map_rng<O, F>(self, f: F) -> MapRng<Self, F>
+    where
+        Self: Sized,
+        F: Fn(Self::Item, &mut R) -> O,
+    {
+        MapRng { generator: self, f }
+    }
+
+    /// Map the output of a generator through a function that also uses randomness,
+    /// this is an alias of `map_rng`
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::range};
+    ///
+    /// // perturb a base value by a random delta
+    /// let perturbed = range(100i32..200).map_with_rng(|base, r| base - 5 + r.num_range(0, 10));
+    /// ```
+    fn map_with_rng<O, F>(self, f: F) -> MapRng<Self, F>
+    where
+        Self: Sized,
+        F: Fn(Self::Item, &mut R) -> O,
+    {
+        self.map_rng(f)
+    }
+
     /// Filter the generated items such that only the item
     /// that matches the predicate 'f' are returned.
     ///
@@ -36,6 +190,9 @@ pub trait Generator {
     /// less than half the generated value, then it should
     /// probably be refined at the source generator.
     ///
+    /// When no generated item matches the predicate after a number of retries,
+    /// the generation is aborted, and the test iteration is discarded.
+    ///
     /// ```
     /// use smoke::{Generator, generator::range};
     /// // u32 number between 1 and 1000 that are odd only
@@ -44,12 +201,102 @@ pub trait Generator {
     fn such_that<F>(self, f: F) -> SuchThat<Self, F>
     where
         Self: Sized,
-        F: Fn(Self::Item) -> bool + Clone,
+        F: Fn(&Self::Item) -> bool + Clone,
     {
         SuchThat {
             retry: 1000,
             generator: self,
             f,
+            stats: None,
+        }
+    }
+
+    /// Similar to `such_that`, also returning the statistics of the predicate
+    /// over all the generations, to check its rejection rate
+    ///
+    /// ```
+    /// use smoke::{Generator, R, Seed, generator::range};
+    ///
+    /// let (not_multiple_of_4, stats) = range(1u32..1000).such_that_counted(|n| n % 4 != 0);
+    /// let mut r = R::from_seed(Seed::from(1));
+    /// for _ in 0..10_000 {
+    ///     not_multiple_of_4.gen(&mut r);
+    /// }
+    /// assert_eq!(stats.accepted(), 10_000);
+    /// assert!(stats.rejection_rate() < 0.5);
+    /// ```
+    fn such_that_counted<F>(self, f: F) -> (SuchThat<Self, F>, SuchThatStats)
+    where
+        Self: Sized,
+        F: Fn(&Self::Item) -> bool + Clone,
+    {
+        let stats = SuchThatStats::default();
+        let mut such_that = self.such_that(f);
+        such_that.stats = Some(stats.clone());
+        (such_that, stats)
+    }
+
+    /// Filter and map the generated items at once, such that only the items
+    /// for which `f` returns a value are kept, and mapped to this value
+    ///
+    /// This is useful when the predicate and the transformation are the same
+    /// partial function, and has the same retry and discard behavior as `such_that`.
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{range, string::string}};
+    /// // strings of digits that fit into a u16, in their parsed form
+    /// let parsed = string(range(1..6), range('0'..'9')).such_that_map(|s| s.parse::<u16>().ok());
+    /// ```
+    fn such_that_map<O, F>(self, f: F) -> SuchThatMap<Self, F>
+    where
+        Self: Sized,
+        F: Fn(Self::Item) -> Option<O> + Clone,
+    {
+        SuchThatMap {
+            retry: 1000,
+            generator: self,
+            f,
+        }
+    }
+
+    /// Combine this generator with another one into a generator of flat tuples
+    ///
+    /// Contrary to `and`, chaining `and_flat` extends the tuples instead of nesting
+    /// them, up to tuples of 6 elements, and generates the same values as the
+    /// `tupleN` generators:
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{num, range, bool_gen}};
+    ///
+    /// let triples = num::<u8>().and_flat(range(0u16..10)).and_flat(bool_gen());
+    /// let (_a, _b, _c): (u8, u16, bool) = triples.gen(&mut smoke::R::from_seed(smoke::Seed::from(1)));
+    /// ```
+    fn and_flat<G>(self, other: G) -> Tuple2<Self, G>
+    where
+        Self: Sized,
+    {
+        tuple2(self, other)
+    }
+
+    /// Filter out the generated items equal to one of the `excluded` values
+    ///
+    /// This has the same retry and discard behavior as `such_that`, the generation
+    /// being aborted when only excluded values are generated.
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::range};
+    /// // any port but the reserved ones
+    /// let ports = range(1u16..1024).excluding(&[22, 80, 443]);
+    /// ```
+    fn excluding(self, excluded: &[Self::Item]) -> Excluding<Self>
+    where
+        Self: Sized,
+        Self::Item: PartialEq + Clone,
+    {
+        Excluding {
+            retry: 1000,
+            generator: self,
+            excluded: excluded.to_vec(),
         }
     }
 
@@ -74,6 +321,75 @@ pub trait Generator {
         }
     }
 
+    /// Zip two generators into one generating a tuple of both items, this is an alias of `and`
+    ///
+    /// For higher arities, `tuple3` up to `tuple6` generates flat tuples instead
+    /// of nesting the pairs.
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{num, range}};
+    ///
+    /// let generator = num::<u32>().zip(range(0u8..10));
+    /// ```
+    fn zip<G>(self, other: G) -> And<Self, G>
+    where
+        Self: Sized,
+    {
+        self.and(other)
+    }
+
+    /// Combine two generators with a mapping function on both generated items,
+    /// this is `product2` in method form
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{num, range}};
+    ///
+    /// let generator = num::<u32>().map2(range(0u32..10), |a, b| a.wrapping_mul(b));
+    /// ```
+    fn map2<G, O, F>(self, other: G, f: F) -> Product2<Self, G, F>
+    where
+        Self: Sized,
+        G: Generator,
+        F: Fn(Self::Item, G::Item) -> O + Clone,
+    {
+        product2(self, other, f)
+    }
+
+    /// Flatten a generator of generators, generating the items of the generated generator
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{one_of, range}};
+    ///
+    /// // either a small or a large number
+    /// let generator = one_of(&[range(0u32..10), range(1_000u32..1_010)]).flatten();
+    /// ```
+    fn flatten(self) -> Flatten<Self>
+    where
+        Self: Sized,
+        Self::Item: Generator,
+    {
+        Flatten { generator: self }
+    }
+
+    /// Change the size parameter with `f` when generating with this generator
+    ///
+    /// This allows to grow or shrink the collections generated by this generator,
+    /// relative to the size parameter of the run. When the size is unbounded,
+    /// this has no effect.
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::{num, range, vector}};
+    ///
+    /// let small_vectors = vector(range(0usize..100), num::<u8>()).scale(|size| size / 4);
+    /// ```
+    fn scale<F>(self, f: F) -> Scale<Self, F>
+    where
+        Self: Sized,
+        F: Fn(usize) -> usize,
+    {
+        Scale { generator: self, f }
+    }
+
     /// This generator or another one.
     ///
     /// It's not recommended to use this combinator to chain more than
@@ -93,6 +409,24 @@ pub trait Generator {
         }
     }
 
+    /// Unwrap the results of a generator, the errors failing the test iteration
+    ///
+    /// The test running the generator reports the `Debug` representation of the error
+    /// as a failure, instead of aborting the whole run with a panic.
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::range};
+    ///
+    /// let digits = range(0u8..9).map(|d| char::from_digit(d as u32, 10).ok_or(d)).assert_ok();
+    /// ```
+    fn assert_ok<T, E>(self) -> AssertOk<Self>
+    where
+        Self: Sized + Generator<Item = Result<T, E>>,
+        E: std::fmt::Debug,
+    {
+        AssertOk { generator: self }
+    }
+
     /// Box a generator into a monomorphic fixed-sized type, that is easier to handle
     fn into_boxed(self) -> BoxGenerator<Self::Item>
     where
@@ -100,6 +434,184 @@ pub trait Generator {
     {
         BoxGenerator(Box::new(self))
     }
+
+    /// Share a generator behind a reference counted pointer, so that cheap clones of
+    /// it can be used by many properties
+    fn into_shared(self) -> ArcGenerator<Self::Item>
+    where
+        Self: Sized + 'static,
+    {
+        ArcGenerator(Arc::new(self))
+    }
+
+    /// Iterate over the items generated from `seed`
+    ///
+    /// The n-th item is the input of the n-th iteration of a test with this seed
+    /// (without size parameter), so that the corpora built from a seed match the
+    /// inputs tested with it.
+    ///
+    /// ```
+    /// use smoke::{Generator, Seed, generator::num};
+    ///
+    /// let corpus: Vec<u32> = num::<u32>().sample_iter(Seed::from(1)).take(100).collect();
+    /// ```
+    fn sample_iter(self, seed: Seed) -> SampleIter<Self>
+    where
+        Self: Sized,
+    {
+        SampleIter {
+            generator: self,
+            r: R::from_seed(seed),
+            idx: 0,
+        }
+    }
+
+    /// Iterate over the `n` first items generated from `seed`, as `sample_iter`
+    ///
+    /// Contrary to `sample_iter(seed).take(n)`, the iterator has an exact size, so
+    /// that collecting it allocates once.
+    ///
+    /// ```
+    /// use smoke::{Generator, Seed, generator::num};
+    ///
+    /// let samples = num::<u32>().take_samples(Seed::from(1), 100);
+    /// assert_eq!(samples.len(), 100);
+    /// ```
+    fn take_samples(self, seed: Seed, n: usize) -> TakeSamples<Self>
+    where
+        Self: Sized,
+    {
+        TakeSamples {
+            samples: self.sample_iter(seed),
+            remaining: n,
+        }
+    }
+}
+
+/// Unbounded iterator over the items of a generator
+pub struct SampleIter<G> {
+    generator: G,
+    r: R,
+    idx: u64,
+}
+
+impl<G: Generator> Iterator for SampleIter<G> {
+    type Item = G::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the same stream as the iterations of the tests (see `Generator::needs_sub_stream`)
+        let item = if self.generator.needs_sub_stream() {
+            self.generator.gen_indexed(self.idx, &mut self.r.sub())
+        } else {
+            self.generator.gen_indexed(self.idx, &mut self.r)
+        };
+        self.idx += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Iterator over a number of items of a generator
+pub struct TakeSamples<G> {
+    samples: SampleIter<G>,
+    remaining: usize,
+}
+
+impl<G: Generator> Iterator for TakeSamples<G> {
+    type Item = G::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.samples.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<G: Generator> ExactSizeIterator for TakeSamples<G> {}
+
+/// Generator able to generate an Item into an existing value
+///
+/// This is useful for item owning an allocation (e.g. `Vec<T>` or `String`), where
+/// the existing buffer is cleared and refilled, re-using its capacity instead of
+/// allocating a new value on every generation.
+///
+/// For the same random generator state, `gen_into` yields the same value as `gen`
+pub trait GeneratorInPlace: Generator {
+    /// Generate the next item into `out`, overwriting its previous content
+    fn gen_into(&self, r: &mut R, out: &mut Self::Item);
+}
+
+/// Error of a fallible generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenError {
+    /// No item could be generated after all the retries of a filter
+    Exhausted,
+}
+
+impl std::fmt::Display for GenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GenError::Exhausted => write!(f, "generation exhausted"),
+        }
+    }
+}
+
+impl std::error::Error for GenError {}
+
+/// Unwrap a fallible generation, panicking with a `SuchThatRetryFailure` on exhaustion,
+/// so that the test iteration is discarded
+pub(crate) fn unwrap_generation<T>(result: Result<T, GenError>) -> T {
+    match result {
+        Ok(x) => x,
+        Err(GenError::Exhausted) => std::panic::panic_any(SuchThatRetryFailure),
+    }
+}
+
+/// Panic payload of a failed generation, reported as a test failure
+#[derive(Debug, Clone)]
+pub(crate) struct GenerationFailure(pub(crate) String);
+
+/// Generator unwrapping the results of a generator
+#[derive(Clone)]
+pub struct AssertOk<G> {
+    generator: G,
+}
+
+impl<G, T, E> AssertOk<G>
+where
+    G: Generator<Item = Result<T, E>>,
+    E: std::fmt::Debug,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> T {
+        match gen_at(&self.generator, idx, r) {
+            Ok(x) => x,
+            Err(e) => std::panic::panic_any(GenerationFailure(format!("{:?}", e))),
+        }
+    }
+    fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<T, GenError> {
+        match try_gen_at(&self.generator, idx, r)? {
+            Ok(x) => Ok(x),
+            Err(e) => std::panic::panic_any(GenerationFailure(format!("{:?}", e))),
+        }
+    }
+}
+
+impl<G, T, E> Generator for AssertOk<G>
+where
+    G: Generator<Item = Result<T, E>>,
+    E: std::fmt::Debug,
+{
+    type Item = T;
+    indexed_generation!(fallible);
 }
 
 /// A generic generator
@@ -110,11 +622,161 @@ impl<T> Generator for BoxGenerator<T> {
     fn gen(&self, r: &mut R) -> Self::Item {
         self.0.gen(r)
     }
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        self.0.try_gen(r)
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        self.0.gen_indexed(idx, r)
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        self.0.try_gen_indexed(idx, r)
+    }
+    fn needs_sub_stream(&self) -> bool {
+        self.0.needs_sub_stream()
+    }
     fn into_boxed(self) -> BoxGenerator<Self::Item> {
         self
     }
 }
 
+/// A generic generator, shared between its clones
+pub struct ArcGenerator<T>(pub(crate) Arc<dyn Generator<Item = T>>);
+
+impl<T> Clone for ArcGenerator<T> {
+    fn clone(&self) -> Self {
+        ArcGenerator(self.0.clone())
+    }
+}
+
+impl<T> Generator for ArcGenerator<T> {
+    type Item = T;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        self.0.gen(r)
+    }
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        self.0.try_gen(r)
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        self.0.gen_indexed(idx, r)
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        self.0.try_gen_indexed(idx, r)
+    }
+    fn needs_sub_stream(&self) -> bool {
+        self.0.needs_sub_stream()
+    }
+    fn into_shared(self) -> ArcGenerator<Self::Item> {
+        self
+    }
+}
+
+/// A borrowed generator, to reuse a generator in combinators without cloning or boxing it
+impl<G: Generator + ?Sized> Generator for &G {
+    type Item = G::Item;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        (**self).gen(r)
+    }
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        (**self).try_gen(r)
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        (**self).gen_indexed(idx, r)
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        (**self).try_gen_indexed(idx, r)
+    }
+    fn needs_sub_stream(&self) -> bool {
+        (**self).needs_sub_stream()
+    }
+}
+
+/// A boxed generator, including the boxed trait objects `Box<dyn Generator<Item = T>>`
+impl<G: Generator + ?Sized> Generator for Box<G> {
+    type Item = G::Item;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        (**self).gen(r)
+    }
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        (**self).try_gen(r)
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        (**self).gen_indexed(idx, r)
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        (**self).try_gen_indexed(idx, r)
+    }
+    fn needs_sub_stream(&self) -> bool {
+        (**self).needs_sub_stream()
+    }
+}
+
+/// A shared generator, e.g. a large table of values used by several generators
+impl<G: Generator + ?Sized> Generator for Arc<G> {
+    type Item = G::Item;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        (**self).gen(r)
+    }
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        (**self).try_gen(r)
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        (**self).gen_indexed(idx, r)
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        (**self).try_gen_indexed(idx, r)
+    }
+    fn needs_sub_stream(&self) -> bool {
+        (**self).needs_sub_stream()
+    }
+}
+
+/// Generate with `generator`, for the iteration `idx` of a test if known
+pub(crate) fn gen_at<G: Generator + ?Sized>(generator: &G, idx: Option<u64>, r: &mut R) -> G::Item {
+    match idx {
+        None => generator.gen(r),
+        Some(idx) => generator.gen_indexed(idx, r),
+    }
+}
+
+/// Fallible version of `gen_at`
+pub(crate) fn try_gen_at<G: Generator + ?Sized>(
+    generator: &G,
+    idx: Option<u64>,
+    r: &mut R,
+) -> Result<G::Item, GenError> {
+    match idx {
+        None => generator.try_gen(r),
+        Some(idx) => generator.try_gen_indexed(idx, r),
+    }
+}
+
+/// Generate from a sub-stream of `r`, or directly from `r` for the generators not
+/// needing one (see `Generator::needs_sub_stream`)
+pub(crate) fn gen_sub<G: Generator + ?Sized>(
+    generator: &G,
+    idx: Option<u64>,
+    r: &mut R,
+) -> G::Item {
+    if generator.needs_sub_stream() {
+        gen_at(generator, idx, &mut r.sub())
+    } else {
+        gen_at(generator, idx, r)
+    }
+}
+
+/// Fallible version of `gen_sub`
+pub(crate) fn try_gen_sub<G: Generator + ?Sized>(
+    generator: &G,
+    idx: Option<u64>,
+    r: &mut R,
+) -> Result<G::Item, GenError> {
+    if generator.needs_sub_stream() {
+        try_gen_at(generator, idx, &mut r.sub())
+    } else {
+        try_gen_at(generator, idx, r)
+    }
+}
+
 /// A product generator of one and another
 #[derive(Clone)]
 pub struct And<A, B> {
@@ -122,17 +784,26 @@ pub struct And<A, B> {
     gen_b: B,
 }
 
+impl<A: Generator, B: Generator> And<A, B> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> (A::Item, B::Item) {
+        let a = gen_sub(&self.gen_a, idx, r);
+        let b = gen_sub(&self.gen_b, idx, r);
+        (a, b)
+    }
+    fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<(A::Item, B::Item), GenError> {
+        let a = try_gen_sub(&self.gen_a, idx, r)?;
+        let b = try_gen_sub(&self.gen_b, idx, r)?;
+        Ok((a, b))
+    }
+}
+
 impl<A, B, T, U> Generator for And<A, B>
 where
     A: Generator<Item = T>,
     B: Generator<Item = U>,
 {
     type Item = (T, U);
-    fn gen(&self, r: &mut R) -> Self::Item {
-        let a = self.gen_a.gen(&mut r.sub());
-        let b = self.gen_b.gen(&mut r.sub());
-        (a, b)
-    }
+    indexed_generation!(fallible);
 }
 
 /// An alternative generator between one or another
@@ -142,21 +813,29 @@ pub struct Or<A, B> {
     gen_b: B,
 }
 
-impl<A, B, T> Generator for Or<A, B>
+impl<A, B, T> Or<A, B>
 where
     A: Generator<Item = T>,
     B: Generator<Item = T>,
 {
-    type Item = T;
-    fn gen(&self, r: &mut R) -> Self::Item {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> T {
         if r.bool() {
-            self.gen_a.gen(&mut r.sub())
+            gen_at(&self.gen_a, idx, &mut r.sub())
         } else {
-            self.gen_b.gen(&mut r.sub())
+            gen_at(&self.gen_b, idx, &mut r.sub())
         }
     }
 }
 
+impl<A, B, T> Generator for Or<A, B>
+where
+    A: Generator<Item = T>,
+    B: Generator<Item = T>,
+{
+    type Item = T;
+    indexed_generation!();
+}
+
 /// Application of a closure on the generated value
 #[derive(Clone)]
 pub struct Map<G, F> {
@@ -173,6 +852,84 @@ where
         let x = self.generator.gen(r);
         (self.f)(x)
     }
+    fn try_gen(&self, r: &mut R) -> Result<O, GenError> {
+        let x = self.generator.try_gen(r)?;
+        Ok((self.f)(x))
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> O {
+        let x = self.generator.gen_indexed(idx, r);
+        (self.f)(x)
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<O, GenError> {
+        let x = self.generator.try_gen_indexed(idx, r)?;
+        Ok((self.f)(x))
+    }
+    fn needs_sub_stream(&self) -> bool {
+        self.generator.needs_sub_stream()
+    }
+}
+
+/// Generator of fixed examples for the first iterations of a test, then of the
+/// items of another generator
+#[derive(Clone)]
+pub struct WithExamples<G: Generator> {
+    generator: G,
+    examples: Vec<G::Item>,
+}
+
+impl<G: Generator> WithExamples<G> {
+    fn example(&self, idx: u64) -> Option<G::Item>
+    where
+        G::Item: Clone,
+    {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.examples.get(idx))
+            .cloned()
+    }
+
+    /// Draw the item replaced by an example from a generator drawing directly from
+    /// the random generator of its parent, so that the next items are the same as
+    /// without the examples
+    fn skip(&self, idx: u64, r: &mut R) {
+        if !self.generator.needs_sub_stream() {
+            let _ = self.generator.try_gen_indexed(idx, r);
+        }
+    }
+}
+
+impl<G: Generator> Generator for WithExamples<G>
+where
+    G::Item: Clone,
+{
+    type Item = G::Item;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        self.generator.gen(r)
+    }
+    fn try_gen(&self, r: &mut R) -> Result<Self::Item, GenError> {
+        self.generator.try_gen(r)
+    }
+    fn gen_indexed(&self, idx: u64, r: &mut R) -> Self::Item {
+        match self.example(idx) {
+            Some(x) => {
+                self.skip(idx, r);
+                x
+            }
+            None => self.generator.gen_indexed(idx, r),
+        }
+    }
+    fn try_gen_indexed(&self, idx: u64, r: &mut R) -> Result<Self::Item, GenError> {
+        match self.example(idx) {
+            Some(x) => {
+                self.skip(idx, r);
+                Ok(x)
+            }
+            None => self.generator.try_gen_indexed(idx, r),
+        }
+    }
+    fn needs_sub_stream(&self) -> bool {
+        self.generator.needs_sub_stream()
+    }
 }
 
 /// Generator filtering mechanisms, such that the resulting generator,
@@ -182,25 +939,275 @@ pub struct SuchThat<G, F> {
     retry: u32,
     generator: G,
     f: F,
+    stats: Option<SuchThatStats>,
+}
+
+/// Statistics of the predicate of a `such_that` filter, shared with the filter
+/// and its clones, see `Generator::such_that_counted`
+#[derive(Debug, Clone, Default)]
+pub struct SuchThatStats {
+    attempts: Arc<AtomicU64>,
+    accepted: Arc<AtomicU64>,
+}
+
+impl SuchThatStats {
+    fn record(&self, accepted: bool) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        if accepted {
+            self.accepted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of items checked by the predicate
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    /// Number of items accepted by the predicate
+    pub fn accepted(&self) -> u64 {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    /// Ratio of the items rejected by the predicate, or 0 without any attempt
+    pub fn rejection_rate(&self) -> f64 {
+        match self.attempts() {
+            0 => 0.0,
+            attempts => (attempts - self.accepted()) as f64 / attempts as f64,
+        }
+    }
+}
+
+impl<G, F> SuchThat<G, F> {
+    /// Set the number of retries before aborting the generation (1000 by default)
+    pub fn with_retry(mut self, retry: u32) -> Self {
+        self.retry = retry;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct SuchThatRetryFailure;
 
+impl<G: Generator, F> SuchThat<G, F>
+where
+    F: Fn(&G::Item) -> bool + Clone,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> G::Item {
+        unwrap_generation(self.try_generate(idx, r))
+    }
+    /// Only the first attempt is generated for the iteration `idx`, an example
+    /// rejected by the predicate being replaced by random items
+    fn try_generate(&self, mut idx: Option<u64>, r: &mut R) -> Result<G::Item, GenError> {
+        let mut retry = self.retry;
+        loop {
+            let x = try_gen_at(&self.generator, idx.take(), r)?;
+            let accepted = (self.f)(&x);
+            if let Some(stats) = &self.stats {
+                stats.record(accepted);
+            }
+            if accepted {
+                break Ok(x);
+            }
+            if retry == 0 {
+                break Err(GenError::Exhausted);
+            } else {
+                retry -= 1;
+            }
+        }
+    }
+}
+
 impl<G: Generator, F> Generator for SuchThat<G, F>
 where
     F: Fn(&G::Item) -> bool + Clone,
 {
     type Item = G::Item;
-    fn gen(&self, r: &mut R) -> Self::Item {
+    indexed_generation!(fallible);
+
+    fn needs_sub_stream(&self) -> bool {
+        self.generator.needs_sub_stream()
+    }
+}
+
+/// Generator filtering out a list of values
+#[derive(Clone)]
+pub struct Excluding<G: Generator> {
+    retry: u32,
+    generator: G,
+    excluded: Vec<G::Item>,
+}
+
+impl<G: Generator> Excluding<G> {
+    /// Set the number of retries before aborting the generation (1000 by default)
+    pub fn with_retry(mut self, retry: u32) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+impl<G: Generator> Excluding<G>
+where
+    G::Item: PartialEq,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> G::Item {
+        unwrap_generation(self.try_generate(idx, r))
+    }
+    /// Only the first attempt is generated for the iteration `idx`, see `SuchThat`
+    fn try_generate(&self, mut idx: Option<u64>, r: &mut R) -> Result<G::Item, GenError> {
+        let mut retry = self.retry;
+        loop {
+            let x = try_gen_at(&self.generator, idx.take(), r)?;
+            if !self.excluded.contains(&x) {
+                break Ok(x);
+            }
+            if retry == 0 {
+                break Err(GenError::Exhausted);
+            } else {
+                retry -= 1;
+            }
+        }
+    }
+}
+
+impl<G: Generator> Generator for Excluding<G>
+where
+    G::Item: PartialEq,
+{
+    type Item = G::Item;
+    indexed_generation!(fallible);
+}
+
+/// Map generator with access to a pseudo random generator
+#[derive(Clone)]
+pub struct MapRng<G, F> {
+    generator: G,
+    f: F,
+}
+
+impl<O, G: Generator, F> MapRng<G, F>
+where
+    F: Fn(G::Item, &mut R) -> O,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> O {
+        let x = gen_at(&self.generator, idx, r);
+        (self.f)(x, &mut r.sub())
+    }
+    fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<O, GenError> {
+        let x = try_gen_at(&self.generator, idx, r)?;
+        Ok((self.f)(x, &mut r.sub()))
+    }
+}
+
+impl<O, G: Generator, F> Generator for MapRng<G, F>
+where
+    F: Fn(G::Item, &mut R) -> O,
+{
+    type Item = O;
+    indexed_generation!(fallible);
+
+    fn needs_sub_stream(&self) -> bool {
+        self.generator.needs_sub_stream()
+    }
+}
+
+/// Generator of the items of generated generators
+#[derive(Clone)]
+pub struct Flatten<G> {
+    generator: G,
+}
+
+impl<G> Flatten<G>
+where
+    G: Generator,
+    G::Item: Generator,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> <G::Item as Generator>::Item {
+        let inner = gen_at(&self.generator, idx, r);
+        gen_at(&inner, idx, &mut r.sub())
+    }
+}
+
+impl<G> Generator for Flatten<G>
+where
+    G: Generator,
+    G::Item: Generator,
+{
+    type Item = <G::Item as Generator>::Item;
+    indexed_generation!();
+}
+
+/// Generator with a modified size parameter
+#[derive(Clone)]
+pub struct Scale<G, F> {
+    generator: G,
+    f: F,
+}
+
+impl<G: Generator, F> Scale<G, F>
+where
+    F: Fn(usize) -> usize,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> G::Item {
+        let size = r.size();
+        r.set_size(size.map(&self.f));
+        let x = gen_at(&self.generator, idx, r);
+        r.set_size(size);
+        x
+    }
+    fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<G::Item, GenError> {
+        let size = r.size();
+        r.set_size(size.map(&self.f));
+        let x = try_gen_at(&self.generator, idx, r);
+        r.set_size(size);
+        x
+    }
+}
+
+impl<G: Generator, F> Generator for Scale<G, F>
+where
+    F: Fn(usize) -> usize,
+{
+    type Item = G::Item;
+    indexed_generation!(fallible);
+
+    fn needs_sub_stream(&self) -> bool {
+        self.generator.needs_sub_stream()
+    }
+}
+
+/// Generator filtering and mapping mechanisms, such that the resulting generator,
+/// generate the mapped elements of the Item elements where the mapping returns a value.
+#[derive(Clone)]
+pub struct SuchThatMap<G, F> {
+    retry: u32,
+    generator: G,
+    f: F,
+}
+
+impl<G, F> SuchThatMap<G, F> {
+    /// Set the number of retries before aborting the generation (1000 by default)
+    pub fn with_retry(mut self, retry: u32) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+impl<O, G: Generator, F> SuchThatMap<G, F>
+where
+    F: Fn(G::Item) -> Option<O> + Clone,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> O {
+        unwrap_generation(self.try_generate(idx, r))
+    }
+    /// Only the first attempt is generated for the iteration `idx`, see `SuchThat`
+    fn try_generate(&self, mut idx: Option<u64>, r: &mut R) -> Result<O, GenError> {
         let mut retry = self.retry;
         loop {
-            let x = self.generator.gen(r);
-            if (self.f)(&x) {
-                break x;
+            if let Some(x) = (self.f)(try_gen_at(&self.generator, idx.take(), r)?) {
+                break Ok(x);
             }
             if retry == 0 {
-                std::panic::panic_any(SuchThatRetryFailure);
+                break Err(GenError::Exhausted);
             } else {
                 retry -= 1;
             }
@@ -208,6 +1215,14 @@ where
     }
 }
 
+impl<O, G: Generator, F> Generator for SuchThatMap<G, F>
+where
+    F: Fn(G::Item) -> Option<O> + Clone,
+{
+    type Item = O;
+    indexed_generation!(fallible);
+}
+
 /// Constant generator, always return the same value
 #[derive(Clone)]
 pub struct Constant<T>(T);
@@ -217,6 +1232,9 @@ impl<T: Clone> Generator for Constant<T> {
     fn gen(&self, _: &mut R) -> Self::Item {
         self.0.clone()
     }
+    fn needs_sub_stream(&self) -> bool {
+        false
+    }
 }
 
 /// The constant generator: always yield the same value
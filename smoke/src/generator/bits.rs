@@ -0,0 +1,285 @@
+//! integer generators with constrained bit patterns
+
+use super::super::rand::{NumPrimitive, R};
+use super::base::Generator;
+use core::marker::PhantomData;
+
+/// Unsigned integer types whose bits can be manipulated by the bits generators
+pub trait BitPrimitive: NumPrimitive {
+    /// Number of bits of the type
+    const BITS: u32;
+
+    /// Zero extend the bits of the value into a u128
+    fn to_bits(self) -> u128;
+
+    /// Truncate the bits of a u128 into the type
+    fn from_bits(bits: u128) -> Self;
+}
+
+macro_rules! define_BitPrimitive {
+    ($($t:ty),*) => {
+        $(
+            impl BitPrimitive for $t {
+                const BITS: u32 = <$t>::BITS;
+                fn to_bits(self) -> u128 {
+                    self as u128
+                }
+                fn from_bits(bits: u128) -> Self {
+                    bits as $t
+                }
+            }
+        )*
+    };
+}
+
+define_BitPrimitive!(u8, u16, u32, u64, u128, usize);
+
+/// Generate random multiples of a power of two
+#[derive(Debug, Clone, Copy)]
+pub struct Aligned<T> {
+    mask: u128,
+    phantom: PhantomData<T>,
+}
+
+impl<T: BitPrimitive> Generator for Aligned<T> {
+    type Item = T;
+    fn gen(&self, r: &mut R) -> T {
+        T::from_bits(r.num::<T>().to_bits() & self.mask)
+    }
+}
+
+/// Generate values with a number of set bits in a range
+#[derive(Debug, Clone, Copy)]
+pub struct WithPopcount<T> {
+    min: u32,
+    max: u32,
+    phantom: PhantomData<T>,
+}
+
+impl<T: BitPrimitive> Generator for WithPopcount<T> {
+    type Item = T;
+    fn gen(&self, r: &mut R) -> T {
+        let popcount = r.num_range(self.min, self.max);
+        // choose `popcount` distinct positions with a partial Fisher-Yates shuffle,
+        // so that all the values with this number of set bits are equally likely
+        let mut positions: Vec<u32> = (0..T::BITS).collect();
+        let mut bits = 0u128;
+        for i in 0..popcount as usize {
+            let j = r.num_range(i, positions.len() - 1);
+            positions.swap(i, j);
+            bits |= 1 << positions[i];
+        }
+        T::from_bits(bits)
+    }
+}
+
+/// Generate values with fixed bits, and random bits elsewhere
+#[derive(Debug, Clone, Copy)]
+pub struct Masked<T> {
+    mask: T,
+    value: T,
+}
+
+impl<T: BitPrimitive> Generator for Masked<T> {
+    type Item = T;
+    fn gen(&self, r: &mut R) -> T {
+        let mask = self.mask.to_bits();
+        let random = r.num::<T>().to_bits();
+        T::from_bits((self.value.to_bits() & mask) | (random & !mask))
+    }
+}
+
+/// Generate random subsets of the bits set in a mask
+#[derive(Debug, Clone, Copy)]
+pub struct Flags {
+    mask: u32,
+}
+
+impl Generator for Flags {
+    type Item = u32;
+    fn gen(&self, r: &mut R) -> u32 {
+        r.num::<u32>() & self.mask
+    }
+}
+
+/// Generate random multiples of `align`, which must be a power of two
+///
+/// ```
+/// use smoke::generator::bits::aligned;
+///
+/// let page_addresses = aligned::<u64>(4096);
+/// ```
+///
+/// If align isn't a power of two then a runtime error is thrown
+pub fn aligned<T: BitPrimitive>(align: T) -> Aligned<T> {
+    let align = align.to_bits();
+    assert!(align.is_power_of_two());
+    Aligned {
+        mask: !(align - 1),
+        phantom: PhantomData,
+    }
+}
+
+/// Generate values where the number of set bits is in `popcount` (both included,
+/// as for `range`)
+///
+/// The number of set bits is uniformly chosen in the range, then the value is uniformly
+/// chosen among the values with this number of set bits.
+///
+/// ```
+/// use smoke::generator::bits::with_popcount;
+///
+/// // sparse bitsets
+/// let sparse = with_popcount::<u64>(0..3);
+/// ```
+///
+/// If the range is empty or goes beyond the number of bits of T then a runtime error is thrown
+pub fn with_popcount<T: BitPrimitive>(popcount: std::ops::Range<u32>) -> WithPopcount<T> {
+    assert!(popcount.start <= popcount.end && popcount.end <= T::BITS);
+    WithPopcount {
+        min: popcount.start,
+        max: popcount.end,
+        phantom: PhantomData,
+    }
+}
+
+/// Generate values with the bits of `value` where `mask` is set, and random bits where
+/// `mask` is not set
+///
+/// ```
+/// use smoke::generator::bits::masked;
+///
+/// // random flags, with the high nibble always 0xA
+/// let flags = masked(0xf0u8, 0xa0);
+/// ```
+pub fn masked<T: BitPrimitive>(mask: T, value: T) -> Masked<T> {
+    Masked { mask, value }
+}
+
+/// Generate random combinations of the flags set in `mask`, each flag being set
+/// half of the time
+///
+/// Bits not set in the mask are never set, so no invalid flag is generated.
+///
+/// ```
+/// use smoke::generator::flags;
+///
+/// const READ: u32 = 0x1;
+/// const WRITE: u32 = 0x2;
+/// const EXEC: u32 = 0x4;
+///
+/// let permissions = flags(READ | WRITE | EXEC);
+/// ```
+pub fn flags(mask: u32) -> Flags {
+    Flags { mask }
+}
+
+/// Generate random combinations of the flags in the slice, as for `flags` with
+/// the union of the flags as mask
+///
+/// ```
+/// use smoke::generator::flags_from;
+///
+/// #[derive(Clone, Copy)]
+/// enum Permission {
+///     Read = 0x1,
+///     Write = 0x2,
+///     Exec = 0x4,
+/// }
+///
+/// impl From<Permission> for u32 {
+///     fn from(p: Permission) -> u32 {
+///         p as u32
+///     }
+/// }
+///
+/// let permissions = flags_from(&[Permission::Read, Permission::Write, Permission::Exec]);
+/// ```
+pub fn flags_from<F: Copy + Into<u32>>(flags: &[F]) -> Flags {
+    Flags {
+        mask: flags.iter().fold(0, |mask, flag| mask | (*flag).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{equal, less_equal};
+    use crate::{forall, Context, Property, Testable};
+
+    fn context() -> Context {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(2000);
+        ctx
+    }
+
+    #[test]
+    fn aligned_multiples() {
+        let results = forall(aligned::<u64>(64))
+            .ensure(|n| equal(n % 64, 0))
+            .test(&context());
+        assert_eq!(results.nb_failed, 0);
+        let results = forall(aligned::<u8>(1))
+            .ensure(|_| equal(0, 0))
+            .test(&context());
+        assert_eq!(results.nb_failed, 0);
+    }
+
+    #[test]
+    fn popcount_bounds() {
+        let results = forall(with_popcount::<u32>(3..5))
+            .ensure(|n| less_equal(3, n.count_ones()).and(less_equal(n.count_ones(), 5)))
+            .test(&context());
+        assert_eq!(results.nb_failed, 0);
+        let results = forall(with_popcount::<u128>(128..128))
+            .ensure(|n| equal(n, u128::MAX))
+            .test(&context());
+        assert_eq!(results.nb_failed, 0);
+    }
+
+    #[test]
+    fn popcount_uniform_positions() {
+        let mut r = R::from_seed(crate::Seed::from(0xb175));
+        let gen = with_popcount::<u8>(1..1);
+        let mut counts = [0; 8];
+        for _ in 0..8000 {
+            counts[gen.gen(&mut r).trailing_zeros() as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|c| (800..1200).contains(c)),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn masked_fixed_bits() {
+        let results = forall(masked(0xff00_00ffu32, 0x1234_5678))
+            .ensure(|n| equal(n & 0xff00_00ff, 0x1200_0078))
+            .test(&context());
+        assert_eq!(results.nb_failed, 0);
+    }
+
+    #[test]
+    fn flags_subset() {
+        let results = forall(flags(0x8000_1035))
+            .ensure(|n| equal(n & !0x8000_1035, 0))
+            .test(&context());
+        assert_eq!(results.nb_failed, 0);
+        let results = forall(flags(0)).ensure(|n| equal(n, 0)).test(&context());
+        assert_eq!(results.nb_failed, 0);
+    }
+
+    #[test]
+    fn flags_from_subset() {
+        let mut r = R::from_seed(crate::Seed::from(0xf1a9));
+        let gen = flags_from(&[0x1u32, 0x4, 0x40]);
+        let mut seen = 0;
+        for _ in 0..1000 {
+            let n = gen.gen(&mut r);
+            assert_eq!(n & !0x45, 0);
+            seen |= n;
+        }
+        assert_eq!(seen, 0x45);
+    }
+}
@@ -3,6 +3,7 @@
 use super::super::rand::R;
 use super::base::Generator;
 use super::numerical::range;
+use super::unicode_category;
 
 /// Generate ASCII char
 #[derive(Clone, Copy)]
@@ -30,6 +31,8 @@ pub struct DigitsChar;
 impl Generator for DigitsChar {
     type Item = char;
 
+    // generator ranges are inclusive of their end
+    #[allow(clippy::almost_complete_range)]
     fn gen(&self, r: &mut R) -> Self::Item {
         range('0'..'9').gen(r)
     }
@@ -39,3 +42,232 @@ impl Generator for DigitsChar {
 pub fn digits() -> DigitsChar {
     DigitsChar
 }
+
+/// Unicode general category (major class only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeCategory {
+    /// Letters (Lu, Ll, Lt, Lm, Lo)
+    Letter,
+    /// Combining marks (Mn, Mc, Me)
+    Mark,
+    /// Numbers (Nd, Nl, No)
+    Number,
+    /// Punctuations (Pc, Pd, Ps, Pe, Pi, Pf, Po)
+    Punctuation,
+    /// Symbols (Sm, Sc, Sk, So)
+    Symbol,
+    /// Separators (Zs, Zl, Zp)
+    Separator,
+}
+
+impl UnicodeCategory {
+    fn ranges(self) -> &'static [(u32, u32)] {
+        match self {
+            UnicodeCategory::Letter => unicode_category::LETTER,
+            UnicodeCategory::Mark => unicode_category::MARK,
+            UnicodeCategory::Number => unicode_category::NUMBER,
+            UnicodeCategory::Punctuation => unicode_category::PUNCTUATION,
+            UnicodeCategory::Symbol => unicode_category::SYMBOL,
+            UnicodeCategory::Separator => unicode_category::SEPARATOR,
+        }
+    }
+
+    /// Check if a char belongs to this category
+    pub fn contains(self, c: char) -> bool {
+        let c = c as u32;
+        self.ranges()
+            .binary_search_by(|&(start, end)| {
+                if end < c {
+                    std::cmp::Ordering::Less
+                } else if start > c {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+}
+
+/// Generate char of a specific unicode category
+#[derive(Clone, Copy)]
+pub struct CategoryChar {
+    ranges: &'static [(u32, u32)],
+    nb_codepoints: u32,
+}
+
+impl Generator for CategoryChar {
+    type Item = char;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        self.nth(r.num_range(0, self.nb_codepoints - 1))
+    }
+}
+
+impl CategoryChar {
+    /// The nth code point of the category
+    fn nth(&self, mut n: u32) -> char {
+        for (start, end) in self.ranges {
+            let len = end - start + 1;
+            if n < len {
+                return std::char::from_u32(start + n).unwrap();
+            }
+            n -= len
+        }
+        unreachable!()
+    }
+}
+
+/// generate char uniformly amongst all the assigned code points of a unicode category
+///
+/// ```
+/// use smoke::generator::char::{category, UnicodeCategory};
+///
+/// let letters = category(UnicodeCategory::Letter);
+/// ```
+pub fn category(category: UnicodeCategory) -> CategoryChar {
+    let ranges = category.ranges();
+    let nb_codepoints = ranges.iter().map(|(start, end)| end - start + 1).sum();
+    CategoryChar {
+        ranges,
+        nb_codepoints,
+    }
+}
+
+/// Categories of the printable characters, with the combining marks last
+const PRINTABLE_CATEGORIES: [UnicodeCategory; 6] = [
+    UnicodeCategory::Letter,
+    UnicodeCategory::Number,
+    UnicodeCategory::Punctuation,
+    UnicodeCategory::Symbol,
+    UnicodeCategory::Separator,
+    UnicodeCategory::Mark,
+];
+
+/// Generate printable char, see `printable`
+#[derive(Clone)]
+pub struct PrintableChar {
+    categories: Vec<CategoryChar>,
+    nb_codepoints: u32,
+}
+
+impl PrintableChar {
+    fn new(with_combining: bool) -> Self {
+        let nb_categories = if with_combining {
+            PRINTABLE_CATEGORIES.len()
+        } else {
+            PRINTABLE_CATEGORIES.len() - 1
+        };
+        let categories: Vec<CategoryChar> = PRINTABLE_CATEGORIES[..nb_categories]
+            .iter()
+            .map(|c| category(*c))
+            .collect();
+        let nb_codepoints = categories.iter().map(|c| c.nb_codepoints).sum();
+        PrintableChar {
+            categories,
+            nb_codepoints,
+        }
+    }
+
+    /// Also generate the combining marks (e.g. U+0301 COMBINING ACUTE ACCENT)
+    pub fn with_combining(self) -> Self {
+        Self::new(true)
+    }
+}
+
+impl Generator for PrintableChar {
+    type Item = char;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let mut n = r.num_range(0, self.nb_codepoints - 1);
+        for category in self.categories.iter() {
+            if n < category.nb_codepoints {
+                return category.nth(n);
+            }
+            n -= category.nb_codepoints
+        }
+        unreachable!()
+    }
+}
+
+/// generate char uniformly amongst the assigned code points of the whole unicode
+/// range, excluding the control and format characters, the surrogates, the private
+/// use characters and the combining marks (unless `with_combining` is used)
+///
+/// ```
+/// use smoke::generator::{char::printable, string::string, range};
+///
+/// let i18n_strings = string(range(0..32), printable());
+/// let with_accents = printable().with_combining();
+/// ```
+pub fn printable() -> PrintableChar {
+    PrintableChar::new(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seed;
+
+    fn samples(cat: UnicodeCategory) -> Vec<char> {
+        let mut r = R::from_seed(Seed::from(0x0123_4567));
+        let gen = category(cat);
+        (0..2000).map(|_| gen.gen(&mut r)).collect()
+    }
+
+    #[test]
+    fn category_membership() {
+        let all = [
+            UnicodeCategory::Letter,
+            UnicodeCategory::Mark,
+            UnicodeCategory::Number,
+            UnicodeCategory::Punctuation,
+            UnicodeCategory::Symbol,
+            UnicodeCategory::Separator,
+        ];
+        for cat in all.iter() {
+            for c in samples(*cat) {
+                assert!(cat.contains(c), "{:?} not in {:?}", c, cat);
+                for other in all.iter().filter(|o| *o != cat) {
+                    assert!(!other.contains(c), "{:?} also in {:?}", c, other);
+                }
+                assert!(!c.is_control());
+            }
+        }
+    }
+
+    #[test]
+    fn printable_chars() {
+        let mut r = R::from_seed(Seed::from(0x0123_4567));
+        let chars: Vec<char> = (0..5000).map(|_| printable().gen(&mut r)).collect();
+        for c in chars.iter() {
+            assert!(!c.is_control(), "{:?}", c);
+            assert!(!UnicodeCategory::Mark.contains(*c), "{:?}", c);
+        }
+        // most of the assigned code points are beyond the BMP
+        assert!(chars.iter().any(|c| c.len_utf8() == 4));
+
+        let gen = printable().with_combining();
+        let chars: Vec<char> = (0..5000).map(|_| gen.gen(&mut r)).collect();
+        assert!(chars.iter().all(|c| !c.is_control()));
+        assert!(chars.iter().any(|c| UnicodeCategory::Mark.contains(*c)));
+    }
+
+    #[test]
+    fn category_std_classes() {
+        assert!(samples(UnicodeCategory::Letter)
+            .iter()
+            .all(|c| c.is_alphabetic()));
+        assert!(samples(UnicodeCategory::Number)
+            .iter()
+            .all(|c| c.is_numeric()));
+        assert!(samples(UnicodeCategory::Separator)
+            .iter()
+            .all(|c| c.is_whitespace()));
+        for cat in [UnicodeCategory::Punctuation, UnicodeCategory::Symbol].iter() {
+            assert!(samples(*cat)
+                .iter()
+                .all(|c| !c.is_numeric() && !c.is_whitespace()));
+        }
+    }
+}
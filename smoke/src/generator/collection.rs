@@ -1,7 +1,8 @@
 use super::super::rand::R;
-use super::base::Generator;
-use std::mem::MaybeUninit;
-use std::ptr;
+use super::base::{
+    gen_at, indexed_generation, try_gen_at, BoxGenerator, GenError, Generator, GeneratorInPlace,
+};
+use std::collections::BTreeMap;
 
 /// A generator of array of constant length N where elements are defined by a generator
 pub struct Array<G, const N: usize> {
@@ -14,27 +15,25 @@ impl<G: Generator, const N: usize> Array<G, N> {
     }
 }
 
-impl<T, G, const N: usize> Generator for Array<G, N>
+impl<T, G, const N: usize> Array<G, N>
 where
     G: Generator<Item = T>,
 {
-    type Item = [T; N];
-    fn gen<'a>(&self, r: &mut R) -> Self::Item {
-        let mut items: [MaybeUninit<T>; N] = unsafe { MaybeUninit::uninit().assume_init() };
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> [T; N] {
         let mut sub_r = r.sub();
-        for elem in &mut items[..] {
-            let cell: T = (self.gen).gen(&mut sub_r);
-            unsafe { ptr::write(elem.as_mut_ptr(), cell) }
-        }
-
-        // https://github.com/rust-lang/rust/issues/61956
-        let ptr = &mut items as *mut _ as *mut [T; N];
-        let res = unsafe { ptr.read() };
-        core::mem::forget(items);
-        res
+        // if the element generator panics, the already generated elements are dropped
+        std::array::from_fn(|_| gen_at(&self.gen, idx, &mut sub_r))
     }
 }
 
+impl<T, G, const N: usize> Generator for Array<G, N>
+where
+    G: Generator<Item = T>,
+{
+    type Item = [T; N];
+    indexed_generation!();
+}
+
 /// A generator of vector of T
 #[derive(Clone)]
 pub struct Vector<SZ, G> {
@@ -42,24 +41,192 @@ pub struct Vector<SZ, G> {
     t: G,
 }
 
+impl<T, SZ, G> Vector<SZ, G>
+where
+    SZ: Generator<Item = usize>,
+    G: Generator<Item = T>,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Vec<T> {
+        let sz = gen_at(&self.size, idx, r);
+        let sz = r.bounded_size(sz);
+        let mut v = Vec::with_capacity(sz);
+        let mut sub_r = r.sub();
+        for _ in 0..sz {
+            let cell = gen_at(&self.t, idx, &mut sub_r);
+            v.push(cell)
+        }
+        v
+    }
+    fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<Vec<T>, GenError> {
+        let sz = try_gen_at(&self.size, idx, r)?;
+        let sz = r.bounded_size(sz);
+        let mut v = Vec::with_capacity(sz);
+        let mut sub_r = r.sub();
+        for _ in 0..sz {
+            let cell = try_gen_at(&self.t, idx, &mut sub_r)?;
+            v.push(cell)
+        }
+        Ok(v)
+    }
+}
+
 impl<T, SZ, G> Generator for Vector<SZ, G>
 where
     SZ: Generator<Item = usize>,
     G: Generator<Item = T>,
 {
     type Item = Vec<T>;
-    fn gen(&self, r: &mut R) -> Self::Item {
+    indexed_generation!(fallible);
+}
+
+impl<T, SZ, G> GeneratorInPlace for Vector<SZ, G>
+where
+    SZ: Generator<Item = usize>,
+    G: Generator<Item = T>,
+{
+    fn gen_into(&self, r: &mut R, out: &mut Self::Item) {
         let sz = (self.size).gen(r);
-        let mut v = Vec::with_capacity(sz);
+        let sz = r.bounded_size(sz);
+        out.clear();
+        out.reserve(sz);
         let mut sub_r = r.sub();
         for _ in 0..sz {
             let cell = self.t.gen(&mut sub_r);
-            v.push(cell)
+            out.push(cell)
+        }
+    }
+}
+
+/// A generator of vectors mixing the elements of two generators
+#[derive(Clone)]
+pub struct Interleave<SZ, A, B> {
+    size: SZ,
+    gen_a: A,
+    gen_b: B,
+    randomly: bool,
+}
+
+impl<SZ, A, B> Interleave<SZ, A, B> {
+    /// Choose the generator of each element randomly, instead of alternating them
+    pub fn randomly(mut self) -> Self {
+        self.randomly = true;
+        self
+    }
+}
+
+impl<T, SZ, A, B> Interleave<SZ, A, B>
+where
+    SZ: Generator<Item = usize>,
+    A: Generator<Item = T>,
+    B: Generator<Item = T>,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Vec<T> {
+        let sz = gen_at(&self.size, idx, r);
+        let sz = r.bounded_size(sz);
+        let mut v = Vec::with_capacity(sz);
+        let mut choice_r = r.sub();
+        let mut a_r = r.sub();
+        let mut b_r = r.sub();
+        for i in 0..sz {
+            let from_a = if self.randomly {
+                choice_r.bool()
+            } else {
+                i % 2 == 0
+            };
+            if from_a {
+                v.push(gen_at(&self.gen_a, idx, &mut a_r))
+            } else {
+                v.push(gen_at(&self.gen_b, idx, &mut b_r))
+            }
         }
         v
     }
 }
 
+impl<T, SZ, A, B> Generator for Interleave<SZ, A, B>
+where
+    SZ: Generator<Item = usize>,
+    A: Generator<Item = T>,
+    B: Generator<Item = T>,
+{
+    type Item = Vec<T>;
+    indexed_generation!();
+}
+
+/// A generator of map where keys are defined by a generator of keys list
+#[derive(Clone)]
+pub struct MapFromKeys<KS, G> {
+    keys: KS,
+    values: G,
+}
+
+impl<K, V, KS, G> MapFromKeys<KS, G>
+where
+    K: Ord,
+    KS: Generator<Item = Vec<K>>,
+    G: Generator<Item = V>,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> BTreeMap<K, V> {
+        let keys = gen_at(&self.keys, idx, &mut r.sub());
+        let mut values_r = r.sub();
+        let mut map = BTreeMap::new();
+        for key in keys {
+            map.entry(key)
+                .or_insert_with(|| gen_at(&self.values, idx, &mut values_r));
+        }
+        map
+    }
+}
+
+impl<K, V, KS, G> Generator for MapFromKeys<KS, G>
+where
+    K: Ord,
+    KS: Generator<Item = Vec<K>>,
+    G: Generator<Item = V>,
+{
+    type Item = BTreeMap<K, V>;
+    indexed_generation!();
+}
+
+/// A generator of records, as a list of named fields where some fields are optional
+pub struct Record<V> {
+    required: Vec<(String, BoxGenerator<V>)>,
+    optional: Vec<(String, BoxGenerator<V>)>,
+    optional_ratio: (u32, u32),
+}
+
+impl<V> Record<V> {
+    /// Include each optional field with a probability of `numerator / denominator`
+    /// (1/2 by default)
+    pub fn with_optional_ratio(mut self, numerator: u32, denominator: u32) -> Self {
+        assert!(denominator > 0 && numerator <= denominator);
+        self.optional_ratio = (numerator, denominator);
+        self
+    }
+}
+
+impl<V> Record<V> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Vec<(String, V)> {
+        let mut fields = Vec::with_capacity(self.required.len() + self.optional.len());
+        let mut sub_r = r.sub();
+        for (name, gen) in self.required.iter() {
+            fields.push((name.clone(), gen_at(gen, idx, &mut sub_r)))
+        }
+        let (numerator, denominator) = self.optional_ratio;
+        for (name, gen) in self.optional.iter() {
+            if sub_r.gen_ratio(numerator, denominator) {
+                fields.push((name.clone(), gen_at(gen, idx, &mut sub_r)))
+            }
+        }
+        fields
+    }
+}
+
+impl<V> Generator for Record<V> {
+    type Item = Vec<(String, V)>;
+    indexed_generation!();
+}
+
 /// Create an array of elements where the size is defined of this array is determined by constant generic
 /// and the type of elements by the generator
 ///
@@ -76,6 +243,8 @@ where
 
 /// Create a vector of elements where the size of the vector is determined by the first generator
 /// and the type of elements in the second
+///
+/// The size is bounded by the size parameter of the run, if any (see `SizeStrategy`)
 pub fn vector<SZ, EL, T>(size: SZ, elements: EL) -> Vector<SZ, EL>
 where
     SZ: Generator<Item = usize>,
@@ -83,3 +252,208 @@ where
 {
     Vector { size, t: elements }
 }
+
+/// Create a vector of the size determined by the first generator, alternating the
+/// elements of the two other generators, starting with the first one
+///
+/// Use `randomly` to choose the generator of each element randomly instead:
+///
+/// ```
+/// use smoke::generator::{interleave, range};
+///
+/// let opens_and_closes = interleave(range(0..16), range(0u8..9), range(100u8..109));
+/// let mixed = interleave(range(0..16), range(0u8..9), range(100u8..109)).randomly();
+/// ```
+///
+/// The size is bounded by the size parameter of the run, if any (see `SizeStrategy`)
+pub fn interleave<SZ, A, B, T>(size: SZ, gen_a: A, gen_b: B) -> Interleave<SZ, A, B>
+where
+    SZ: Generator<Item = usize>,
+    A: Generator<Item = T>,
+    B: Generator<Item = T>,
+{
+    Interleave {
+        size,
+        gen_a,
+        gen_b,
+        randomly: false,
+    }
+}
+
+/// Create a map where the keys are the (possibly duplicated) keys of the list generated
+/// by the first generator, and each distinct key gets a value from the second generator
+///
+/// This gives a direct control over the structure of the keys, for example to get
+/// colliding keys:
+///
+/// ```
+/// use smoke::generator::{map_from_keys, num, range, vector};
+///
+/// let map_gen = map_from_keys(vector(range(0..32), range(0u8..8)), num::<u32>());
+/// ```
+pub fn map_from_keys<K, V, KS, G>(keys: KS, values: G) -> MapFromKeys<KS, G>
+where
+    K: Ord,
+    KS: Generator<Item = Vec<K>>,
+    G: Generator<Item = V>,
+{
+    MapFromKeys { keys, values }
+}
+
+/// Create a record generator, where all the `required` fields are present, followed
+/// by a random subset of the `optional` fields, in the order of the lists
+///
+/// ```
+/// use smoke::generator::{record, string::ascii, range, Generator};
+///
+/// let config = record(
+///     vec![("name".to_string(), ascii(range(1..16)).into_boxed())],
+///     vec![("comment".to_string(), ascii(range(0..64)).into_boxed())],
+/// );
+/// ```
+pub fn record<V>(
+    required: Vec<(String, BoxGenerator<V>)>,
+    optional: Vec<(String, BoxGenerator<V>)>,
+) -> Record<V> {
+    Record {
+        required,
+        optional,
+        optional_ratio: (1, 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seed;
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn interleave_sources() {
+        use crate::generator::range;
+
+        let mut r = R::from_seed(Seed::from(0x1e4));
+        let alternating = interleave(range(5..5), range(0u8..9), range(100u8..109));
+        let v = alternating.gen(&mut r);
+        assert_eq!(v.len(), 5);
+        for (i, e) in v.iter().enumerate() {
+            assert_eq!(*e < 100, i % 2 == 0, "{:?}", v);
+        }
+
+        let mixed = interleave(range(0..32), range(0u8..9), range(100u8..109)).randomly();
+        let mut from_a = 0;
+        let mut from_b = 0;
+        for _ in 0..100 {
+            let v = mixed.gen(&mut r);
+            assert!(v.len() <= 32);
+            from_a += v.iter().filter(|e| **e < 100).count();
+            from_b += v.iter().filter(|e| **e >= 100).count();
+        }
+        assert!(from_a > 500 && from_b > 500, "{} {}", from_a, from_b);
+    }
+
+    thread_local! {
+        static CREATED: Cell<usize> = const { Cell::new(0) };
+        static DROPPED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct DropCounted;
+
+    impl Drop for DropCounted {
+        fn drop(&mut self) {
+            DROPPED.with(|d| d.set(d.get() + 1))
+        }
+    }
+
+    /// Generate drop counted elements, panicking on the `panic_at`-th generation
+    struct DropCountedGen {
+        panic_at: usize,
+    }
+
+    impl Generator for DropCountedGen {
+        type Item = DropCounted;
+        fn gen(&self, _: &mut R) -> DropCounted {
+            let created = CREATED.with(|c| c.get());
+            if created == self.panic_at {
+                panic!("element generator failure")
+            }
+            CREATED.with(|c| c.set(created + 1));
+            DropCounted
+        }
+    }
+
+    fn counters() -> (usize, usize) {
+        (CREATED.with(|c| c.get()), DROPPED.with(|d| d.get()))
+    }
+
+    fn reset() {
+        CREATED.with(|c| c.set(0));
+        DROPPED.with(|d| d.set(0));
+    }
+
+    #[test]
+    fn array_drops_on_panic() {
+        let mut r = R::from_seed(Seed::from(1));
+        for panic_at in 0..8 {
+            reset();
+            let gen = array::<_, _, 8>(DropCountedGen { panic_at });
+            assert!(catch_unwind(AssertUnwindSafe(|| gen.gen(&mut r))).is_err());
+            assert_eq!(counters(), (panic_at, panic_at));
+        }
+    }
+
+    #[test]
+    fn map_keys_from_generator() {
+        use crate::generator::{num, range};
+        let keys = vector(range(0..20), range(0u16..10));
+        let map_gen = map_from_keys(&keys, num::<u64>());
+        for seed in 0..100 {
+            let map = map_gen.gen(&mut R::from_seed(Seed::from(seed)));
+            let mut expected = keys.gen(&mut R::from_seed(Seed::from(seed)).sub());
+            expected.sort_unstable();
+            expected.dedup();
+            assert_eq!(map.keys().copied().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn record_fields() {
+        use crate::generator::{constant, num};
+        let record_gen = record(
+            vec![
+                ("a".to_string(), num::<u32>().into_boxed()),
+                ("b".to_string(), constant(1).into_boxed()),
+            ],
+            vec![
+                ("x".to_string(), num::<u32>().into_boxed()),
+                ("y".to_string(), num::<u32>().into_boxed()),
+            ],
+        );
+        let mut r = R::from_seed(Seed::from(0x4ec0));
+        let mut optional_counts = [0; 2];
+        for _ in 0..200 {
+            let fields = record_gen.gen(&mut r);
+            let names: Vec<&str> = fields.iter().map(|(n, _)| n.as_str()).collect();
+            assert_eq!(names[..2], ["a", "b"]);
+            assert!(names[2..].iter().all(|n| *n == "x" || *n == "y"));
+            optional_counts[0] += names.contains(&"x") as usize;
+            optional_counts[1] += names.contains(&"y") as usize;
+        }
+        assert!(optional_counts.iter().all(|c| (60..140).contains(c)));
+
+        let always = record(vec![], vec![("x".to_string(), num::<u8>().into_boxed())])
+            .with_optional_ratio(1, 1);
+        assert_eq!(always.gen(&mut r).len(), 1);
+    }
+
+    #[test]
+    fn array_drops_once() {
+        let mut r = R::from_seed(Seed::from(1));
+        reset();
+        let items = array::<_, _, 8>(DropCountedGen { panic_at: 100 }).gen(&mut r);
+        assert_eq!(counters(), (8, 0));
+        drop(items);
+        assert_eq!(counters(), (8, 8));
+    }
+}
@@ -1,8 +1,11 @@
 //! combinators
 
+use super::super::initonce::InitOnce;
 use super::super::rand::R;
-use super::base::{BoxGenerator, Generator};
-use std::sync::Arc;
+use super::base::{gen_at, gen_sub, indexed_generation, BoxGenerator, Generator, Map};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 /// One of the element from a list
 #[derive(Clone)]
@@ -16,6 +19,80 @@ impl<T: Clone> Generator for OneOf<T> {
         let nb = r.num_range(0, self.data.len() - 1);
         self.data[nb].clone()
     }
+    fn needs_sub_stream(&self) -> bool {
+        false
+    }
+}
+
+/// A reference to one of the element from a borrowed list
+#[derive(Debug, Clone, Copy)]
+pub struct OneOfRef<'a, T> {
+    data: &'a [T],
+}
+
+impl<'a, T> Generator for OneOfRef<'a, T> {
+    type Item = &'a T;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let nb = r.num_range(0, self.data.len() - 1);
+        &self.data[nb]
+    }
+}
+
+/// One of the element from a list of `Copy` elements
+#[derive(Clone)]
+pub struct OneOfCopy<T> {
+    data: Box<[T]>,
+}
+
+impl<T: Copy> Generator for OneOfCopy<T> {
+    type Item = T;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let nb = r.num_range(0, self.data.len() - 1);
+        self.data[nb]
+    }
+}
+
+/// An index between 0 and a length (excluded)
+#[derive(Debug, Clone, Copy)]
+pub struct IndexOf {
+    len: usize,
+}
+
+impl Generator for IndexOf {
+    type Item = usize;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        r.num_range(0, self.len - 1)
+    }
+}
+
+/// One of the element produced by a function on an index
+#[derive(Clone)]
+pub struct OneOfFn<F> {
+    count: usize,
+    f: F,
+}
+
+impl<T, F: Fn(usize) -> T> Generator for OneOfFn<F> {
+    type Item = T;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let nb = r.num_range(0, self.count - 1);
+        (self.f)(nb)
+    }
+}
+
+/// Cycle through all the elements produced by a function on an index
+pub struct ExhaustiveCycle<F> {
+    count: usize,
+    next: AtomicUsize,
+    f: F,
+}
+
+impl<T, F: Fn(usize) -> T> Generator for ExhaustiveCycle<F> {
+    type Item = T;
+    fn gen(&self, _: &mut R) -> Self::Item {
+        let nb = self.next.fetch_add(1, Ordering::Relaxed) % self.count;
+        (self.f)(nb)
+    }
 }
 
 /// Choose one of the generator of T arbitrarily
@@ -35,48 +112,106 @@ impl<T> Choose<T> {
     }
 }
 
-impl<T> Generator for Choose<T> {
-    type Item = T;
-    fn gen(&self, r: &mut R) -> Self::Item {
+impl<T> Choose<T> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> T {
         let nb = r.num_range(0, self.generators.len() - 1);
-        (self.generators[nb]).gen(&mut r.sub())
+        gen_at(&self.generators[nb], idx, &mut r.sub())
     }
 }
 
+impl<T> Generator for Choose<T> {
+    type Item = T;
+    indexed_generation!();
+}
+
 /// A weighted random distribution of multiple generators
 #[derive(Clone)]
 pub struct Frequency<T> {
-    frequencies: Box<[usize]>,
-    generators: Arc<Box<[WeightedBoxGenerator<T>]>>,
+    weights: Box<[usize]>,
+    cumulative: Box<[usize]>,
+    generators: Arc<Box<[BoxGenerator<T>]>>,
 }
 
-/// A Generic Boxed Generator with an associated weight (for frequency)
-type WeightedBoxGenerator<T> = (usize, BoxGenerator<T>);
+/// Cumulative sums of the weights, to pick a generator with a binary search
+///
+/// If any weight is 0 then a runtime error is thrown, listing the indices of the
+/// zero weights, as their generators would never be used
+fn cumulative_weights(weights: &[usize]) -> Box<[usize]> {
+    let zeros: Vec<usize> = (0..weights.len()).filter(|i| weights[*i] == 0).collect();
+    assert!(
+        zeros.is_empty(),
+        "frequency: the weights at the indices {:?} are 0, their generators would never be used",
+        zeros
+    );
+    weights
+        .iter()
+        .scan(0usize, |sum, weight| {
+            *sum = sum
+                .checked_add(*weight)
+                .expect("frequency: the sum of the weights overflows");
+            Some(*sum)
+        })
+        .collect()
+}
 
 impl<T> Frequency<T> {
-    fn new(gens: Vec<(usize, BoxGenerator<T>)>) -> Self {
-        let total: usize = gens.iter().map(|x| x.0).sum();
-        let mut frequencies = Vec::with_capacity(total);
-        for (i, (nb, _)) in gens.iter().enumerate() {
-            // push nb times this generator index
-            for _ in 0..*nb {
-                frequencies.push(i)
-            }
+    pub(crate) fn new(gens: Vec<(usize, BoxGenerator<T>)>) -> Self {
+        let (weights, generators): (Vec<usize>, Vec<BoxGenerator<T>>) = gens.into_iter().unzip();
+        Frequency {
+            cumulative: cumulative_weights(&weights),
+            weights: weights.into(),
+            generators: Arc::new(generators.into()),
         }
+    }
+
+    /// The weights of the generators, in the order of the generators
+    pub fn weights(&self) -> &[usize] {
+        &self.weights
+    }
+
+    /// The same generators, with the new weights, in the order of the generators
+    ///
+    /// The generators are shared with this generator, without cloning them.
+    ///
+    /// ```
+    /// use smoke::generator::{constant, frequency, Generator};
+    ///
+    /// let mostly_a = frequency(vec![
+    ///     (9, Box::new(constant('a'))),
+    ///     (1, Box::new(constant('b'))),
+    /// ]);
+    /// let mostly_b = mostly_a.clone().reweighted(&[1, 9]);
+    /// assert_eq!(mostly_b.weights(), &[1, 9]);
+    /// ```
+    ///
+    /// If the number of weights differs from the number of generators, or if any
+    /// weight is 0 then a runtime error is thrown
+    pub fn reweighted(self, weights: &[usize]) -> Self {
+        assert_eq!(
+            weights.len(),
+            self.generators.len(),
+            "frequency: {} weights for {} generators",
+            weights.len(),
+            self.generators.len()
+        );
         Frequency {
-            frequencies: frequencies.into(),
-            generators: Arc::new(gens.into()),
+            cumulative: cumulative_weights(weights),
+            weights: weights.into(),
+            generators: self.generators,
         }
     }
 }
 
+impl<T> Frequency<T> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> T {
+        let nb = r.cumulative_weighted_index(&self.cumulative);
+        gen_at(&self.generators[nb], idx, &mut r.sub())
+    }
+}
+
 impl<T> Generator for Frequency<T> {
     type Item = T;
-    fn gen(&self, r: &mut R) -> Self::Item {
-        let nb = r.num_range(0, self.frequencies.len() - 1);
-        let idx = self.frequencies[nb];
-        (&self.generators[idx].1).gen(&mut r.sub())
-    }
+    indexed_generation!();
 }
 
 /// Choose randomly from a list of T elements
@@ -87,6 +222,187 @@ pub fn one_of<T: Clone>(slice: &[T]) -> OneOf<T> {
     }
 }
 
+/// Choose randomly from a list of T elements, taking ownership of the list
+///
+/// Contrary to `one_of`, the elements are moved instead of cloned when creating
+/// the generator, which matters for large lists built at runtime (e.g. a word
+/// list loaded from a file). The list itself is kept in place when its capacity
+/// is its length, and is otherwise shrunk to its length:
+///
+/// ```
+/// use smoke::generator::one_of_owned;
+///
+/// let words: Vec<String> = "lorem ipsum dolor sit amet".split(' ').map(String::from).collect();
+/// let word = one_of_owned(words);
+/// ```
+///
+/// The chosen element is still cloned on every generation, see `one_of_ref` to
+/// generate references instead, or `one_of_by_index` to compute the elements on demand.
+///
+/// If the vector is empty then a runtime error is thrown
+pub fn one_of_owned<T: Clone>(vec: Vec<T>) -> OneOf<T> {
+    assert!(!vec.is_empty());
+    OneOf {
+        data: vec.into_boxed_slice(),
+    }
+}
+
+/// Choose randomly a reference to an element of a list of T elements
+///
+/// Contrary to `one_of`, the list is not copied and the chosen element is not
+/// cloned on every generation, which matters for large elements (e.g. big
+/// buffers or parsed documents) that are only read by the property:
+///
+/// ```
+/// use smoke::generator::one_of_ref;
+///
+/// static DOCUMENTS: [&str; 3] = ["{}", "[1, 2, 3]", "{\"a\": null}"];
+/// let documents = one_of_ref(&DOCUMENTS);
+/// ```
+///
+/// If the slice is empty then a runtime error is thrown
+pub fn one_of_ref<T>(slice: &[T]) -> OneOfRef<'_, T> {
+    assert!(!slice.is_empty());
+    OneOfRef { data: slice }
+}
+
+/// Choose randomly among the elements of a sorted slice between `low` and `high`
+/// (both included)
+///
+/// This is similar to `range` for any ordered type, e.g. to generate keys inside
+/// (or outside) the keys range of an existing map:
+///
+/// ```
+/// use smoke::generator::range_of;
+///
+/// let keys = ["apple", "banana", "cherry", "date", "fig"];
+/// let middle_keys = range_of(&keys, "b", "d");
+/// ```
+///
+/// The bounds are searched once, at construction. If the slice isn't sorted, if
+/// `low` is greater than `high` or if no element is in the range, then a runtime
+/// error is thrown
+pub fn range_of<T>(sorted: &[T], low: T, high: T) -> OneOf<T>
+where
+    T: Ord + Clone + std::fmt::Debug,
+{
+    assert!(
+        sorted.windows(2).all(|w| w[0] <= w[1]),
+        "range_of: the slice isn't sorted"
+    );
+    assert!(
+        low <= high,
+        "range_of: the low bound {:?} is greater than the high bound {:?}",
+        low,
+        high
+    );
+    let start = sorted.partition_point(|e| *e < low);
+    let end = sorted.partition_point(|e| *e <= high);
+    assert!(
+        start < end,
+        "range_of: no element of the slice in the range [{:?}, {:?}]",
+        low,
+        high
+    );
+    one_of(&sorted[start..end])
+}
+
+/// Choose randomly from a list of `Copy` elements
+///
+/// ```
+/// use smoke::generator::one_of_copy;
+///
+/// let ports = one_of_copy(&[22u16, 80, 443, 8080]);
+/// ```
+///
+/// If the slice is empty then a runtime error is thrown
+pub fn one_of_copy<T: Copy>(slice: &[T]) -> OneOfCopy<T> {
+    assert!(!slice.is_empty());
+    OneOfCopy { data: slice.into() }
+}
+
+/// Choose randomly an index between 0 and len (excluded)
+///
+/// This is useful to choose from data which isn't `Clone`, by indexing it:
+///
+/// ```
+/// use smoke::{Generator, generator::index_of};
+///
+/// struct Connection { port: u16 }
+///
+/// let connections = vec![Connection { port: 22 }, Connection { port: 80 }];
+/// let chosen = index_of(connections.len()).map(|i| connections[i].port);
+/// ```
+///
+/// If len is 0 then a runtime error is thrown
+pub fn index_of(len: usize) -> IndexOf {
+    assert!(len > 0);
+    IndexOf { len }
+}
+
+/// Choose randomly an index between 0 and count (excluded), and map it through `f`
+///
+/// This is useful for enums which cannot be enumerated, where a new variant only
+/// needs a change of `count` and of the mapping function:
+///
+/// ```
+/// use smoke::generator::one_of_fn;
+///
+/// #[derive(Debug, Clone, Copy)]
+/// enum Direction { North, East, South, West }
+///
+/// let directions = one_of_fn(4, |i| match i {
+///     0 => Direction::North,
+///     1 => Direction::East,
+///     2 => Direction::South,
+///     _ => Direction::West,
+/// });
+/// ```
+///
+/// If count is 0 then a runtime error is thrown
+pub fn one_of_fn<T, F: Fn(usize) -> T>(count: usize, f: F) -> OneOfFn<F> {
+    assert!(count > 0);
+    OneOfFn { count, f }
+}
+
+/// Choose randomly one of `len` elements, computing the chosen element from its index
+/// with `f`, the same as `one_of_fn`
+///
+/// Contrary to `one_of_owned`, the elements don't need to exist beforehand, e.g.
+/// for a large list of generated names:
+///
+/// ```
+/// use smoke::generator::one_of_by_index;
+///
+/// let user = one_of_by_index(100_000, |i| format!("user{:05}", i));
+/// ```
+///
+/// If len is 0 then a runtime error is thrown
+pub fn one_of_by_index<T, F: Fn(usize) -> T>(len: usize, f: F) -> OneOfFn<F> {
+    one_of_fn(len, f)
+}
+
+/// Cycle through the indices between 0 and count (excluded) in order, mapping them through `f`
+///
+/// Each index is produced once every `count` generations, which gives a guaranteed
+/// coverage of small domains, instead of a random one.
+///
+/// The position in the cycle is kept by the generator itself (using interior mutability),
+/// and not derived from the random generator, thus the generated values depend on
+/// the number of previous generations and not on the seed: sharing this generator
+/// between properties, or re-running a property with the same seed, will continue the cycle
+/// where it was left.
+///
+/// If count is 0 then a runtime error is thrown
+pub fn exhaustive_cycle<T, F: Fn(usize) -> T>(count: usize, f: F) -> ExhaustiveCycle<F> {
+    assert!(count > 0);
+    ExhaustiveCycle {
+        count,
+        next: AtomicUsize::new(0),
+        f,
+    }
+}
+
 /// Create a generator from multiple generators
 ///
 /// If the vector is empty then a runtime error is thrown
@@ -101,7 +417,7 @@ pub fn choose<T>(gens: Vec<Box<dyn Generator<Item = T>>>) -> Choose<T> {
 /// which is has 30% (3/(3+7)) to generate from the A generator and
 /// 70% (7/(3+7)) to generate from the B generator.
 ///
-/// If the vector is empty then a runtime error is thrown
+/// If the vector is empty, or if any weight is 0 then a runtime error is thrown
 pub fn frequency<T>(gens: Vec<(usize, Box<dyn Generator<Item = T>>)>) -> Frequency<T> {
     assert!(!gens.is_empty());
     let mut frequencies_gen = Vec::new();
@@ -111,3 +427,902 @@ pub fn frequency<T>(gens: Vec<(usize, Box<dyn Generator<Item = T>>)>) -> Frequen
 
     Frequency::new(frequencies_gen)
 }
+
+/// Builder of a `frequency` generator of the variants of an enum, see `variants`
+pub struct Variants<T> {
+    gens: Vec<(usize, Box<dyn Generator<Item = T>>)>,
+}
+
+impl<T> Variants<T> {
+    /// Add a generator of a variant, chosen with the given weight
+    pub fn weighted<G>(mut self, weight: usize, gen: G) -> Self
+    where
+        G: Generator<Item = T> + 'static,
+    {
+        self.gens.push((weight, Box::new(gen)));
+        self
+    }
+
+    /// The generator choosing between the variants according to their weight
+    ///
+    /// If no variant was added then a runtime error is thrown
+    pub fn build(self) -> Frequency<T> {
+        frequency(self.gens)
+    }
+}
+
+/// Build a generator of an enum from the weighted generators of its variants
+///
+/// ```
+/// use smoke::generator::{num, variants, Generator};
+///
+/// #[derive(Debug, Clone)]
+/// enum Shape {
+///     Circle(u32),
+///     Square(u32),
+/// }
+///
+/// // 3 circles for 1 square
+/// let shapes = variants()
+///     .weighted(3, num::<u32>().map(Shape::Circle))
+///     .weighted(1, num::<u32>().map(Shape::Square))
+///     .build();
+/// ```
+pub fn variants<T>() -> Variants<T> {
+    Variants { gens: Vec::new() }
+}
+
+/// Generator mixing corner values in the items of a generator, see `mostly`
+#[derive(Clone)]
+pub struct Mostly<G: Generator> {
+    main: G,
+    corners: Box<[G::Item]>,
+    corner_ratio: (u32, u32),
+}
+
+impl<G: Generator> Mostly<G>
+where
+    G::Item: Clone,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> G::Item {
+        let (numerator, denominator) = self.corner_ratio;
+        if r.gen_ratio(numerator, denominator) {
+            let nb = r.num_range(0, self.corners.len() - 1);
+            self.corners[nb].clone()
+        } else {
+            gen_sub(&self.main, idx, r)
+        }
+    }
+}
+
+impl<G: Generator> Generator for Mostly<G>
+where
+    G::Item: Clone,
+{
+    type Item = G::Item;
+    indexed_generation!();
+}
+
+/// Generate one of the `corners` values with a probability of
+/// `corner_ratio.0 / corner_ratio.1`, and otherwise an item of `main`
+///
+/// The corner values, such as the limits of a domain or the values known to be
+/// handled specially, are then regularly tested whatever the distribution of
+/// `main`:
+///
+/// ```
+/// use smoke::generator::{mostly, num, Generator};
+///
+/// // the limits of u16 in 1 item out of 8
+/// let lengths = mostly(num::<u16>(), &[0, 1, u16::MAX], (1, 8));
+///
+/// let names = mostly(num::<u32>().map(|n| n.to_string()), &["".to_string()], (1, 20));
+/// ```
+///
+/// If `corners` is empty, if `corner_ratio.1` is 0, or if `corner_ratio.0` is greater
+/// than `corner_ratio.1` then a runtime error is thrown
+pub fn mostly<G: Generator>(main: G, corners: &[G::Item], corner_ratio: (u32, u32)) -> Mostly<G>
+where
+    G::Item: Clone,
+{
+    assert!(!corners.is_empty(), "mostly: no corner values");
+    let (numerator, denominator) = corner_ratio;
+    assert!(
+        denominator > 0 && numerator <= denominator,
+        "mostly: invalid corner ratio {}/{}",
+        numerator,
+        denominator
+    );
+    Mostly {
+        main,
+        corners: corners.into(),
+        corner_ratio,
+    }
+}
+
+/// Generator keeping a state across all its generations
+pub struct Stateful<S, F> {
+    state: Mutex<S>,
+    f: F,
+}
+
+impl<S, T, F> Generator for Stateful<S, F>
+where
+    F: Fn(&mut S, &mut R) -> T,
+{
+    type Item = T;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        // a panic of `f` can't leave the state inconsistent for the next generation
+        // more than any other value of the state, so the poisoning is ignored
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (self.f)(&mut state, r)
+    }
+}
+
+/// Generate values from `f`, which can update a state kept across all the generations,
+/// starting from `init`
+///
+/// This allows generators of values depending on the previously generated values,
+/// for example monotonically increasing timestamps, or avoiding consecutive duplicates:
+///
+/// ```
+/// use smoke::generator::{stateful, range};
+/// use smoke::Generator;
+///
+/// let timestamps = stateful(1_600_000_000u64, |now, r| {
+///     *now += r.num_range(1u64, 3600);
+///     *now
+/// });
+///
+/// let no_consecutive_duplicates = stateful(None, |last, r| {
+///     let x = loop {
+///         let x = range(0u8..3).gen(r);
+///         if *last != Some(x) {
+///             break x;
+///         }
+///     };
+///     *last = Some(x);
+///     x
+/// });
+/// ```
+///
+/// The state is not reset between the iterations or the tests using the generator,
+/// so the generated values depend on all the previous generations and not only on
+/// the seed of the iteration: replaying a failure with its seed only reproduces the
+/// same values when the generator is created anew, and used in the same order.
+pub fn stateful<S, T, F>(init: S, f: F) -> Stateful<S, F>
+where
+    F: Fn(&mut S, &mut R) -> T,
+{
+    Stateful {
+        state: Mutex::new(init),
+        f,
+    }
+}
+
+/// Generator of a value computed once, on the first generation
+pub struct Once<T, F> {
+    value: InitOnce<T>,
+    f: F,
+}
+
+impl<T: Clone, F: Fn() -> T> Generator for Once<T, F> {
+    type Item = T;
+    fn gen(&self, _: &mut R) -> Self::Item {
+        self.value.load(&self.f).clone()
+    }
+}
+
+/// Generate the value computed by `f`, which is only called on the first generation,
+/// the next generations returning clones of this value
+///
+/// This is useful for expensive values that are constant for the whole run, like
+/// a large lookup table used by the other generators of a pipeline:
+///
+/// ```
+/// use smoke::generator::{once, range};
+/// use smoke::Generator;
+/// use std::sync::Arc;
+///
+/// let primes = once(|| {
+///     let primes: Vec<u32> = (2..10_000).filter(|n| (2..*n).all(|d| n % d != 0)).collect();
+///     Arc::new(primes)
+/// });
+/// let prime_products = primes.and(range(0usize..100).and(range(0usize..100)))
+///     .map(|(primes, (a, b))| primes[a] as u64 * primes[b] as u64);
+/// ```
+///
+/// The value doesn't depend on the random generator, so it is intentionally the same
+/// for every iteration and every seed. Wrap expensive values in an `Arc` or `Rc`, so
+/// that the clones are cheap.
+pub fn once<T: Clone, F: Fn() -> T>(f: F) -> Once<T, F> {
+    Once {
+        value: InitOnce::init(),
+        f,
+    }
+}
+
+/// Generator of the results of a fallible function
+#[derive(Clone)]
+pub struct TryFromFn<F> {
+    f: F,
+}
+
+impl<T, E, F> Generator for TryFromFn<F>
+where
+    F: Fn(&mut R) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        (self.f)(r)
+    }
+}
+
+/// Generate the results of the fallible function `f`, typically unwrapped with
+/// `Generator::assert_ok` so that the errors are reported as test failures
+///
+/// ```
+/// use smoke::generator::{try_from_fn, one_of};
+/// use smoke::Generator;
+///
+/// let words = try_from_fn(|r| {
+///     let list = std::fs::read_to_string("/usr/share/dict/words")?;
+///     let words: Vec<String> = list.lines().map(String::from).collect();
+///     Ok::<_, std::io::Error>(one_of(&words).gen(r))
+/// })
+/// .assert_ok();
+/// ```
+pub fn try_from_fn<T, E, F>(f: F) -> TryFromFn<F>
+where
+    F: Fn(&mut R) -> Result<T, E>,
+{
+    TryFromFn { f }
+}
+
+/// Generator of the values of G wrapped into a `Box`
+pub type Boxed<G> = Map<G, fn(<G as Generator>::Item) -> Box<<G as Generator>::Item>>;
+
+/// Generator of the values of G wrapped into a `Rc`
+pub type Rced<G> = Map<G, fn(<G as Generator>::Item) -> Rc<<G as Generator>::Item>>;
+
+/// Generator of the values of G wrapped into an `Arc`
+pub type Arced<G> = Map<G, fn(<G as Generator>::Item) -> Arc<<G as Generator>::Item>>;
+
+/// Wrap the generated values into a `Box`
+///
+/// ```
+/// use smoke::generator::{boxed_value, num};
+///
+/// let boxed_numbers = boxed_value(num::<u64>());
+/// ```
+pub fn boxed_value<G: Generator>(gen: G) -> Boxed<G> {
+    gen.map(Box::new)
+}
+
+/// Wrap the generated values into a `Rc`
+///
+/// ```
+/// use smoke::generator::{rc, string::ascii, range};
+///
+/// let shared_strings = rc(ascii(range(0..16)));
+/// ```
+pub fn rc<G: Generator>(gen: G) -> Rced<G> {
+    gen.map(Rc::new)
+}
+
+/// Wrap the generated values into an `Arc`
+///
+/// ```
+/// use smoke::generator::{arc, num, range, vector};
+///
+/// let shared_vectors = arc(vector(range(0..16), num::<u8>()));
+/// ```
+pub fn arc<G: Generator>(gen: G) -> Arced<G> {
+    gen.map(Arc::new)
+}
+
+/// Generator of the values of G converted into a `Box<T>`
+pub type BoxedItem<G, T> = Map<G, fn(<G as Generator>::Item) -> Box<T>>;
+
+/// Generator of the values of G converted into a `Rc<T>`
+pub type RcItem<G, T> = Map<G, fn(<G as Generator>::Item) -> Rc<T>>;
+
+/// Generator of the values of G converted into an `Arc<T>`
+pub type ArcItem<G, T> = Map<G, fn(<G as Generator>::Item) -> Arc<T>>;
+
+/// Convert the generated values into a `Box<T>`
+///
+/// Unlike `boxed_value`, the values are converted with `From`, so that `T` can
+/// be unsized, e.g. a `Box<str>` from a `String` or a `Box<[T]>` from a `Vec<T>`:
+///
+/// ```
+/// use smoke::generator::{boxed_item, string::ascii, range};
+///
+/// let boxed_strings = boxed_item::<str, _>(ascii(range(0..16)));
+/// ```
+pub fn boxed_item<T: ?Sized, G>(gen: G) -> BoxedItem<G, T>
+where
+    G: Generator,
+    Box<T>: From<G::Item>,
+{
+    gen.map(Box::from)
+}
+
+/// Convert the generated values into a `Rc<T>`
+///
+/// Unlike `rc`, the values are converted with `From`, so that `T` can be unsized:
+///
+/// ```
+/// use smoke::generator::{rc_item, string::ascii, range};
+///
+/// let shared_strings = rc_item::<str, _>(ascii(range(0..16)));
+/// ```
+pub fn rc_item<T: ?Sized, G>(gen: G) -> RcItem<G, T>
+where
+    G: Generator,
+    Rc<T>: From<G::Item>,
+{
+    gen.map(Rc::from)
+}
+
+/// Convert the generated values into an `Arc<T>`
+///
+/// Unlike `arc`, the values are converted with `From`, so that `T` can be unsized,
+/// which is useful for the APIs taking an `Arc<[u8]>`:
+///
+/// ```
+/// use smoke::generator::{arc_item, num, range, vector};
+///
+/// let shared_bytes = arc_item::<[u8], _>(vector(range(0..64), num::<u8>()));
+/// ```
+pub fn arc_item<T: ?Sized, G>(gen: G) -> ArcItem<G, T>
+where
+    G: Generator,
+    Arc<T>: From<G::Item>,
+{
+    gen.map(Arc::from)
+}
+
+/// Dependent generator where the second items depends on what has been generated by the first generator
+pub struct Depends<G, F> {
+    src_gen: G,
+    dst_gen: F,
+}
+
+impl<G1, G2, F> Depends<G1, F>
+where
+    G1: Generator,
+    G2: Generator,
+    F: Fn(&G1::Item) -> G2,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> (G1::Item, G2::Item) {
+        let x = gen_at(&self.src_gen, idx, &mut r.sub());
+        let g2 = (self.dst_gen)(&x);
+        let y = gen_at(&g2, idx, &mut r.sub());
+        (x, y)
+    }
+
+    /// Map the pair of items through a function, without building the intermediate tuple
+    ///
+    /// The items are the same as the ones of the dependent generator.
+    ///
+    /// ```
+    /// use smoke::generator::{depends, range, num};
+    ///
+    /// let sorted_pair = depends(num::<u32>(), |a| range(*a..u32::MAX)).map_into(|a, b| [a, b]);
+    /// ```
+    pub fn map_into<O, M>(self, mapper: M) -> DependsMap<G1, F, M>
+    where
+        M: Fn(G1::Item, G2::Item) -> O,
+    {
+        DependsMap {
+            depends: self,
+            mapper,
+        }
+    }
+}
+
+impl<G1, G2, F> Generator for Depends<G1, F>
+where
+    G1: Generator,
+    G2: Generator,
+    F: Fn(&G1::Item) -> G2,
+{
+    type Item = (G1::Item, G2::Item);
+
+    indexed_generation!();
+}
+
+/// Dependent generator mapping the pair of items through a function, see `Depends::map_into`
+pub struct DependsMap<G, F, M> {
+    depends: Depends<G, F>,
+    mapper: M,
+}
+
+impl<G1, G2, F, M, O> DependsMap<G1, F, M>
+where
+    G1: Generator,
+    G2: Generator,
+    F: Fn(&G1::Item) -> G2,
+    M: Fn(G1::Item, G2::Item) -> O,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> O {
+        let (x, y) = self.depends.generate(idx, r);
+        (self.mapper)(x, y)
+    }
+}
+
+impl<G1, G2, F, M, O> Generator for DependsMap<G1, F, M>
+where
+    G1: Generator,
+    G2: Generator,
+    F: Fn(&G1::Item) -> G2,
+    M: Fn(G1::Item, G2::Item) -> O,
+{
+    type Item = O;
+
+    indexed_generation!();
+}
+
+/// Generate an item, and a second item from the generator built by `f` from the first item
+///
+/// `f` is called for each generated item, to build the generator of the second item.
+///
+/// ```
+/// use smoke::generator::{depends, range, vector, num};
+///
+/// let vec_and_index = depends(vector(range(1..16), num::<u8>()), |v| range(0..v.len() - 1));
+/// ```
+///
+/// The classic length then content pattern, e.g. a length prefixed buffer:
+///
+/// ```
+/// use smoke::generator::{depends, range, vector, constant, num};
+///
+/// let buffers = depends(range(0usize..64), |n| vector(constant(*n), num::<u8>()))
+///     .map_into(|n, content| {
+///         let mut buffer = vec![n as u8];
+///         buffer.extend(content);
+///         buffer
+///     });
+/// ```
+pub fn depends<F, G1, G2>(g1: G1, f: F) -> Depends<G1, F>
+where
+    G1: Generator,
+    G2: Generator,
+    F: Fn(&G1::Item) -> G2,
+{
+    Depends {
+        src_gen: g1,
+        dst_gen: f,
+    }
+}
+
+macro_rules! generate_choose {
+    ($name:ident, $fct_name:ident, $nb:expr, $(($idx:tt, $type_name:ident, $type_param:ident),)*) => {
+        #[doc = concat!(stringify!($name), " generator type, choose arbitrarily between ", stringify!($nb), " generators of the same item")]
+        #[derive(Clone)]
+        pub struct $name<$($type_param),*> {
+            $($type_name : $type_param),*
+        }
+
+        impl<T, $($type_param),*> $name<$($type_param),*>
+            where $($type_param: Generator<Item = T>,)*
+        {
+            fn generate(&self, idx: Option<u64>, r: &mut R) -> T {
+                let nb = r.num_range(0, $nb - 1);
+                match nb {
+                    $($idx => gen_at(&self.$type_name, idx, &mut r.sub()),)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        impl<T, $($type_param),*> Generator for $name<$($type_param),*>
+            where $($type_param: Generator<Item = T>,)*
+        {
+            type Item = T;
+
+            indexed_generation!();
+        }
+
+        #[doc = concat!("Create a generator choosing arbitrarily between ", stringify!($nb), " generators")]
+        #[doc = ""]
+        #[doc = "This is the same as `choose`, without boxing the generators"]
+        pub fn $fct_name<T, $($type_param),*>($($type_name: $type_param),*) -> $name<$($type_param),*>
+            where $($type_param: Generator<Item = T>,)*
+        {
+            $name { $($type_name),* }
+        }
+    };
+}
+
+generate_choose! {Choose2, choose2, 2, (0, a, A), (1, b, B),}
+generate_choose! {Choose3, choose3, 3, (0, a, A), (1, b, B), (2, c, C),}
+generate_choose! {Choose4, choose4, 4, (0, a, A), (1, b, B), (2, c, C), (3, d, D),}
+generate_choose! {Choose5, choose5, 5, (0, a, A), (1, b, B), (2, c, C), (3, d, D), (4, e, E),}
+generate_choose! {Choose6, choose6, 6, (0, a, A), (1, b, B), (2, c, C), (3, d, D), (4, e, E), (5, f, F),}
+
+macro_rules! generate_frequency {
+    ($name:ident, $fct_name:ident, $nb:expr, $(($type_name:ident, $type_param:ident),)*) => {
+        #[doc = concat!(stringify!($name), " generator type, a weighted random distribution of ", stringify!($nb), " generators of the same item")]
+        #[derive(Clone)]
+        pub struct $name<$($type_param),*> {
+            total: usize,
+            $($type_name : (usize, $type_param)),*
+        }
+
+        impl<T, $($type_param),*> $name<$($type_param),*>
+            where $($type_param: Generator<Item = T>,)*
+        {
+            fn generate(&self, idx: Option<u64>, r: &mut R) -> T {
+                let mut nb = r.num_range(0, self.total - 1);
+                $(
+                    if nb < self.$type_name.0 {
+                        return gen_at(&self.$type_name.1, idx, &mut r.sub());
+                    }
+                    nb -= self.$type_name.0;
+                )*
+                unreachable!()
+            }
+        }
+
+        impl<T, $($type_param),*> Generator for $name<$($type_param),*>
+            where $($type_param: Generator<Item = T>,)*
+        {
+            type Item = T;
+
+            indexed_generation!();
+        }
+
+        #[doc = concat!("Create a generator from ", stringify!($nb), " generators and their associated weight")]
+        #[doc = ""]
+        #[doc = "This is the same as `frequency`, without boxing the generators"]
+        #[doc = ""]
+        #[doc = "If any weight is 0 then a runtime error is thrown"]
+        pub fn $fct_name<T, $($type_param),*>($($type_name: (usize, $type_param)),*) -> $name<$($type_param),*>
+            where $($type_param: Generator<Item = T>,)*
+        {
+            let total = cumulative_weights(&[$($type_name.0),*])[$nb - 1];
+            $name { total, $($type_name),* }
+        }
+    };
+}
+
+generate_frequency! {Frequency2, frequency2, 2, (a, A), (b, B),}
+generate_frequency! {Frequency3, frequency3, 3, (a, A), (b, B), (c, C),}
+generate_frequency! {Frequency4, frequency4, 4, (a, A), (b, B), (c, C), (d, D),}
+generate_frequency! {Frequency5, frequency5, 5, (a, A), (b, B), (c, C), (d, D), (e, E),}
+generate_frequency! {Frequency6, frequency6, 6, (a, A), (b, B), (c, C), (d, D), (e, E), (f, F),}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::constant;
+    use crate::Seed;
+
+    #[test]
+    fn once_computed_once() {
+        let calls = AtomicUsize::new(0);
+        let table = once(|| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Arc::new(vec![1u32, 2, 3])
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let mut r = R::from_seed(Seed::from(4));
+        for _ in 0..100 {
+            assert_eq!(*table.gen(&mut r), vec![1, 2, 3]);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn once_retried_after_panic() {
+        let calls = AtomicUsize::new(0);
+        let value = once(|| {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("first initialization fails")
+            }
+            7u32
+        });
+        let mut r = R::from_seed(Seed::from(4));
+        let first = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| value.gen(&mut r)));
+        assert!(first.is_err());
+        assert_eq!(value.gen(&mut r), 7);
+        assert_eq!(value.gen(&mut r), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn wrapper_items() {
+        use crate::generator::{num, range, string::ascii, vector};
+
+        let mut r = R::from_seed(Seed::from(3));
+        let bytes = vector(range(0..8), num::<u8>());
+        let v = bytes.gen(&mut R::from_seed(Seed::from(3)));
+        let shared: Arc<[u8]> = arc_item(&bytes).gen(&mut r);
+        assert_eq!(&shared[..], &v[..]);
+
+        let s = ascii(range(0..8)).gen(&mut R::from_seed(Seed::from(4)));
+        let boxed: Box<str> = boxed_item(ascii(range(0..8))).gen(&mut R::from_seed(Seed::from(4)));
+        assert_eq!(&*boxed, s.as_str());
+        let counted: Rc<str> = rc_item(ascii(range(0..8))).gen(&mut R::from_seed(Seed::from(4)));
+        assert_eq!(&*counted, s.as_str());
+
+        let n: Arc<u32> = arc_item(num::<u32>()).gen(&mut R::from_seed(Seed::from(5)));
+        assert_eq!(*n, num::<u32>().gen(&mut R::from_seed(Seed::from(5))));
+    }
+
+    #[test]
+    fn stateful_increasing() {
+        let mut r = R::from_seed(Seed::from(9));
+        let timestamps = stateful(0u64, |now, r| {
+            *now += r.num_range(1u64, 10);
+            *now
+        });
+        let values: Vec<u64> = (0..100).map(|_| timestamps.gen(&mut r)).collect();
+        assert!(values.windows(2).all(|w| w[0] < w[1]));
+        assert!(values[99] >= 100 && values[99] <= 1000);
+    }
+
+    #[test]
+    fn range_of_bounds() {
+        let keys = ["apple", "banana", "cherry", "date", "fig"];
+        let mut r = R::from_seed(Seed::from(1));
+        let g = range_of(&keys, "b", "d");
+        let chosen: Vec<&str> = (0..200).map(|_| g.gen(&mut r)).collect();
+        assert!(chosen.iter().all(|k| *k == "banana" || *k == "cherry"));
+        assert!(chosen.contains(&"banana") && chosen.contains(&"cherry"));
+
+        let g = range_of(&[1, 3, 3, 5, 8], 3, 5);
+        assert!((0..100).all(|_| (3..=5).contains(&g.gen(&mut r))));
+        assert_eq!(range_of(&[1, 3, 5], 5, 100).gen(&mut r), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "range_of: no element of the slice in the range [6, 7]")]
+    fn range_of_empty() {
+        range_of(&[1, 3, 5, 8], 6, 7);
+    }
+
+    #[test]
+    fn index_of_bounds() {
+        let mut r = R::from_seed(Seed::from(1));
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[index_of(7).gen(&mut r)] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert_eq!(index_of(1).gen(&mut r), 0);
+    }
+
+    fn frequency_panic<F: FnOnce() + std::panic::UnwindSafe>(f: F) -> String {
+        let e = std::panic::catch_unwind(f).unwrap_err();
+        match e.downcast::<String>() {
+            Ok(message) => *message,
+            Err(e) => e.downcast_ref::<&str>().unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn frequency_zero_weights() {
+        let message = frequency_panic(|| {
+            frequency::<u8>(vec![
+                (1, Box::new(constant(1))),
+                (0, Box::new(constant(2))),
+                (3, Box::new(constant(3))),
+                (0, Box::new(constant(4))),
+            ]);
+        });
+        assert!(message.contains("indices [1, 3] are 0"), "{}", message);
+
+        let message = frequency_panic(|| {
+            frequency::<u8>(vec![(0, Box::new(constant(1)))]);
+        });
+        assert!(message.contains("indices [0] are 0"), "{}", message);
+
+        let message = frequency_panic(|| {
+            frequency::<u8>(vec![(1, Box::new(constant(1)))]).reweighted(&[1, 1]);
+        });
+        assert!(
+            message.contains("2 weights for 1 generators"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn frequency_single_and_reweighted() {
+        let mut r = R::from_seed(Seed::from(1));
+        let single = frequency(vec![(5, Box::new(constant(7u8)))]);
+        assert_eq!(single.weights(), &[5]);
+        assert!((0..100).all(|_| single.gen(&mut r) == 7));
+
+        let g = frequency(vec![
+            (1, Box::new(constant(0u8))),
+            (1, Box::new(constant(1u8))),
+        ]);
+        let mostly_one = g.clone().reweighted(&[1, 99]);
+        assert_eq!(g.weights(), &[1, 1]);
+        assert_eq!(mostly_one.weights(), &[1, 99]);
+        let ones = (0..1000).filter(|_| mostly_one.gen(&mut r) == 1).count();
+        assert!(ones > 950, "{}", ones);
+        let ones = (0..1000).filter(|_| g.gen(&mut r) == 1).count();
+        assert!((400..600).contains(&ones), "{}", ones);
+    }
+
+    #[test]
+    fn mostly_corner_ratio() {
+        use crate::generator::range;
+        let mut r = R::from_seed(Seed::from(0xc0));
+        let g = mostly(range(10u32..1_000_000), &[0, 1, u32::MAX], (1, 10));
+        let values: Vec<u32> = (0..100_000).map(|_| g.gen(&mut r)).collect();
+        let corners = values
+            .iter()
+            .filter(|v| **v < 10 || **v == u32::MAX)
+            .count();
+        assert!((9_500..10_500).contains(&corners), "{}", corners);
+        for corner in [0, 1, u32::MAX].iter() {
+            let nb = values.iter().filter(|v| *v == corner).count();
+            assert!((3_000..3_700).contains(&nb), "{}: {}", corner, nb);
+        }
+
+        let never = mostly(constant('a'), &['b'], (0, 1));
+        assert!((0..1000).all(|_| never.gen(&mut r) == 'a'));
+        let always = mostly(constant('a'), &['b'], (1, 1));
+        assert!((0..1000).all(|_| always.gen(&mut r) == 'b'));
+    }
+
+    #[test]
+    fn variants_weights() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        enum Msg {
+            Ping(u8),
+            Quit,
+        }
+        let gen = variants()
+            .weighted(3, crate::generator::num::<u8>().map(Msg::Ping))
+            .weighted(1, crate::generator::constant(Msg::Quit))
+            .build();
+        let mut r = R::from_seed(Seed::from(1));
+        let quits = (0..4000).filter(|_| gen.gen(&mut r) == Msg::Quit).count();
+        // 1000 expected, 5 standard deviations apart
+        assert!((860..1140).contains(&quits), "{} quits", quits);
+    }
+
+    #[test]
+    fn depends_length_content() {
+        use crate::generator::{constant, num, range, vector};
+
+        let pairs = depends(range(0usize..8), |n| vector(constant(*n), num::<u8>()));
+        let contents = depends(range(0usize..8), |n| vector(constant(*n), num::<u8>()))
+            .map_into(|_, content| content);
+        let mut r = R::from_seed(Seed::from(1));
+        let pairs: Vec<(usize, Vec<u8>)> = (0..20).map(|_| pairs.gen(&mut r)).collect();
+        let mut r = R::from_seed(Seed::from(1));
+        let contents: Vec<Vec<u8>> = (0..20).map(|_| contents.gen(&mut r)).collect();
+        assert!(pairs.iter().all(|(n, v)| v.len() == *n));
+        let expected: Vec<Vec<u8>> = pairs.iter().map(|(_, v)| v.clone()).collect();
+        assert_eq!(contents, expected);
+        assert_eq!(
+            &contents[..3],
+            &[
+                vec![],
+                vec![33, 189, 15, 219, 251, 144],
+                vec![82, 81, 169, 189]
+            ]
+        );
+    }
+
+    #[test]
+    fn one_of_copy_elements() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        enum Tag {
+            A,
+            B,
+        }
+        let mut r = R::from_seed(Seed::from(1));
+        let tags: Vec<Tag> = (0..100)
+            .map(|_| one_of_copy(&[Tag::A, Tag::B]).gen(&mut r))
+            .collect();
+        assert!(tags.contains(&Tag::A) && tags.contains(&Tag::B));
+    }
+
+    #[test]
+    fn one_of_owned_not_copied() {
+        let words: Vec<String> = (0..50_000).map(|i| format!("word{}", i)).collect();
+        // the list is only kept in place without spare capacity, as collected here
+        assert_eq!(words.capacity(), words.len());
+        let (data, first) = (words.as_ptr(), words[0].as_ptr());
+        let gen = one_of_owned(words);
+        // the same allocations, without copying the list or cloning its strings
+        assert!(std::ptr::eq(gen.data.as_ptr(), data));
+        assert!(std::ptr::eq(gen.data[0].as_ptr(), first));
+
+        let mut spare = vec![String::from("spare")];
+        spare.reserve(100);
+        let first = spare[0].as_ptr();
+        let gen = one_of_owned(spare);
+        // the list is shrunk, but its strings are still moved
+        assert!(std::ptr::eq(gen.data[0].as_ptr(), first));
+
+        let mut r = R::from_seed(Seed::from(1));
+        let word = gen.gen(&mut r);
+        assert!(gen.data.contains(&word));
+    }
+
+    #[test]
+    fn one_of_fn_bounds() {
+        let mut r = R::from_seed(Seed::from(1));
+        let gen = one_of_fn(5, |i| i * 10);
+        let mut seen = [false; 5];
+        for _ in 0..1000 {
+            let v = gen.gen(&mut r);
+            assert_eq!(v % 10, 0);
+            seen[v / 10] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert_eq!(
+            stream(one_of_by_index(5, |i| i * 10)),
+            stream(one_of_fn(5, |i| i * 10))
+        );
+    }
+
+    fn stream<G: Generator>(g: G) -> Vec<G::Item> {
+        let mut r = R::from_seed(Seed::from(0x1234_5678));
+        (0..200).map(|_| g.gen(&mut r)).collect()
+    }
+
+    #[test]
+    fn choose_tuple_same_as_boxed() {
+        use crate::generator::{constant, range};
+        let boxed = choose(vec![
+            Box::new(constant(0u32)),
+            Box::new(range(10u32..20)),
+            Box::new(range(100u32..200)),
+        ]);
+        let unboxed = choose3(constant(0u32), range(10u32..20), range(100u32..200));
+        assert_eq!(stream(boxed), stream(unboxed.clone()));
+        assert_eq!(stream(&unboxed), stream(unboxed));
+    }
+
+    #[test]
+    fn frequency_tuple_same_as_boxed() {
+        use crate::generator::{constant, range};
+        let boxed = frequency(vec![
+            (3, Box::new(constant(0u32))),
+            (1, Box::new(constant(1u32))),
+            (7, Box::new(range(10u32..20))),
+        ]);
+        let unboxed = frequency3(
+            (3, constant(0u32)),
+            (1, constant(1u32)),
+            (7, range(10u32..20)),
+        );
+        let values = stream(unboxed);
+        assert_eq!(stream(boxed), values);
+        assert!(values.contains(&0) && values.contains(&1));
+    }
+
+    #[test]
+    fn one_of_ref_static() {
+        static BUFFERS: [[u8; 4]; 3] = [[1; 4], [2; 4], [3; 4]];
+        let refs = stream(one_of_ref(&BUFFERS));
+        assert!(refs
+            .iter()
+            .all(|b| BUFFERS.iter().any(|s| std::ptr::eq(*b, s))));
+        let values: Vec<[u8; 4]> = refs.into_iter().copied().collect();
+        assert_eq!(values, stream(one_of(&BUFFERS)));
+    }
+
+    #[test]
+    fn exhaustive_cycle_coverage() {
+        let mut r = R::from_seed(Seed::from(1));
+        let gen = exhaustive_cycle(7, |i| i);
+        for _ in 0..3 {
+            let mut cycle: Vec<usize> = (0..7).map(|_| gen.gen(&mut r)).collect();
+            cycle.sort_unstable();
+            assert_eq!(cycle, (0..7).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! date and time generators, for the types of the chrono crate
+
+use super::super::rand::R;
+use super::base::Generator;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// Generate `NaiveDate` in a range, see `dates`
+#[derive(Debug, Clone, Copy)]
+pub struct Dates {
+    min_days: i32,
+    max_days: i32,
+}
+
+impl Generator for Dates {
+    type Item = NaiveDate;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        // the range is drawn unsigned, as the days before the common era are negative
+        let span = (self.max_days - self.min_days) as u32;
+        let days = self.min_days + r.num_range(0u32, span) as i32;
+        NaiveDate::from_num_days_from_ce_opt(days).unwrap()
+    }
+}
+
+/// generate dates uniformly between `min` and `max` (included)
+///
+/// Only valid dates are generated, each day of the range being equally likely.
+///
+/// ```
+/// use smoke::generator::date::dates;
+/// use chrono::NaiveDate;
+///
+/// let dates_2000s = dates(
+///     NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+///     NaiveDate::from_ymd_opt(2099, 12, 31).unwrap(),
+/// );
+/// ```
+///
+/// If min is after max then a runtime error is thrown
+pub fn dates(min: NaiveDate, max: NaiveDate) -> Dates {
+    assert!(min <= max, "dates: {} is after {}", min, max);
+    Dates {
+        min_days: min.num_days_from_ce(),
+        max_days: max.num_days_from_ce(),
+    }
+}
+
+/// Generate `NaiveTime` of the whole day, see `times`
+#[derive(Debug, Clone, Copy)]
+pub struct Times;
+
+impl Generator for Times {
+    type Item = NaiveTime;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let secs = r.num_range(0, 86_399);
+        let nanos = r.num_range(0, NANOS_PER_SEC - 1);
+        NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos).unwrap()
+    }
+}
+
+/// generate times of the day, with a nanosecond precision, excluding the leap seconds
+///
+/// ```
+/// use smoke::generator::date::times;
+///
+/// let time_of_day = times();
+/// ```
+pub fn times() -> Times {
+    Times
+}
+
+/// Generate `NaiveDateTime` in a range, see `date_times`
+#[derive(Debug, Clone, Copy)]
+pub struct DateTimes {
+    min: NaiveDateTime,
+    /// Whole seconds between min and max
+    secs: i64,
+    /// Nanoseconds of max beyond the whole seconds
+    nanos: u32,
+}
+
+impl Generator for DateTimes {
+    type Item = NaiveDateTime;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let secs = r.num_range(0, self.secs);
+        let max_nanos = if secs == self.secs {
+            self.nanos
+        } else {
+            NANOS_PER_SEC - 1
+        };
+        let nanos = r.num_range(0, max_nanos);
+        self.min + Duration::seconds(secs) + Duration::nanoseconds(i64::from(nanos))
+    }
+}
+
+/// generate date and times between `min` and `max` (included), with a nanosecond
+/// precision, excluding the leap seconds
+///
+/// ```
+/// use smoke::generator::date::date_times;
+/// use chrono::NaiveDate;
+///
+/// let day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+/// let leap_day = date_times(
+///     day.and_hms_opt(0, 0, 0).unwrap(),
+///     day.and_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+/// );
+/// ```
+///
+/// If min is after max then a runtime error is thrown
+pub fn date_times(min: NaiveDateTime, max: NaiveDateTime) -> DateTimes {
+    assert!(min <= max, "date_times: {} is after {}", min, max);
+    let span = max - min;
+    let secs = span.num_seconds();
+    let nanos = (span - Duration::seconds(secs)).num_nanoseconds().unwrap() as u32;
+    DateTimes { min, secs, nanos }
+}
+
+/// Generate `DateTime<Utc>` in a range, see `utc_date_times`
+#[derive(Debug, Clone, Copy)]
+pub struct UtcDateTimes(DateTimes);
+
+impl Generator for UtcDateTimes {
+    type Item = DateTime<Utc>;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        Utc.from_utc_datetime(&self.0.gen(r))
+    }
+}
+
+/// generate UTC date and times between `min` and `max` (included), with a nanosecond
+/// precision, excluding the leap seconds
+///
+/// ```
+/// use smoke::generator::date::utc_date_times;
+/// use chrono::{TimeZone, Utc};
+///
+/// let unix_32bits = utc_date_times(
+///     Utc.timestamp_opt(0, 0).unwrap(),
+///     Utc.timestamp_opt(i32::MAX as i64, 0).unwrap(),
+/// );
+/// ```
+///
+/// If min is after max then a runtime error is thrown
+pub fn utc_date_times(min: DateTime<Utc>, max: DateTime<Utc>) -> UtcDateTimes {
+    UtcDateTimes(date_times(min.naive_utc(), max.naive_utc()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seed;
+    use chrono::Timelike;
+
+    fn samples<G: Generator>(g: G) -> Vec<G::Item> {
+        let mut r = R::from_seed(Seed::from(0x0da7e));
+        (0..2000).map(|_| g.gen(&mut r)).collect()
+    }
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn dates_in_range() {
+        let (min, max) = (ymd(1999, 12, 30), ymd(2001, 3, 2));
+        let v = samples(dates(min, max));
+        assert!(v.iter().all(|d| *d >= min && *d <= max));
+        // every date is valid, including the leap day of 2000
+        for d in v.iter() {
+            assert_eq!(ymd(d.year(), d.month(), d.day()), *d);
+        }
+        assert!(v.contains(&ymd(2000, 2, 29)));
+        assert!(v.contains(&min) && v.contains(&max));
+
+        let single = samples(dates(min, min));
+        assert!(single.iter().all(|d| *d == min));
+    }
+
+    #[test]
+    fn dates_before_common_era() {
+        let (min, max) = (ymd(-44, 3, 15), ymd(14, 8, 19));
+        let v = samples(dates(min, max));
+        assert!(v.iter().all(|d| *d >= min && *d <= max));
+        assert!(v.iter().any(|d| d.year() < 0));
+        assert!(v.iter().any(|d| d.year() > 0));
+
+        let bce = samples(dates(ymd(-100, 1, 1), ymd(-50, 12, 31)));
+        assert!(bce.iter().all(|d| d.year() >= -100 && d.year() <= -50));
+    }
+
+    #[test]
+    fn date_times_in_range() {
+        let min = ymd(2024, 2, 28).and_hms_nano_opt(23, 0, 0, 500).unwrap();
+        let max = ymd(2024, 3, 1).and_hms_nano_opt(1, 0, 0, 250).unwrap();
+        let v = samples(date_times(min, max));
+        assert!(v.iter().all(|dt| *dt >= min && *dt <= max));
+        assert!(v.iter().any(|dt| dt.date() == ymd(2024, 2, 29)));
+
+        let instant = samples(date_times(max, max));
+        assert!(instant.iter().all(|dt| *dt == max));
+
+        let utc = samples(utc_date_times(
+            Utc.from_utc_datetime(&min),
+            Utc.from_utc_datetime(&max),
+        ));
+        assert!(utc
+            .iter()
+            .all(|dt| dt.naive_utc() >= min && dt.naive_utc() <= max));
+    }
+
+    #[test]
+    fn times_valid() {
+        let v = samples(times());
+        assert!(v.iter().all(|t| t.nanosecond() < NANOS_PER_SEC));
+        assert!(v.iter().any(|t| t.hour() < 12) && v.iter().any(|t| t.hour() >= 12));
+    }
+}
@@ -7,51 +7,456 @@
 //! it returns the Item directly, and takes an extra random generator
 //! to generate the next element.
 
-use super::rand::R;
-
 mod base;
+pub mod bits;
 pub mod char;
 mod collection;
 mod combinators;
+#[cfg(feature = "chrono")]
+pub mod date;
+mod net;
 mod numerical;
+pub mod path;
 mod product;
 pub mod string;
+#[cfg(feature = "text")]
+pub mod text;
+mod unicode_category;
 
+pub use crate::model::{commands, Commands};
 pub use base::*;
+pub use bits::{flags, flags_from, Flags};
 pub use collection::*;
 pub use combinators::*;
+pub use net::*;
 pub use numerical::*;
 pub use product::*;
 
-/// Dependent generator where the second items depends on what has been generated by the first generator
-pub struct Depends<G, F> {
-    src_gen: G,
-    dst_gen: F,
-}
+#[cfg(test)]
+mod tests {
+    //! Golden tests of the generated streams
+    //!
+    //! Any change of the expected values here means that previously recorded seeds
+    //! no longer reproduce the same inputs, and require bumping `GENERATION_VERSION`
+
+    use super::*;
+    use crate::{Seed, Testable, GENERATION_VERSION, R};
 
-impl<G1, G2, F> Generator for Depends<G1, F>
-where
-    G1: Generator,
-    G2: Generator,
-    F: Fn(&G1::Item) -> G2,
-{
-    type Item = (G1::Item, G2::Item);
-    fn gen(&self, r: &mut R) -> Self::Item {
-        let x = self.src_gen.gen(&mut r.sub());
-        let g2 = (self.dst_gen)(&x);
-        let y = g2.gen(&mut r.sub());
-        (x, y)
+    const GOLDEN_SEED: u128 = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+
+    fn golden<G: Generator>(g: G, n: usize) -> Vec<G::Item> {
+        let mut r = R::from_seed(Seed::from(GOLDEN_SEED));
+        (0..n).map(|_| g.gen(&mut r)).collect()
+    }
+
+    /// The inputs of a test run with the golden seed
+    fn tested_inputs<G>(g: G, n: u64) -> Vec<G::Item>
+    where
+        G: Generator,
+        G::Item: std::fmt::Debug + 'static,
+    {
+        let inputs = std::cell::RefCell::new(Vec::new());
+        let mut ctx = crate::Context::new();
+        ctx.set_seed(Seed::from(GOLDEN_SEED));
+        ctx.set_nb_tests(n);
+        crate::forall(g)
+            .ensure(|input| {
+                inputs.borrow_mut().push(input);
+                crate::property::equal(0, 0)
+            })
+            .test(&ctx);
+        inputs.into_inner()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// All the generators are reachable from this module, whichever sub-module defines them
+    #[test]
+    #[allow(unused_imports)]
+    fn public_surface() {
+        use crate::generator::{
+            arc, arc_item, array, bits, bool_gen, bool_ratio, boxed_item, boxed_value, char,
+            choose, constant, depends, exhaustive_cycle, frequency, ipv4_in, map_from_keys, mostly,
+            num, one_of, one_of_by_index, one_of_fn, one_of_ref, range, rc, rc_item, record,
+            string, tuple2, vector,
+        };
+        use crate::generator::{
+            And, BoxGenerator, Depends, Flatten, GenError, Generator, GeneratorInPlace, Map,
+            MapRng, Or, Product2, Scale, SuchThat, SuchThatMap, Vector,
+        };
+    }
+
+    #[test]
+    fn golden_version() {
+        assert_eq!(GENERATION_VERSION, 4);
+    }
+
+    #[test]
+    fn golden_num() {
+        let v: Vec<String> = golden(num::<u64>(), 4)
+            .iter()
+            .map(|n| hex(&n.to_be_bytes()))
+            .collect();
+        assert_eq!(
+            v,
+            vec![
+                "2468a5eb7dd34e91",
+                "edf17076b20ff95d",
+                "519b33c655a880a8",
+                "9d614d701ade3228"
+            ]
+        );
+    }
+
+    /// usize values are generated from u64 values on all targets: the 32 bits targets
+    /// get the truncation of the 64 bits values, and the same values for the ranges
+    /// fitting in 32 bits
+    #[test]
+    fn golden_usize() {
+        let wide: Vec<u64> = golden(num::<u64>(), 4);
+        let sizes: Vec<u64> = golden(num::<usize>(), 4)
+            .iter()
+            .map(|n| *n as u64)
+            .collect();
+        assert_eq!(sizes, wide);
+        let truncated: Vec<u32> = golden(num::<usize>(), 4)
+            .iter()
+            .map(|n| *n as u32)
+            .collect();
+        let simulated: Vec<u32> = wide.iter().map(|n| *n as u32).collect();
+        assert_eq!(truncated, simulated);
+
+        let max32 = u32::MAX as usize;
+        let ranged: Vec<u64> = golden(range(1usize..max32), 64)
+            .iter()
+            .map(|n| *n as u64)
+            .collect();
+        assert_eq!(ranged, golden(range(1u64..max32 as u64), 64));
+        let indices = golden(range(0usize..1000), 8);
+        assert_eq!(indices, vec![768, 318, 123, 919, 72, 674, 967, 129]);
+    }
+
+    /// The leaf generators of the products draw directly from the random generator
+    /// of the product, and the other generators from a sub-stream
+    #[test]
+    fn golden_tuple() {
+        let v = golden(tuple3(num::<u8>(), range(0u8..9), constant(7u8)), 4);
+        assert_eq!(v, vec![(235, 5, 7), (118, 3, 7), (198, 8, 7), (112, 0, 7)]);
+        // a value for each of the 2 random leaves, without any sub-stream derivation
+        let draws = golden(num::<u8>(), 8);
+        let firsts: Vec<u8> = v.iter().map(|t| t.0).collect();
+        assert_eq!(firsts, vec![draws[0], draws[2], draws[4], draws[6]]);
+
+        let v = golden(tuple2(num::<u8>(), vector(constant(2), num::<u8>())), 2);
+        assert_eq!(v, vec![(235, vec![34, 52]), (118, vec![243, 230])]);
+    }
+
+    /// The wrappers of the leaf generators draw directly from the random generator
+    /// of the product too
+    #[test]
+    fn golden_wrapped_leaves() {
+        let wrapped = tuple3(
+            num::<u8>().map(|n| n).such_that(|_| true),
+            range(0u8..9).with_examples(vec![]).scale(|s| s / 2),
+            constant(7u8),
+        );
+        let v = golden(wrapped, 4);
+        assert_eq!(v, vec![(235, 5, 7), (118, 3, 7), (198, 8, 7), (112, 0, 7)]);
+
+        let v = golden(tuple2(num::<u8>().map_rng(|n, _| n), num::<u8>()), 4);
+        assert_eq!(v, vec![(235, 118), (93, 168), (112, 171), (211, 119)]);
+
+        // the examples replace the first inputs without changing the next ones
+        let mut expected = tested_inputs(num::<u32>(), 6);
+        expected[..2].copy_from_slice(&[0, 1]);
+        assert_eq!(
+            tested_inputs(num::<u32>().with_examples(vec![0, 1]), 6),
+            expected
+        );
+    }
+
+    #[test]
+    fn golden_range() {
+        let v: Vec<String> = golden(range(10u32..1000), 8)
+            .iter()
+            .map(|n| hex(&n.to_be_bytes()))
+            .collect();
+        assert_eq!(
+            v,
+            vec![
+                "000001d6", "000002f6", "00000295", "00000021", "00000367", "000002ff", "00000095",
+                "000000e1"
+            ]
+        );
+    }
+
+    #[test]
+    fn golden_vector() {
+        let v: Vec<String> = golden(vector(range(0..6), num::<u8>()), 4)
+            .iter()
+            .map(|v| hex(v))
+            .collect();
+        assert_eq!(v, vec!["256b38b7fc", "220fceeb", "3aa6", "d3"]);
+    }
+
+    #[test]
+    fn map_rng_deterministic() {
+        let corrupted = vector(range(1..16), num::<u8>()).map_rng(|mut v, r| {
+            let idx = r.num_range(0, v.len() - 1);
+            v[idx] ^= 0xff;
+            v
+        });
+        let v = golden(&corrupted, 16);
+        assert_eq!(v, golden(&corrupted, 16));
+
+        let original = &golden(vector(range(1..16), num::<u8>()), 1)[0];
+        let differences = original.iter().zip(&v[0]).filter(|(a, b)| a != b);
+        assert_eq!(differences.count(), 1);
+    }
+
+    #[test]
+    fn reference_generator() {
+        let g = vector(range(0..6), num::<u8>());
+        let lengths = golden((&g).map(|v| v.len()), 4);
+        assert_eq!(lengths, vec![5, 4, 2, 1]);
+        assert_eq!(golden(&g, 4), golden(g, 4));
+    }
+
+    /// Helpers over borrowed trait objects, composing without owning the generators
+    #[test]
+    fn dyn_generators() {
+        use std::sync::Arc;
+
+        fn pairs<'a>(
+            size: &'a dyn Generator<Item = usize>,
+            elements: &'a dyn Generator<Item = u8>,
+        ) -> impl Generator<Item = Vec<(u8, u8)>> + 'a {
+            vector(size, tuple2(elements, elements.map(|n| n / 2)))
+        }
+
+        let size = range(0usize..6);
+        let table: Vec<u8> = (0..=255).collect();
+        let elements = one_of(&table);
+        let v = golden(pairs(&size, &elements), 4);
+        let expected = golden(
+            vector(
+                range(0usize..6),
+                tuple2(one_of(&table), one_of(&table).map(|n| n / 2)),
+            ),
+            4,
+        );
+        assert_eq!(v, expected);
+
+        let boxed: Box<dyn Generator<Item = u8>> = Box::new(one_of(&table));
+        let shared = Arc::new(one_of(&table));
+        assert_eq!(golden(&boxed, 8), golden(one_of(&table), 8));
+        assert_eq!(golden(shared.clone(), 8), golden(one_of(&table), 8));
+        assert_eq!(
+            golden(vector(&size, boxed), 4),
+            golden(vector(&size, shared), 4)
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let g = one_of(&[range(0u32..9), range(1_000u32..1_009)]).flatten();
+        let v = golden(g, 100);
+        assert!(v.iter().all(|n| *n < 10 || (1_000..1_010).contains(n)));
+        assert!(v.iter().any(|n| *n < 10) && v.iter().any(|n| *n >= 1_000));
+
+        let constants = golden(one_of(&[constant(1), constant(2)]).flatten(), 100);
+        assert!(constants.iter().all(|n| *n == 1 || *n == 2));
+    }
+
+    #[test]
+    fn take_samples() {
+        let g = vector(range(0..6), num::<u8>());
+        let mut samples = (&g).take_samples(Seed::from(GOLDEN_SEED), 10);
+        assert_eq!(samples.len(), 10);
+        assert_eq!(samples.size_hint(), (10, Some(10)));
+        samples.next();
+        assert_eq!(samples.len(), 9);
+        let rest: Vec<Vec<u8>> = samples.collect();
+        assert_eq!(rest.capacity(), 9);
+
+        let bounded: Vec<Vec<u8>> = (&g).take_samples(Seed::from(GOLDEN_SEED), 10).collect();
+        let unbounded: Vec<Vec<u8>> = (&g).sample_iter(Seed::from(GOLDEN_SEED)).take(10).collect();
+        assert_eq!(bounded, unbounded);
+        assert_eq!((&g).take_samples(Seed::from(GOLDEN_SEED), 0).count(), 0);
+    }
+
+    /// The samples of a seed are the inputs of the tests run with this seed
+    #[test]
+    fn samples_are_tested_inputs() {
+        let leaf = num::<u32>();
+        let samples: Vec<u32> = (&leaf).take_samples(Seed::from(GOLDEN_SEED), 16).collect();
+        assert_eq!(samples, tested_inputs(&leaf, 16));
+
+        let pairs = tuple2(num::<u8>(), range(0u8..9));
+        let samples: Vec<(u8, u8)> = (&pairs).take_samples(Seed::from(GOLDEN_SEED), 16).collect();
+        assert_eq!(samples, tested_inputs(&pairs, 16));
+    }
+
+    #[test]
+    fn and_flat_tuples() {
+        let flat = num::<u8>()
+            .and_flat(range(0u16..10))
+            .and_flat(num::<u32>())
+            .and_flat(bool_gen());
+        let v: Vec<(u8, u16, u32, bool)> = golden(flat, 8);
+        let expected = golden(
+            tuple4(num::<u8>(), range(0u16..10), num::<u32>(), bool_gen()),
+            8,
+        );
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn tuple_clone_choose() {
+        let small = tuple3(range(0u8..9), range(0u16..9), bool_gen());
+        let large = tuple3(range(100u8..109), range(100u16..109), bool_gen());
+        let g = choose(vec![
+            Box::new(small.clone()),
+            Box::new(small.clone()),
+            Box::new(large),
+        ]);
+        let v = golden(g, 100);
+        assert!(v.iter().all(|(a, b, _)| (*a < 10) == (*b < 10)));
+        assert!(v.iter().any(|(a, _, _)| *a < 10) && v.iter().any(|(a, _, _)| *a >= 100));
+        assert_eq!(golden(small.clone(), 8), golden(small, 8));
+
+        let t = tuple3(num::<u8>(), range(0u16..9), bool_gen());
+        let mapped = golden(t.clone().map(|(a, b, c)| format!("{} {} {}", a, b, c)), 8);
+        let fused = golden(t.into_struct(|a, b, c| format!("{} {} {}", a, b, c)), 8);
+        assert_eq!(fused, mapped);
+    }
+
+    #[test]
+    fn excluding_values() {
+        let v = golden(range(0u8..5).excluding(&[0, 2, 5]), 500);
+        assert!(v.iter().all(|n| *n == 1 || *n == 3 || *n == 4));
+        assert!(v.contains(&1) && v.contains(&3) && v.contains(&4));
+
+        let mut r = R::from_seed(Seed::from(GOLDEN_SEED));
+        let all_excluded = range(0u8..2).excluding(&[0, 1, 2]).with_retry(5);
+        assert_eq!(all_excluded.try_gen(&mut r), Err(GenError::Exhausted));
+
+        // an over-broad exclusion discards the iterations instead of failing them
+        let mut ctx = crate::Context::new();
+        ctx.set_nb_tests(20);
+        let results = crate::forall(all_excluded)
+            .ensure(|n| crate::property::less(n, 3))
+            .test(&ctx);
+        assert_eq!(results.nb_skipped, 20);
+        assert_eq!(results.nb_failed, 0);
+    }
+
+    #[test]
+    fn such_that_map_attempts() {
+        use std::cell::Cell;
+
+        let attempts = Cell::new(0);
+        let counted = num::<u32>().map(|n| {
+            attempts.set(attempts.get() + 1);
+            n
+        });
+        let mut r = R::from_seed(Seed::from(GOLDEN_SEED));
+        let mut expected = R::from_seed(Seed::from(GOLDEN_SEED));
+        for _ in 0..20 {
+            attempts.set(0);
+            let v = (&counted)
+                .such_that_map(|n| if n & 3 == 0 { Some(n / 4) } else { None })
+                .gen(&mut r);
+            let mut expected_attempts = 1;
+            while expected.num::<u32>() & 3 != 0 {
+                expected_attempts += 1;
+            }
+            assert_eq!(attempts.get(), expected_attempts);
+            assert!(v <= u32::MAX / 4);
+        }
+
+        attempts.set(0);
+        let never = (&counted).such_that_map(|_| None::<u32>).with_retry(5);
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| never.gen(&mut r)));
+        assert!(res.unwrap_err().is::<SuchThatRetryFailure>());
+        assert_eq!(attempts.get(), 6);
+    }
+
+    #[test]
+    fn such_that_stats() {
+        let (multiples, stats) = range(0u32..99).such_that_counted(|n| n % 3 == 0);
+        assert_eq!(stats.rejection_rate(), 0.0);
+        let v = golden(&multiples, 10_000);
+        assert!(v.iter().all(|n| n % 3 == 0));
+        assert_eq!(stats.accepted(), 10_000);
+        assert!(stats.attempts() > 25_000 && stats.attempts() < 35_000);
+        let rate = stats.rejection_rate();
+        assert!((0.6..0.72).contains(&rate), "{}", rate);
+
+        // the clones of the filter share the statistics
+        let (never, stats) = range(0u32..9).such_that_counted(|n| *n > 9);
+        let never = never.with_retry(4);
+        let mut r = R::from_seed(Seed::from(GOLDEN_SEED));
+        assert_eq!(never.clone().try_gen(&mut r), Err(GenError::Exhausted));
+        assert_eq!((stats.attempts(), stats.accepted()), (5, 0));
+        assert_eq!(stats.rejection_rate(), 1.0);
+    }
+
+    #[test]
+    fn try_gen_exhausted() {
+        let never = range(0u32..10).such_that(|n| *n > 10).with_retry(3);
+        let mut r = R::from_seed(Seed::from(GOLDEN_SEED));
+        assert_eq!(never.try_gen(&mut r), Err(GenError::Exhausted));
+        assert_eq!(
+            (&never).map(|n| n + 1).try_gen(&mut r),
+            Err(GenError::Exhausted)
+        );
+        assert_eq!(
+            vector(range(1..4), &never).try_gen(&mut r),
+            Err(GenError::Exhausted)
+        );
+        assert_eq!(
+            tuple2(num::<u8>(), &never).try_gen(&mut r),
+            Err(GenError::Exhausted)
+        );
+        let nested = range(0u32..10).such_that(|n| *n < 5).such_that(|n| *n > 5);
+        assert_eq!(nested.try_gen(&mut r), Err(GenError::Exhausted));
+
+        let even = range(0u32..10).such_that(|n| n % 2 == 0);
+        assert_eq!(golden(&even, 8), {
+            let mut r = R::from_seed(Seed::from(GOLDEN_SEED));
+            (0..8)
+                .map(|_| even.try_gen(&mut r).unwrap())
+                .collect::<Vec<_>>()
+        });
+    }
+
+    #[test]
+    fn golden_ascii() {
+        let v: Vec<String> = golden(string::ascii(range(0..8)), 4)
+            .iter()
+            .map(|s| hex(s.as_bytes()))
+            .collect();
+        assert_eq!(
+            v,
+            vec![
+                "654b58",
+                "222f6e4b4e",
+                "3a264b592d734933",
+                "3347766462626e74"
+            ]
+        );
     }
-}
 
-pub fn depends<F, G1, G2>(g1: G1, f: F) -> Depends<G1, F>
-where
-    G1: Generator,
-    G2: Generator,
-    F: FnOnce(&G1::Item) -> G2,
-{
-    Depends {
-        src_gen: g1,
-        dst_gen: f,
+    #[test]
+    fn golden_choose() {
+        let g = choose(vec![
+            Box::new(constant(0u8)),
+            Box::new(constant(1)),
+            Box::new(range(100u8..200)),
+        ]);
+        assert_eq!(hex(&golden(g, 10)), "00009e6d01010100a301");
     }
 }
@@ -0,0 +1,87 @@
+//! network address generators
+
+use super::super::rand::R;
+use super::base::Generator;
+use std::net::Ipv4Addr;
+
+/// Generate IPv4 addresses inside a network block
+#[derive(Debug, Clone, Copy)]
+pub struct Ipv4In {
+    network: u32,
+    host_mask: u32,
+}
+
+impl Generator for Ipv4In {
+    type Item = Ipv4Addr;
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let host = r.num::<u32>() & self.host_mask;
+        Ipv4Addr::from(self.network | host)
+    }
+}
+
+/// Generate IPv4 addresses inside the network block in CIDR notation `cidr` (e.g. "10.0.0.0/8")
+///
+/// The network bits are the ones of the address in the CIDR notation, and the host
+/// bits are randomly generated. An error is returned if `cidr` isn't a valid CIDR notation.
+///
+/// ```
+/// use smoke::generator::ipv4_in;
+///
+/// let private_addresses = ipv4_in("192.168.0.0/16").expect("valid cidr");
+/// ```
+pub fn ipv4_in(cidr: &str) -> Result<Ipv4In, &'static str> {
+    let (addr, prefix) = cidr
+        .split_once('/')
+        .ok_or("expecting an address and a prefix length separated by /")?;
+    let addr: Ipv4Addr = addr.parse().map_err(|_| "cannot parse the IPv4 address")?;
+    let prefix: u32 = prefix
+        .parse()
+        .map_err(|_| "cannot parse the prefix length as an integer")?;
+    if prefix > 32 {
+        return Err("prefix length must be between 0 and 32");
+    }
+    let host_mask = u32::MAX.checked_shr(prefix).unwrap_or(0);
+    Ok(Ipv4In {
+        network: u32::from(addr) & !host_mask,
+        host_mask,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seed;
+
+    fn in_block(cidr: &str, network: [u8; 4], prefix: u32) {
+        let gen = ipv4_in(cidr).unwrap();
+        let network = u32::from(Ipv4Addr::from(network));
+        let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+        let mut r = R::from_seed(Seed::from(0xc1d2));
+        for _ in 0..1000 {
+            let addr = u32::from(gen.gen(&mut r));
+            assert_eq!(
+                addr & mask,
+                network,
+                "{} not in {}",
+                Ipv4Addr::from(addr),
+                cidr
+            );
+        }
+    }
+
+    #[test]
+    fn addresses_in_block() {
+        in_block("10.0.0.0/8", [10, 0, 0, 0], 8);
+        in_block("192.168.12.0/22", [192, 168, 12, 0], 22);
+        in_block("172.16.5.4/32", [172, 16, 5, 4], 32);
+        in_block("0.0.0.0/0", [0, 0, 0, 0], 0);
+    }
+
+    #[test]
+    fn invalid_cidr() {
+        assert!(ipv4_in("10.0.0.0").is_err());
+        assert!(ipv4_in("10.0.0/8").is_err());
+        assert!(ipv4_in("10.0.0.0/33").is_err());
+        assert!(ipv4_in("10.0.0.0/x").is_err());
+    }
+}
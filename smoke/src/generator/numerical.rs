@@ -23,6 +23,9 @@ impl<T: NumPrimitive> Generator for Num<T> {
     fn gen(&self, r: &mut R) -> T {
         r.num()
     }
+    fn needs_sub_stream(&self) -> bool {
+        false
+    }
 }
 
 /// Range Primitive generator
@@ -40,6 +43,9 @@ impl<T: NumPrimitive> Generator for NumRange<T> {
     fn gen(&self, r: &mut R) -> T {
         r.num_range(self.0.start, self.0.end)
     }
+    fn needs_sub_stream(&self) -> bool {
+        false
+    }
 }
 
 /// Generator for a simple numeric primitive over the whole possible range
@@ -51,3 +57,72 @@ pub fn num<T: NumPrimitive>() -> Num<T> {
 pub fn range<T: NumPrimitive>(range: std::ops::Range<T>) -> NumRange<T> {
     NumRange::new(range)
 }
+
+/// Boolean generator
+#[derive(Debug, Clone, Copy)]
+pub struct Bool;
+
+impl Generator for Bool {
+    type Item = bool;
+    fn gen(&self, r: &mut R) -> bool {
+        r.bool()
+    }
+}
+
+/// Boolean generator, true with a given probability
+#[derive(Debug, Clone, Copy)]
+pub struct BoolRatio {
+    numerator: u32,
+    denominator: u32,
+}
+
+impl Generator for BoolRatio {
+    type Item = bool;
+    fn gen(&self, r: &mut R) -> bool {
+        r.gen_ratio(self.numerator, self.denominator)
+    }
+}
+
+/// Generator of booleans, with true and false equally likely
+pub fn bool_gen() -> Bool {
+    Bool
+}
+
+/// Generator of booleans, true with a probability of `numerator / denominator`
+///
+/// ```
+/// use smoke::{Generator, generator::{bool_ratio, num}};
+///
+/// // an optional field present 10% of the time
+/// let field = num::<u32>().map2(bool_ratio(1, 10), |v, present| if present { Some(v) } else { None });
+/// ```
+///
+/// If denominator is 0, or numerator is greater than denominator, then a runtime error is thrown
+pub fn bool_ratio(numerator: u32, denominator: u32) -> BoolRatio {
+    assert!(denominator > 0 && numerator <= denominator);
+    BoolRatio {
+        numerator,
+        denominator,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seed;
+
+    fn count_true<G: Generator<Item = bool>>(g: G, n: usize) -> usize {
+        let mut r = R::from_seed(Seed::from(0xb001));
+        (0..n).filter(|_| g.gen(&mut r)).count()
+    }
+
+    #[test]
+    fn bool_ratio_frequency() {
+        let trues = count_true(bool_ratio(1, 10), 100_000);
+        assert!((9_500..10_500).contains(&trues), "{}", trues);
+        let trues = count_true(bool_gen(), 100_000);
+        assert!((49_000..51_000).contains(&trues), "{}", trues);
+        assert_eq!(count_true(bool_ratio(0, 3), 1000), 0);
+        assert_eq!(count_true(bool_ratio(3, 3), 1000), 1000);
+    }
+}
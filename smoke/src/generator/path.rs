@@ -0,0 +1,185 @@
+//! filesystem path and OS string generators
+
+use super::super::rand::R;
+use super::base::{gen_at, indexed_generation, Generator};
+use std::ffi::OsString;
+use std::path::{PathBuf, MAIN_SEPARATOR};
+
+/// Segments with a special meaning, mixed with the generated segments
+const EDGE_SEGMENTS: &[&str] = &["", ".", ".."];
+
+/// Root of the absolute paths of the target platform
+#[cfg(windows)]
+const ROOT: &str = "C:\\";
+#[cfg(not(windows))]
+const ROOT: &str = "/";
+
+/// Generate paths made of generated segments joined with the platform separator
+#[derive(Clone)]
+pub struct PathGenerator<SZ, G> {
+    nb_segments: SZ,
+    segment: G,
+}
+
+impl<SZ, G> PathGenerator<SZ, G>
+where
+    SZ: Generator<Item = usize>,
+    G: Generator<Item = String>,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> PathBuf {
+        let sz = gen_at(&self.nb_segments, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        let mut path = String::new();
+        if r.gen_ratio(1, 4) {
+            path.push_str(ROOT);
+        }
+        let mut segments_r = r.sub();
+        for i in 0..sz {
+            if i > 0 {
+                path.push(MAIN_SEPARATOR);
+            }
+            if segments_r.gen_ratio(1, 8) {
+                let edge = segments_r.num_range(0, EDGE_SEGMENTS.len() - 1);
+                path.push_str(EDGE_SEGMENTS[edge]);
+            } else {
+                path.push_str(&gen_at(&self.segment, idx, &mut segments_r));
+            }
+        }
+        PathBuf::from(path)
+    }
+}
+
+impl<SZ, G> Generator for PathGenerator<SZ, G>
+where
+    SZ: Generator<Item = usize>,
+    G: Generator<Item = String>,
+{
+    type Item = PathBuf;
+
+    indexed_generation!();
+}
+
+/// generate paths of a number of segments specified by the first generator, where each
+/// segment is generated by the second generator
+///
+/// The segments are joined with the separator of the target platform, and the path is
+/// absolute (from `/` or `C:\`) one time out of 4. Some segments are replaced by the edge
+/// cases `""` (resulting in consecutive separators), `"."` and `".."`.
+///
+/// The segment generator shouldn't generate separators, unless the tested code is
+/// expected to handle them.
+///
+/// ```
+/// use smoke::generator::{path::path, string::string, range, char::ascii};
+///
+/// let paths = path(range(0..6), string(range(1..12), ascii()));
+/// ```
+pub fn path<SZ, G>(nb_segments: SZ, segment: G) -> PathGenerator<SZ, G>
+where
+    SZ: Generator<Item = usize>,
+    G: Generator<Item = String>,
+{
+    PathGenerator {
+        nb_segments,
+        segment,
+    }
+}
+
+/// Generate OS strings, sometimes not representable as a `String`
+#[derive(Clone)]
+pub struct OsStringGenerator<G> {
+    generator: G,
+}
+
+impl<G: Generator<Item = String>> OsStringGenerator<G> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> OsString {
+        let s = gen_at(&self.generator, idx, &mut r.sub());
+        if r.gen_ratio(1, 8) {
+            not_unicode(s)
+        } else {
+            OsString::from(s)
+        }
+    }
+}
+
+impl<G: Generator<Item = String>> Generator for OsStringGenerator<G> {
+    type Item = OsString;
+
+    indexed_generation!();
+}
+
+/// Append to `s` an element not representable as unicode: a lone continuation
+/// byte on unix and a lone surrogate on windows
+#[cfg(unix)]
+fn not_unicode(s: String) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    let mut bytes = s.into_bytes();
+    bytes.push(0x80);
+    OsString::from_vec(bytes)
+}
+
+#[cfg(windows)]
+fn not_unicode(s: String) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    let mut wide: Vec<u16> = s.encode_utf16().collect();
+    wide.push(0xd800);
+    OsString::from_wide(&wide)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn not_unicode(s: String) -> OsString {
+    OsString::from(s)
+}
+
+/// generate OS strings from the strings of the generator in parameter
+///
+/// One time out of 8, on the platforms where OS strings are not necessarily unicode
+/// (unix and windows), an invalid element is appended so that `to_str` fails.
+///
+/// ```
+/// use smoke::generator::{path::os_string, string::ascii, range};
+///
+/// let os_strings = os_string(ascii(range(0..16)));
+/// ```
+pub fn os_string<G: Generator<Item = String>>(generator: G) -> OsStringGenerator<G> {
+    OsStringGenerator { generator }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{char, numerical::range, string::string};
+    use crate::Seed;
+
+    fn samples<G: Generator>(g: G) -> Vec<G::Item> {
+        let mut r = R::from_seed(Seed::from(0x9a7b));
+        (0..500).map(|_| g.gen(&mut r)).collect()
+    }
+
+    #[test]
+    fn path_round_trip() {
+        let paths = samples(path(range(0..6), string(range(1..8), char::ascii())));
+        for p in paths.iter() {
+            let s = p.to_str().expect("paths of unicode segments are unicode");
+            assert_eq!(&PathBuf::from(s), p);
+        }
+        let edge_case = format!("{}..", MAIN_SEPARATOR);
+        assert!(paths.iter().any(|p| p.is_absolute()));
+        assert!(paths
+            .iter()
+            .any(|p| p.to_str().unwrap().contains(&edge_case)));
+    }
+
+    #[test]
+    fn os_string_round_trip() {
+        let strings = samples(os_string(string(range(0..8), char::ascii())));
+        for s in strings.iter() {
+            if let Some(valid) = s.to_str() {
+                assert_eq!(&OsString::from(valid), s);
+            }
+        }
+        if cfg!(any(unix, windows)) {
+            assert!(strings.iter().any(|s| s.to_str().is_none()));
+        }
+    }
+}
@@ -1,7 +1,28 @@
 //! product type
+//!
+//! The tuple generators generate the tuples of the items of their generators, and
+//! the product generators map them through a function, typically to build a struct.
+//! A tuple generator becomes a product generator with `into_struct`, generating the
+//! same items without the intermediate tuple:
+//!
+//! ```
+//! use smoke::generator::{num, range, tuple3, Generator};
+//!
+//! #[derive(Debug, Clone)]
+//! struct Color {
+//!     red: u8,
+//!     green: u8,
+//!     alpha: f32,
+//! }
+//!
+//! let colors = tuple3(num::<u8>(), num::<u8>(), range(0u8..100))
+//!     .into_struct(|red, green, alpha| Color { red, green, alpha: alpha as f32 / 100.0 });
+//! ```
+//!
+//! Both are `Clone` when all their generators (and the mapping function) are.
 
 use super::super::rand::R;
-use super::base::Generator;
+use super::base::{gen_sub, indexed_generation, try_gen_sub, GenError, Generator};
 
 macro_rules! generate_tuple {
     ($name:ident, $fct_name:ident, $(($type_name:ident, $type_param:ident),)*) => {
@@ -12,14 +33,24 @@ macro_rules! generate_tuple {
             $($type_name : $type_param),*
         }
 
+        impl<$($type_param),*> $name<$($type_param,)*>
+            where $($type_param: Generator,)*
+        {
+            fn generate(&self, idx: Option<u64>, r: &mut R) -> ( $($type_param::Item),* ) {
+                ($( gen_sub(&self.$type_name, idx, r) ),*)
+            }
+
+            fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<( $($type_param::Item),* ), GenError> {
+                Ok(($( try_gen_sub(&self.$type_name, idx, r)? ),*))
+            }
+        }
+
         impl<$($type_param),*> Generator for $name<$($type_param,)*>
             where $($type_param: Generator,)*
         {
             type Item = ( $($type_param::Item),* );
 
-            fn gen(&self, r: &mut R) -> Self::Item {
-                ($( self.$type_name.gen(&mut r.sub()) ),*)
-            }
+            indexed_generation!(fallible);
         }
 
         #[doc = concat!(stringify!($name), " generator, figuratively a tuple generator of ", stringify!( ($($type_param, )*) ))]
@@ -35,6 +66,45 @@ generate_tuple! {Tuple4, tuple4, (a, A), (b, B), (c, C), (d, D),}
 generate_tuple! {Tuple5, tuple5, (a, A), (b, B), (c, C), (d, D), (e, E),}
 generate_tuple! {Tuple6, tuple6, (a, A), (b, B), (c, C), (d, D), (e, E), (f, F),}
 
+macro_rules! generate_tuple_and_flat {
+    ($name:ident => $next:ident, $(($type_name:ident, $type_param:ident),)* + ($new_name:ident, $new_param:ident)) => {
+        impl<$($type_param),*> $name<$($type_param),*> {
+            /// Extend this tuple generator with one more generator, generating flat
+            /// tuples instead of the nested tuples of `Generator::and`
+            pub fn and_flat<$new_param>(self, $new_name: $new_param) -> $next<$($type_param,)* $new_param> {
+                $next { $($type_name: self.$type_name,)* $new_name }
+            }
+        }
+    };
+}
+
+generate_tuple_and_flat! {Tuple2 => Tuple3, (a, A), (b, B), + (c, C)}
+generate_tuple_and_flat! {Tuple3 => Tuple4, (a, A), (b, B), (c, C), + (d, D)}
+generate_tuple_and_flat! {Tuple4 => Tuple5, (a, A), (b, B), (c, C), (d, D), + (e, E)}
+generate_tuple_and_flat! {Tuple5 => Tuple6, (a, A), (b, B), (c, C), (d, D), (e, E), + (f, F)}
+
+macro_rules! generate_tuple_into_struct {
+    ($name:ident => $product:ident, $(($type_name:ident, $type_param:ident),)*) => {
+        impl<$($type_param),*> $name<$($type_param),*> {
+            /// Map the items of this tuple generator through `mapper`, e.g. a struct
+            /// constructor, generating the same items as `map` without the intermediate tuple
+            pub fn into_struct<M, O>(self, mapper: M) -> $product<$($type_param,)* M>
+            where
+                $($type_param: Generator,)*
+                M: Fn($($type_param::Item),*) -> O + Clone,
+            {
+                $product { $($type_name: self.$type_name,)* mapper }
+            }
+        }
+    };
+}
+
+generate_tuple_into_struct! {Tuple2 => Product2, (a, A), (b, B),}
+generate_tuple_into_struct! {Tuple3 => Product3, (a, A), (b, B), (c, C),}
+generate_tuple_into_struct! {Tuple4 => Product4, (a, A), (b, B), (c, C), (d, D),}
+generate_tuple_into_struct! {Tuple5 => Product5, (a, A), (b, B), (c, C), (d, D), (e, E),}
+generate_tuple_into_struct! {Tuple6 => Product6, (a, A), (b, B), (c, C), (d, D), (e, E), (f, F),}
+
 macro_rules! generate_product {
     ($name:ident, $fct_name:ident, $(($type_name:ident, $type_param:ident),)*) => {
         #[doc = concat!(stringify!($name), " generator type , figuratively generate item of the form M",
@@ -47,6 +117,20 @@ macro_rules! generate_product {
             mapper: M,
         }
 
+        impl<$($type_param),*, M, O> $name<$($type_param),*, M>
+            where
+                $($type_param: Generator,)*
+                M: Fn($($type_param::Item),*) -> O + Clone,
+        {
+            fn generate(&self, idx: Option<u64>, r: &mut R) -> O {
+                (self.mapper)($( gen_sub(&self.$type_name, idx, r) , )*)
+            }
+
+            fn try_generate(&self, idx: Option<u64>, r: &mut R) -> Result<O, GenError> {
+                Ok((self.mapper)($( try_gen_sub(&self.$type_name, idx, r)? , )*))
+            }
+        }
+
         impl<$($type_param),*, M, O> Generator for $name<$($type_param),*, M>
             where
                 $($type_param: Generator,)*
@@ -54,9 +138,7 @@ macro_rules! generate_product {
         {
             type Item = O;
 
-            fn gen(&self, r: &mut R) -> Self::Item {
-                (self.mapper)($( self.$type_name.gen(&mut r.sub()) , )*)
-            }
+            indexed_generation!(fallible);
         }
 
         #[doc = concat!(stringify!($name), " generator, figuratively a product generator of M", stringify!( ($($type_param, )*) ))]
@@ -1,24 +1,45 @@
 //! string based generator types
 
 use super::super::rand::R;
-use super::base::{BoxGenerator, Generator};
+use super::base::{gen_at, indexed_generation, BoxGenerator, Generator, GeneratorInPlace};
+use super::combinators::{one_of_copy, Frequency};
 use super::numerical::range;
+use std::borrow::Cow;
 
 /// Generate String containing only ASCII characters
 pub struct AsciiString(BoxGenerator<usize>);
 
+impl AsciiString {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> String {
+        let sz = gen_at(&self.0, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        let mut chars_r = r.sub();
+        let mut out = Vec::with_capacity(sz);
+        let ascii_range = range(0x20..0x7f).map(|n| std::char::from_u32(n).unwrap());
+        for _ in 0..sz {
+            out.push(ascii_range.gen(&mut chars_r))
+        }
+        out.iter().collect()
+    }
+}
+
 impl Generator for AsciiString {
     type Item = String;
 
-    fn gen(&self, r: &mut R) -> Self::Item {
+    indexed_generation!();
+}
+
+impl GeneratorInPlace for AsciiString {
+    fn gen_into(&self, r: &mut R, out: &mut Self::Item) {
         let sz = self.0.gen(&mut r.sub());
+        let sz = r.bounded_size(sz);
         let mut chars_r = r.sub();
-        let mut out = Vec::with_capacity(sz);
+        out.clear();
+        out.reserve(sz);
         let ascii_range = range(0x20..0x7f).map(|n| std::char::from_u32(n).unwrap());
         for _ in 0..sz {
             out.push(ascii_range.gen(&mut chars_r))
         }
-        out.iter().collect()
     }
 }
 
@@ -38,17 +59,35 @@ pub fn ascii<SZ: Generator<Item = usize> + 'static>(size: SZ) -> AsciiString {
 /// specified by the second generator
 pub struct StringGenerator(BoxGenerator<usize>, BoxGenerator<char>);
 
+impl StringGenerator {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> String {
+        let sz = gen_at(&self.0, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        let mut chars_r = r.sub();
+        let mut out = Vec::with_capacity(sz);
+        for _ in 0..sz {
+            out.push(gen_at(&self.1, idx, &mut chars_r))
+        }
+        out.iter().collect()
+    }
+}
+
 impl Generator for StringGenerator {
     type Item = String;
 
-    fn gen(&self, r: &mut R) -> Self::Item {
+    indexed_generation!();
+}
+
+impl GeneratorInPlace for StringGenerator {
+    fn gen_into(&self, r: &mut R, out: &mut Self::Item) {
         let sz = self.0.gen(&mut r.sub());
+        let sz = r.bounded_size(sz);
         let mut chars_r = r.sub();
-        let mut out = Vec::with_capacity(sz);
+        out.clear();
+        out.reserve(sz);
         for _ in 0..sz {
             out.push(self.1.gen(&mut chars_r))
         }
-        out.iter().collect()
     }
 }
 
@@ -67,3 +106,446 @@ pub fn string<SZ: Generator<Item = usize> + 'static, C: Generator<Item = char> +
 ) -> StringGenerator {
     StringGenerator(size.into_boxed(), chars.into_boxed())
 }
+
+/// Characters starting an identifier
+const IDENT_START: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_";
+
+/// Characters continuing an identifier
+const IDENT_CONTINUE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_0123456789";
+
+/// Generate ASCII identifiers, valid in Rust and C
+pub struct Ident(BoxGenerator<usize>);
+
+impl Ident {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> String {
+        let sz = gen_at(&self.0, idx, &mut r.sub());
+        let sz = r.bounded_size(sz).max(1);
+        let mut chars_r = r.sub();
+        let mut out = String::with_capacity(sz);
+        // a lone underscore isn't an identifier in Rust
+        let start = if sz == 1 {
+            &IDENT_START[..IDENT_START.len() - 1]
+        } else {
+            IDENT_START
+        };
+        out.push(start[chars_r.num_range(0, start.len() - 1)] as char);
+        for _ in 1..sz {
+            out.push(IDENT_CONTINUE[chars_r.num_range(0, IDENT_CONTINUE.len() - 1)] as char)
+        }
+        out
+    }
+}
+
+impl Generator for Ident {
+    type Item = String;
+
+    indexed_generation!();
+}
+
+/// generate ASCII identifiers of size specified by the generator in parameter,
+/// starting with a letter or an underscore, and continuing with letters, digits
+/// and underscores
+///
+/// The identifiers are at least one character long, even when the size generated
+/// or bounded by the size parameter is 0. They may be keywords of the target
+/// language (e.g. `fn` or `int`), which can be filtered with `such_that`.
+///
+/// ```
+/// use smoke::generator::{string::ident, range};
+///
+/// let identifiers = ident(range(1..16));
+/// ```
+pub fn ident<SZ: Generator<Item = usize> + 'static>(size: SZ) -> Ident {
+    Ident(size.into_boxed())
+}
+
+/// Generate byte vectors of valid UTF-8, encoding a number of arbitrary
+/// unicode characters specified by the generator
+pub struct Utf8Bytes(BoxGenerator<usize>);
+
+impl Utf8Bytes {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Vec<u8> {
+        let sz = gen_at(&self.0, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        let mut chars_r = r.sub();
+        let s: String = (0..sz).map(|_| chars_r.codepoint()).collect();
+        s.into_bytes()
+    }
+}
+
+impl Generator for Utf8Bytes {
+    type Item = Vec<u8>;
+
+    indexed_generation!();
+}
+
+/// generate valid UTF-8 byte vectors, of a number of characters specified by the generator
+///
+/// ```
+/// use smoke::generator::{string::utf8_bytes, range};
+///
+/// let valid_utf8 = utf8_bytes(range(0..32));
+/// ```
+pub fn utf8_bytes<SZ: Generator<Item = usize> + 'static>(size: SZ) -> Utf8Bytes {
+    Utf8Bytes(size.into_boxed())
+}
+
+/// The known classes of invalid UTF-8 sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InvalidUtf8Class {
+    /// A character encoded with more bytes than necessary
+    Overlong,
+    /// A continuation byte without a leading byte
+    LoneContinuation,
+    /// A multi-byte sequence missing some of its continuation bytes
+    Truncated,
+    /// A surrogate code unit encoded as a 3 bytes sequence (CESU-8)
+    Surrogate,
+}
+
+const INVALID_UTF8_CLASSES: [InvalidUtf8Class; 4] = [
+    InvalidUtf8Class::Overlong,
+    InvalidUtf8Class::LoneContinuation,
+    InvalidUtf8Class::Truncated,
+    InvalidUtf8Class::Surrogate,
+];
+
+fn continuation(bits: u32) -> u8 {
+    0x80 | (bits & 0x3f) as u8
+}
+
+/// Generate an invalid UTF-8 sequence of the given class, which stays invalid
+/// when inserted at a character boundary of a valid UTF-8 sequence
+fn invalid_sequence(class: InvalidUtf8Class, r: &mut R) -> Vec<u8> {
+    match class {
+        InvalidUtf8Class::Overlong => match r.num_range(0u8, 2) {
+            0 => {
+                let c = r.num_range(0u32, 0x7f);
+                vec![0xc0 | (c >> 6) as u8, continuation(c)]
+            }
+            1 => {
+                let c = r.num_range(0u32, 0x7ff);
+                vec![0xe0, continuation(c >> 6), continuation(c)]
+            }
+            _ => {
+                let c = r.num_range(0u32, 0xffff);
+                vec![
+                    0xf0,
+                    continuation(c >> 12),
+                    continuation(c >> 6),
+                    continuation(c),
+                ]
+            }
+        },
+        InvalidUtf8Class::LoneContinuation => vec![r.num_range(0x80u8, 0xbf)],
+        InvalidUtf8Class::Truncated => {
+            let c = loop {
+                if let Some(c) = std::char::from_u32(r.num_range(0x80, char::MAX as u32)) {
+                    break c;
+                }
+            };
+            let mut buf = [0u8; 4];
+            let len = c.encode_utf8(&mut buf).len();
+            let kept = r.num_range(1, len - 1);
+            buf[..kept].to_vec()
+        }
+        InvalidUtf8Class::Surrogate => {
+            let u = r.num_range(0xd800u32, 0xdfff);
+            vec![
+                0xe0 | (u >> 12) as u8,
+                continuation(u >> 6),
+                continuation(u),
+            ]
+        }
+    }
+}
+
+/// Generate byte vectors of invalid UTF-8, made of a number of valid characters specified
+/// by the generator, and an invalid sequence inserted at an arbitrary character boundary
+pub struct InvalidUtf8(BoxGenerator<usize>);
+
+impl InvalidUtf8 {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Vec<u8> {
+        let sz = gen_at(&self.0, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        let mut chars_r = r.sub();
+        let valid: String = (0..sz).map(|_| chars_r.codepoint()).collect();
+        let mut invalid_r = r.sub();
+        let class = INVALID_UTF8_CLASSES[invalid_r.num_range(0, INVALID_UTF8_CLASSES.len() - 1)];
+        let sequence = invalid_sequence(class, &mut invalid_r);
+        let boundaries: Vec<usize> = valid
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(valid.len()))
+            .collect();
+        let at = boundaries[invalid_r.num_range(0, boundaries.len() - 1)];
+
+        let mut out = valid.into_bytes();
+        out.splice(at..at, sequence);
+        debug_assert!(std::str::from_utf8(&out).is_err());
+        out
+    }
+}
+
+impl Generator for InvalidUtf8 {
+    type Item = Vec<u8>;
+
+    indexed_generation!();
+}
+
+/// generate invalid UTF-8 byte vectors, around a number of valid characters specified by the generator
+///
+/// The invalid part is one of: an overlong encoding, a lone continuation byte,
+/// a truncated multi-byte sequence or a surrogate encoded as CESU-8.
+///
+/// ```
+/// use smoke::generator::{string::invalid_utf8, range};
+///
+/// let invalid = invalid_utf8(range(0..32));
+/// ```
+pub fn invalid_utf8<SZ: Generator<Item = usize> + 'static>(size: SZ) -> InvalidUtf8 {
+    InvalidUtf8(size.into_boxed())
+}
+
+/// generate arbitrary string of size specified by the first generator in parameter,
+/// each character coming from one of the weighted character classes
+///
+/// For example, strings of 90% letters and 10% spaces:
+///
+/// ```
+/// use smoke::generator::{string::mixed, range, Generator};
+///
+/// let sentences = mixed(
+///     range(0..64),
+///     vec![
+///         (9, range('a'..'z').into_boxed()),
+///         (1, range(' '..' ').into_boxed()),
+///     ],
+/// );
+/// ```
+///
+/// If the classes are empty then a runtime error is thrown
+pub fn mixed<SZ: Generator<Item = usize> + 'static>(
+    size: SZ,
+    classes: Vec<(usize, BoxGenerator<char>)>,
+) -> StringGenerator {
+    assert!(!classes.is_empty());
+    StringGenerator(size.into_boxed(), Frequency::new(classes).into_boxed())
+}
+
+const ALPHANUMERIC: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+const WHITESPACE_PUNCTUATION: &str = " \t\n\r!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// Bidirectional controls and zero-width characters, altering the rendering and
+/// the segmentation of the text without being visible
+const INVISIBLE: &str = "\u{200b}\u{200c}\u{200d}\u{200e}\u{200f}\u{202a}\u{202b}\u{202c}\u{202d}\u{202e}\u{2066}\u{2067}\u{2068}\u{2069}\u{2060}\u{feff}";
+
+fn chars_of(chars: &str) -> BoxGenerator<char> {
+    one_of_copy(&chars.chars().collect::<Vec<_>>()).into_boxed()
+}
+
+/// generate mostly textual strings of size specified by the generator in parameter,
+/// with characters 70% ASCII alphanumeric, 20% ASCII whitespace and punctuation,
+/// 9% multi-bytes and 1% control characters
+///
+/// ```
+/// use smoke::generator::{string::mostly_text, range};
+///
+/// let inputs = mostly_text(range(0..256));
+/// ```
+pub fn mostly_text<SZ: Generator<Item = usize> + 'static>(size: SZ) -> StringGenerator {
+    mixed(
+        size,
+        vec![
+            (70, chars_of(ALPHANUMERIC)),
+            (20, chars_of(WHITESPACE_PUNCTUATION)),
+            (7, range('\u{a0}'..'\u{d7ff}').into_boxed()),
+            (2, range('\u{1f300}'..'\u{1faff}').into_boxed()),
+            (1, range('\u{0}'..'\u{1f}').into_boxed()),
+        ],
+    )
+}
+
+/// generate strings hostile to text processing, of size specified by the generator
+/// in parameter: ASCII alphanumeric characters mixed with bidirectional controls,
+/// zero-width characters, combining marks, NULs, control characters and emojis
+///
+/// ```
+/// use smoke::generator::{string::hostile, range};
+///
+/// let inputs = hostile(range(0..64));
+/// ```
+pub fn hostile<SZ: Generator<Item = usize> + 'static>(size: SZ) -> StringGenerator {
+    mixed(
+        size,
+        vec![
+            (20, chars_of(ALPHANUMERIC)),
+            (25, chars_of(INVISIBLE)),
+            (25, range('\u{300}'..'\u{36f}').into_boxed()),
+            (10, range('\u{0}'..'\u{0}').into_boxed()),
+            (10, range('\u{1}'..'\u{1f}').into_boxed()),
+            (10, range('\u{1f300}'..'\u{1faff}').into_boxed()),
+        ],
+    )
+}
+
+/// Static strings used for the borrowed variant of `cow_str`
+const BORROWED_STRS: &[&str] = &["", " ", "a", "0", "smoke", "\u{0}", "\u{e9}", "\u{1f980}"];
+
+/// Generate `Cow<'static, str>`, either borrowed or owned
+#[derive(Clone)]
+pub struct CowStr<G> {
+    generator: G,
+}
+
+impl<G: Generator<Item = String>> CowStr<G> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Cow<'static, str> {
+        if r.bool() {
+            let idx = r.num_range(0, BORROWED_STRS.len() - 1);
+            Cow::Borrowed(BORROWED_STRS[idx])
+        } else {
+            Cow::Owned(gen_at(&self.generator, idx, r))
+        }
+    }
+}
+
+impl<G: Generator<Item = String>> Generator for CowStr<G> {
+    type Item = Cow<'static, str>;
+
+    indexed_generation!();
+}
+
+/// generate `Cow<'static, str>`, where both variants are equally likely
+///
+/// As a borrowed string must outlive the generated value, the `Borrowed` variant
+/// is chosen from a small table of static strings (including the empty string),
+/// and the `Owned` variant is generated by the string generator in parameter.
+///
+/// ```
+/// use smoke::generator::{string::{ascii, cow_str}, range};
+///
+/// let strings = cow_str(ascii(range(0..16)));
+/// ```
+pub fn cow_str<G: Generator<Item = String>>(generator: G) -> CowStr<G> {
+    CowStr { generator }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seed;
+
+    fn samples<G: Generator>(g: G) -> Vec<G::Item> {
+        let mut r = R::from_seed(Seed::from(0x07f8));
+        (0..500).map(|_| g.gen(&mut r)).collect()
+    }
+
+    #[test]
+    fn mostly_text_ratios() {
+        let chars: String = samples(mostly_text(range(100..100))).concat();
+        let count = |f: &dyn Fn(char) -> bool| chars.chars().filter(|c| f(*c)).count();
+        let total = chars.chars().count();
+        assert_eq!(total, 50_000);
+        let alphanumeric = count(&|c| c.is_ascii_alphanumeric());
+        let whitespace = count(&|c| WHITESPACE_PUNCTUATION.contains(c));
+        let multibyte = count(&|c| c.len_utf8() > 1);
+        let control = count(&|c| c.is_ascii_control() && !WHITESPACE_PUNCTUATION.contains(c));
+        // expected percentages, within ~5 standard deviations
+        assert!((34_000..36_000).contains(&alphanumeric), "{}", alphanumeric);
+        assert!((9_550..10_450).contains(&whitespace), "{}", whitespace);
+        assert!((4_180..4_820).contains(&multibyte), "{}", multibyte);
+        assert!((380..620).contains(&control), "{}", control);
+    }
+
+    #[test]
+    fn hostile_valid() {
+        let strings = samples(hostile(range(0..64)));
+        for s in strings.iter() {
+            assert_eq!(std::str::from_utf8(s.as_bytes()), Ok(s.as_str()));
+        }
+        let all: String = strings.concat();
+        assert!(all.contains('\u{0}'));
+        assert!(all.contains('\u{200d}'));
+        assert!(all.contains('\u{202e}'));
+        assert!(all.contains(|c| ('\u{300}'..='\u{36f}').contains(&c)));
+    }
+
+    #[test]
+    fn ident_valid() {
+        let idents = samples(ident(range(0..12)));
+        for id in idents.iter() {
+            let mut chars = id.chars();
+            let first = chars.next().expect("identifiers aren't empty");
+            assert!(first.is_ascii_alphabetic() || first == '_', "{}", id);
+            assert!(
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "{}",
+                id
+            );
+            assert_ne!(id, "_");
+        }
+        assert!(idents.iter().any(|id| id.starts_with('_')));
+        assert!(idents
+            .iter()
+            .any(|id| id.contains(|c: char| c.is_ascii_digit())));
+    }
+
+    #[test]
+    fn utf8_bytes_valid() {
+        for v in samples(utf8_bytes(range(0..16))) {
+            assert!(std::str::from_utf8(&v).is_ok());
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_invalid() {
+        for v in samples(invalid_utf8(range(0..16))) {
+            assert!(std::str::from_utf8(&v).is_err());
+        }
+    }
+
+    fn check_class(class: InvalidUtf8Class) {
+        let mut r = R::from_seed(Seed::from(0x07f8));
+        for _ in 0..500 {
+            let seq = invalid_sequence(class, &mut r);
+            for (prefix, suffix) in [("", ""), ("a", "b"), ("\u{e9}", "\u{1f600}")] {
+                let mut v = prefix.as_bytes().to_vec();
+                v.extend_from_slice(&seq);
+                v.extend_from_slice(suffix.as_bytes());
+                assert!(std::str::from_utf8(&v).is_err(), "{:?} {:x?}", class, v);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_overlong() {
+        check_class(InvalidUtf8Class::Overlong)
+    }
+
+    #[test]
+    fn invalid_lone_continuation() {
+        check_class(InvalidUtf8Class::LoneContinuation)
+    }
+
+    #[test]
+    fn invalid_truncated() {
+        check_class(InvalidUtf8Class::Truncated)
+    }
+
+    #[test]
+    fn invalid_surrogate() {
+        check_class(InvalidUtf8Class::Surrogate)
+    }
+
+    #[test]
+    fn cow_str_variants() {
+        let strings = samples(cow_str(ascii(range(1..8))));
+        assert!(strings
+            .iter()
+            .any(|s| matches!(s, Cow::Borrowed(b) if BORROWED_STRS.contains(b))));
+        assert!(strings
+            .iter()
+            .any(|s| matches!(s, Cow::Owned(o) if !o.is_empty())));
+    }
+}
@@ -0,0 +1,1420 @@
+//! text generators drawn from embedded word and name lists
+//!
+//! Purely random strings never look like real words, names or sentences, which
+//! matters when testing search or ranking code. The lists are compiled into the
+//! crate, and the generated text only depends on the seed.
+//!
+//! This module is only available with the `text` feature (enabled by default), so
+//! that the size sensitive builds can exclude the embedded lists.
+
+use super::super::rand::R;
+use super::base::{gen_at, indexed_generation, Generator};
+
+fn pick(list: &'static [&'static str], r: &mut R) -> &'static str {
+    list[r.num_range(0, list.len() - 1)]
+}
+
+/// `count` common english words joined by single spaces
+fn join_words(count: usize, r: &mut R) -> String {
+    let words: Vec<&str> = (0..count).map(|_| pick(WORDS, r)).collect();
+    words.join(" ")
+}
+
+/// Generate common english words
+#[derive(Debug, Clone, Copy)]
+pub struct Word;
+
+impl Generator for Word {
+    type Item = &'static str;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        pick(WORDS, r)
+    }
+}
+
+/// Generate a number of words joined by spaces
+#[derive(Clone)]
+pub struct Words<SZ> {
+    count: SZ,
+}
+
+impl<SZ: Generator<Item = usize>> Words<SZ> {
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> String {
+        let sz = gen_at(&self.count, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        join_words(sz, &mut r.sub())
+    }
+}
+
+impl<SZ: Generator<Item = usize>> Generator for Words<SZ> {
+    type Item = String;
+
+    indexed_generation!();
+}
+
+/// Generate sentences
+#[derive(Debug, Clone, Copy)]
+pub struct Sentence;
+
+impl Generator for Sentence {
+    type Item = String;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let count = super::numerical::range(3..12).gen(&mut r.sub());
+        // the size parameter bounds the sentences down to their minimum of 3 words
+        let count = r.bounded_size(count).max(3);
+        let mut sentence = join_words(count, &mut r.sub());
+        if let Some(first) = sentence.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        sentence.push('.');
+        sentence
+    }
+}
+
+/// Generate full names
+#[derive(Debug, Clone, Copy)]
+pub struct Name;
+
+impl Generator for Name {
+    type Item = String;
+
+    fn gen(&self, r: &mut R) -> Self::Item {
+        let first = pick(FIRST_NAMES, r);
+        let last = pick(LAST_NAMES, r);
+        format!("{} {}", first, last)
+    }
+}
+
+/// generate words from a list of about a thousand common english words
+///
+/// ```
+/// use smoke::generator::text::word;
+///
+/// let search_terms = word();
+/// ```
+pub fn word() -> Word {
+    Word
+}
+
+/// generate a number of words specified by the generator in parameter, joined
+/// by single spaces
+///
+/// ```
+/// use smoke::generator::{text::words, range};
+///
+/// let titles = words(range(1..6));
+/// ```
+pub fn words<SZ: Generator<Item = usize>>(count: SZ) -> Words<SZ> {
+    Words { count }
+}
+
+/// generate sentences of 3 to 12 words, capitalized and ending with a period
+///
+/// ```
+/// use smoke::generator::text::sentence;
+///
+/// let descriptions = sentence();
+/// ```
+pub fn sentence() -> Sentence {
+    Sentence
+}
+
+/// generate full names, made of a first name and a last name separated by a space
+///
+/// ```
+/// use smoke::generator::text::name;
+///
+/// let customers = name();
+/// ```
+pub fn name() -> Name {
+    Name
+}
+
+/// First names of the generated names
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Alan", "Alice", "Amara", "Andrea", "Anna", "Arjun", "Ben", "Carlos", "Chen", "Chloe",
+    "Daniel", "David", "Elena", "Emma", "Fatima", "Felix", "Grace", "Hana", "Hugo", "Ibrahim",
+    "Ines", "Ivan", "Jack", "James", "Julia", "Kai", "Karin", "Lars", "Laura", "Leo", "Lina",
+    "Lucas", "Maria", "Mateo", "Maya", "Mei", "Mohammed", "Nadia", "Noah", "Nora", "Olga", "Omar",
+    "Oscar", "Paul", "Priya", "Rosa", "Ryan", "Sara", "Sofia", "Tariq", "Thomas", "Uma", "Victor",
+    "Wei", "Yara", "Yusuf", "Zoe",
+];
+
+/// Last names of the generated names
+const LAST_NAMES: &[&str] = &[
+    "Abe", "Adams", "Ahmed", "Alvarez", "Andersen", "Bauer", "Brown", "Chen", "Costa", "Cruz",
+    "Dubois", "Evans", "Fischer", "Garcia", "Gupta", "Hansen", "Hoffmann", "Ito", "Ivanova",
+    "Jensen", "Johnson", "Kim", "Kowalski", "Kumar", "Larsen", "Lee", "Lopez", "Martin", "Meyer",
+    "Moreau", "Muller", "Nakamura", "Nguyen", "Novak", "Okafor", "Olsen", "Park", "Patel",
+    "Petrov", "Rossi", "Santos", "Schmidt", "Silva", "Singh", "Smith", "Suzuki", "Tanaka",
+    "Taylor", "Thompson", "Wagner", "Walker", "Wang", "Williams", "Wilson", "Yamamoto", "Young",
+    "Zhang",
+];
+
+/// Common english words
+const WORDS: &[&str] = &[
+    "a",
+    "able",
+    "about",
+    "above",
+    "accept",
+    "across",
+    "act",
+    "action",
+    "active",
+    "actually",
+    "add",
+    "address",
+    "admit",
+    "adult",
+    "affect",
+    "after",
+    "again",
+    "against",
+    "age",
+    "agency",
+    "agent",
+    "ago",
+    "agree",
+    "ahead",
+    "air",
+    "all",
+    "allow",
+    "almost",
+    "alone",
+    "along",
+    "already",
+    "also",
+    "although",
+    "always",
+    "among",
+    "amount",
+    "analysis",
+    "anchor",
+    "and",
+    "animal",
+    "another",
+    "answer",
+    "any",
+    "anyone",
+    "anything",
+    "appear",
+    "apple",
+    "apply",
+    "approach",
+    "area",
+    "argue",
+    "arm",
+    "around",
+    "arrive",
+    "arrow",
+    "art",
+    "article",
+    "artist",
+    "as",
+    "ask",
+    "assume",
+    "at",
+    "attack",
+    "attention",
+    "attorney",
+    "audience",
+    "author",
+    "authority",
+    "autumn",
+    "available",
+    "avoid",
+    "away",
+    "baby",
+    "back",
+    "bad",
+    "badge",
+    "bag",
+    "bakery",
+    "ball",
+    "balloon",
+    "bamboo",
+    "bank",
+    "banner",
+    "bar",
+    "barrel",
+    "base",
+    "basket",
+    "be",
+    "beacon",
+    "beard",
+    "beat",
+    "beautiful",
+    "because",
+    "become",
+    "bed",
+    "bee",
+    "before",
+    "begin",
+    "behavior",
+    "behind",
+    "believe",
+    "bell",
+    "bench",
+    "benefit",
+    "berry",
+    "best",
+    "better",
+    "between",
+    "beyond",
+    "bicycle",
+    "big",
+    "bill",
+    "billion",
+    "bit",
+    "black",
+    "blanket",
+    "blood",
+    "blossom",
+    "blue",
+    "board",
+    "boat",
+    "body",
+    "bone",
+    "book",
+    "border",
+    "born",
+    "both",
+    "bottle",
+    "box",
+    "boy",
+    "bracket",
+    "branch",
+    "brass",
+    "bread",
+    "break",
+    "breeze",
+    "brick",
+    "bridge",
+    "bring",
+    "bronze",
+    "brother",
+    "brush",
+    "bubble",
+    "bucket",
+    "budget",
+    "build",
+    "building",
+    "business",
+    "but",
+    "butter",
+    "button",
+    "buy",
+    "by",
+    "cabin",
+    "cable",
+    "cactus",
+    "cake",
+    "call",
+    "camera",
+    "campaign",
+    "can",
+    "canal",
+    "cancer",
+    "candidate",
+    "candle",
+    "canvas",
+    "canyon",
+    "capital",
+    "car",
+    "carbon",
+    "card",
+    "care",
+    "career",
+    "carpet",
+    "carry",
+    "case",
+    "castle",
+    "catch",
+    "cause",
+    "cedar",
+    "cell",
+    "center",
+    "central",
+    "century",
+    "certain",
+    "certainly",
+    "chair",
+    "chalk",
+    "challenge",
+    "chance",
+    "change",
+    "channel",
+    "character",
+    "charge",
+    "check",
+    "cherry",
+    "child",
+    "chimney",
+    "choice",
+    "choose",
+    "church",
+    "circle",
+    "citizen",
+    "city",
+    "civil",
+    "claim",
+    "class",
+    "clear",
+    "clearly",
+    "cliff",
+    "clock",
+    "close",
+    "cloud",
+    "clover",
+    "coach",
+    "coffee",
+    "coin",
+    "cold",
+    "collection",
+    "college",
+    "color",
+    "come",
+    "comet",
+    "commercial",
+    "common",
+    "community",
+    "company",
+    "compare",
+    "computer",
+    "concern",
+    "condition",
+    "conference",
+    "consider",
+    "consumer",
+    "contain",
+    "continue",
+    "control",
+    "copper",
+    "coral",
+    "cost",
+    "cotton",
+    "could",
+    "country",
+    "couple",
+    "course",
+    "court",
+    "cover",
+    "crane",
+    "crater",
+    "create",
+    "crime",
+    "crystal",
+    "cultural",
+    "culture",
+    "cup",
+    "current",
+    "curtain",
+    "cushion",
+    "customer",
+    "cut",
+    "daisy",
+    "dark",
+    "data",
+    "daughter",
+    "dawn",
+    "day",
+    "dead",
+    "deal",
+    "death",
+    "debate",
+    "decade",
+    "decide",
+    "decision",
+    "deep",
+    "defense",
+    "degree",
+    "delta",
+    "describe",
+    "desert",
+    "design",
+    "despite",
+    "detail",
+    "determine",
+    "develop",
+    "development",
+    "diamond",
+    "die",
+    "difference",
+    "different",
+    "difficult",
+    "dinner",
+    "direction",
+    "director",
+    "discover",
+    "discuss",
+    "discussion",
+    "disease",
+    "do",
+    "doctor",
+    "dog",
+    "dolphin",
+    "door",
+    "down",
+    "dragon",
+    "draw",
+    "dream",
+    "drive",
+    "drop",
+    "drug",
+    "drum",
+    "during",
+    "each",
+    "eagle",
+    "early",
+    "east",
+    "easy",
+    "eat",
+    "echo",
+    "economic",
+    "economy",
+    "edge",
+    "education",
+    "effect",
+    "effort",
+    "eight",
+    "either",
+    "election",
+    "else",
+    "employee",
+    "end",
+    "energy",
+    "engine",
+    "enjoy",
+    "enough",
+    "enter",
+    "entire",
+    "environment",
+    "environmental",
+    "especially",
+    "establish",
+    "even",
+    "evening",
+    "event",
+    "ever",
+    "every",
+    "everybody",
+    "everyone",
+    "everything",
+    "evidence",
+    "exactly",
+    "example",
+    "executive",
+    "exist",
+    "expect",
+    "experience",
+    "expert",
+    "explain",
+    "eye",
+    "face",
+    "fact",
+    "factor",
+    "fail",
+    "falcon",
+    "fall",
+    "family",
+    "far",
+    "fast",
+    "father",
+    "fear",
+    "feather",
+    "federal",
+    "feel",
+    "feeling",
+    "fence",
+    "fern",
+    "ferry",
+    "few",
+    "field",
+    "fight",
+    "figure",
+    "fill",
+    "film",
+    "final",
+    "finally",
+    "financial",
+    "find",
+    "fine",
+    "finger",
+    "finish",
+    "fire",
+    "firm",
+    "first",
+    "fish",
+    "five",
+    "flame",
+    "floor",
+    "flute",
+    "fly",
+    "focus",
+    "follow",
+    "food",
+    "foot",
+    "for",
+    "force",
+    "foreign",
+    "forest",
+    "forget",
+    "form",
+    "former",
+    "forward",
+    "fossil",
+    "fountain",
+    "four",
+    "fox",
+    "free",
+    "friend",
+    "from",
+    "front",
+    "frost",
+    "full",
+    "fund",
+    "future",
+    "galaxy",
+    "game",
+    "garden",
+    "garnet",
+    "gas",
+    "gate",
+    "general",
+    "generation",
+    "get",
+    "giant",
+    "ginger",
+    "girl",
+    "give",
+    "glacier",
+    "glass",
+    "globe",
+    "go",
+    "goal",
+    "good",
+    "government",
+    "granite",
+    "grape",
+    "gravel",
+    "great",
+    "green",
+    "ground",
+    "group",
+    "grow",
+    "growth",
+    "guess",
+    "gun",
+    "guy",
+    "hair",
+    "half",
+    "hand",
+    "hang",
+    "happen",
+    "happy",
+    "harbor",
+    "hard",
+    "harvest",
+    "have",
+    "hawk",
+    "hazel",
+    "he",
+    "head",
+    "health",
+    "hear",
+    "heart",
+    "heat",
+    "heavy",
+    "helmet",
+    "help",
+    "her",
+    "here",
+    "herself",
+    "high",
+    "him",
+    "himself",
+    "his",
+    "history",
+    "hit",
+    "hold",
+    "home",
+    "honey",
+    "hope",
+    "horizon",
+    "hospital",
+    "hot",
+    "hotel",
+    "hour",
+    "house",
+    "how",
+    "however",
+    "huge",
+    "human",
+    "hundred",
+    "husband",
+    "idea",
+    "identify",
+    "if",
+    "image",
+    "imagine",
+    "impact",
+    "important",
+    "improve",
+    "in",
+    "include",
+    "including",
+    "increase",
+    "indeed",
+    "indicate",
+    "individual",
+    "industry",
+    "information",
+    "inside",
+    "instead",
+    "institution",
+    "interest",
+    "interesting",
+    "international",
+    "interview",
+    "into",
+    "investment",
+    "involve",
+    "island",
+    "issue",
+    "it",
+    "item",
+    "its",
+    "itself",
+    "ivory",
+    "jacket",
+    "jelly",
+    "jewel",
+    "job",
+    "join",
+    "jungle",
+    "just",
+    "keep",
+    "kettle",
+    "key",
+    "kid",
+    "kill",
+    "kind",
+    "kitchen",
+    "kite",
+    "know",
+    "knowledge",
+    "ladder",
+    "lagoon",
+    "land",
+    "language",
+    "lantern",
+    "large",
+    "last",
+    "late",
+    "later",
+    "laugh",
+    "lava",
+    "law",
+    "lawyer",
+    "lay",
+    "lead",
+    "leader",
+    "learn",
+    "least",
+    "leave",
+    "left",
+    "leg",
+    "legal",
+    "lemon",
+    "less",
+    "let",
+    "letter",
+    "level",
+    "lie",
+    "life",
+    "light",
+    "like",
+    "likely",
+    "lilac",
+    "line",
+    "lion",
+    "list",
+    "listen",
+    "little",
+    "live",
+    "lizard",
+    "lobster",
+    "local",
+    "long",
+    "look",
+    "lose",
+    "loss",
+    "lot",
+    "love",
+    "low",
+    "machine",
+    "magazine",
+    "magnet",
+    "main",
+    "maintain",
+    "major",
+    "majority",
+    "make",
+    "man",
+    "manage",
+    "management",
+    "manager",
+    "many",
+    "maple",
+    "marble",
+    "market",
+    "marriage",
+    "material",
+    "matter",
+    "may",
+    "maybe",
+    "me",
+    "meadow",
+    "mean",
+    "measure",
+    "media",
+    "medical",
+    "meet",
+    "meeting",
+    "melon",
+    "member",
+    "memory",
+    "mention",
+    "message",
+    "meteor",
+    "method",
+    "middle",
+    "might",
+    "military",
+    "million",
+    "mind",
+    "minute",
+    "mirror",
+    "miss",
+    "mission",
+    "mist",
+    "model",
+    "modern",
+    "moment",
+    "money",
+    "month",
+    "moon",
+    "more",
+    "morning",
+    "moss",
+    "most",
+    "mother",
+    "mountain",
+    "mouth",
+    "move",
+    "movement",
+    "movie",
+    "much",
+    "music",
+    "must",
+    "my",
+    "myself",
+    "name",
+    "nation",
+    "national",
+    "natural",
+    "nature",
+    "near",
+    "nearly",
+    "necessary",
+    "need",
+    "needle",
+    "nest",
+    "network",
+    "never",
+    "new",
+    "news",
+    "newspaper",
+    "next",
+    "nice",
+    "nickel",
+    "night",
+    "no",
+    "none",
+    "nor",
+    "north",
+    "not",
+    "note",
+    "nothing",
+    "notice",
+    "now",
+    "number",
+    "oak",
+    "oasis",
+    "occur",
+    "ocean",
+    "of",
+    "off",
+    "offer",
+    "office",
+    "officer",
+    "official",
+    "often",
+    "oh",
+    "oil",
+    "ok",
+    "old",
+    "olive",
+    "on",
+    "once",
+    "one",
+    "only",
+    "onto",
+    "open",
+    "operation",
+    "opportunity",
+    "option",
+    "or",
+    "orange",
+    "orbit",
+    "orchid",
+    "order",
+    "organization",
+    "other",
+    "others",
+    "otter",
+    "our",
+    "out",
+    "outside",
+    "over",
+    "owl",
+    "own",
+    "owner",
+    "paddle",
+    "page",
+    "pain",
+    "painting",
+    "palace",
+    "panda",
+    "paper",
+    "parent",
+    "parrot",
+    "part",
+    "participant",
+    "particular",
+    "particularly",
+    "partner",
+    "party",
+    "pass",
+    "past",
+    "patient",
+    "pattern",
+    "pay",
+    "peace",
+    "pearl",
+    "pebble",
+    "pencil",
+    "people",
+    "pepper",
+    "per",
+    "perform",
+    "performance",
+    "perhaps",
+    "period",
+    "person",
+    "personal",
+    "phone",
+    "physical",
+    "piano",
+    "pick",
+    "picture",
+    "piece",
+    "pillow",
+    "pine",
+    "place",
+    "plan",
+    "planet",
+    "plant",
+    "play",
+    "player",
+    "plum",
+    "pocket",
+    "point",
+    "police",
+    "policy",
+    "political",
+    "politics",
+    "pond",
+    "poor",
+    "poppy",
+    "popular",
+    "population",
+    "position",
+    "positive",
+    "possible",
+    "power",
+    "practice",
+    "prepare",
+    "present",
+    "president",
+    "pressure",
+    "pretty",
+    "prevent",
+    "price",
+    "prism",
+    "private",
+    "probably",
+    "problem",
+    "process",
+    "produce",
+    "product",
+    "production",
+    "professional",
+    "professor",
+    "program",
+    "project",
+    "property",
+    "protect",
+    "prove",
+    "provide",
+    "public",
+    "pull",
+    "purpose",
+    "push",
+    "put",
+    "puzzle",
+    "quality",
+    "quartz",
+    "question",
+    "quickly",
+    "quite",
+    "rabbit",
+    "race",
+    "radio",
+    "rain",
+    "raise",
+    "range",
+    "rate",
+    "rather",
+    "raven",
+    "reach",
+    "read",
+    "ready",
+    "real",
+    "reality",
+    "realize",
+    "really",
+    "reason",
+    "receive",
+    "recent",
+    "recently",
+    "recognize",
+    "record",
+    "red",
+    "reduce",
+    "reef",
+    "reflect",
+    "region",
+    "relate",
+    "relationship",
+    "religious",
+    "remain",
+    "remember",
+    "remove",
+    "report",
+    "represent",
+    "require",
+    "research",
+    "resource",
+    "respond",
+    "response",
+    "responsibility",
+    "rest",
+    "result",
+    "return",
+    "reveal",
+    "ribbon",
+    "rich",
+    "right",
+    "rise",
+    "risk",
+    "river",
+    "road",
+    "robin",
+    "rock",
+    "rocket",
+    "role",
+    "room",
+    "rose",
+    "ruby",
+    "rule",
+    "run",
+    "saddle",
+    "safe",
+    "sail",
+    "salmon",
+    "same",
+    "sand",
+    "satellite",
+    "save",
+    "say",
+    "scarf",
+    "scene",
+    "school",
+    "science",
+    "scientist",
+    "score",
+    "sea",
+    "season",
+    "seat",
+    "second",
+    "section",
+    "security",
+    "see",
+    "seek",
+    "seem",
+    "sell",
+    "send",
+    "senior",
+    "sense",
+    "series",
+    "serious",
+    "serve",
+    "service",
+    "set",
+    "seven",
+    "several",
+    "shadow",
+    "shake",
+    "share",
+    "she",
+    "shell",
+    "ship",
+    "shoot",
+    "short",
+    "shot",
+    "should",
+    "shoulder",
+    "show",
+    "side",
+    "sign",
+    "significant",
+    "silk",
+    "silver",
+    "similar",
+    "simple",
+    "simply",
+    "since",
+    "sing",
+    "single",
+    "sister",
+    "sit",
+    "site",
+    "situation",
+    "six",
+    "size",
+    "skill",
+    "skin",
+    "sky",
+    "small",
+    "smile",
+    "snow",
+    "so",
+    "social",
+    "society",
+    "socket",
+    "soldier",
+    "some",
+    "somebody",
+    "someone",
+    "something",
+    "sometimes",
+    "son",
+    "song",
+    "soon",
+    "sort",
+    "sound",
+    "source",
+    "south",
+    "southern",
+    "space",
+    "spark",
+    "speak",
+    "special",
+    "specific",
+    "speech",
+    "spend",
+    "sphere",
+    "spider",
+    "spiral",
+    "sponge",
+    "sport",
+    "spring",
+    "squirrel",
+    "staff",
+    "stage",
+    "stand",
+    "standard",
+    "star",
+    "start",
+    "state",
+    "statement",
+    "station",
+    "stay",
+    "step",
+    "still",
+    "stock",
+    "stone",
+    "stop",
+    "store",
+    "storm",
+    "story",
+    "strategy",
+    "stream",
+    "street",
+    "strong",
+    "structure",
+    "student",
+    "study",
+    "stuff",
+    "style",
+    "subject",
+    "success",
+    "successful",
+    "such",
+    "suddenly",
+    "suffer",
+    "sugar",
+    "suggest",
+    "summer",
+    "summit",
+    "sun",
+    "support",
+    "sure",
+    "surface",
+    "swan",
+    "system",
+    "table",
+    "take",
+    "talk",
+    "task",
+    "tax",
+    "teach",
+    "teacher",
+    "team",
+    "technology",
+    "television",
+    "tell",
+    "ten",
+    "tend",
+    "term",
+    "test",
+    "than",
+    "thank",
+    "that",
+    "the",
+    "their",
+    "them",
+    "themselves",
+    "then",
+    "theory",
+    "there",
+    "these",
+    "they",
+    "thing",
+    "think",
+    "third",
+    "this",
+    "those",
+    "though",
+    "thought",
+    "thousand",
+    "threat",
+    "three",
+    "through",
+    "throughout",
+    "throw",
+    "thunder",
+    "thus",
+    "tiger",
+    "timber",
+    "time",
+    "to",
+    "today",
+    "together",
+    "tomato",
+    "tonight",
+    "too",
+    "top",
+    "total",
+    "tough",
+    "toward",
+    "tower",
+    "town",
+    "trade",
+    "traditional",
+    "trail",
+    "training",
+    "travel",
+    "treat",
+    "treatment",
+    "tree",
+    "trial",
+    "trip",
+    "trouble",
+    "true",
+    "truth",
+    "try",
+    "tulip",
+    "tunnel",
+    "turn",
+    "turtle",
+    "two",
+    "type",
+    "under",
+    "understand",
+    "unit",
+    "until",
+    "up",
+    "upon",
+    "us",
+    "use",
+    "usually",
+    "valley",
+    "value",
+    "various",
+    "velvet",
+    "very",
+    "victim",
+    "view",
+    "violence",
+    "violet",
+    "visit",
+    "voice",
+    "volcano",
+    "vote",
+    "wagon",
+    "wait",
+    "walk",
+    "wall",
+    "walnut",
+    "want",
+    "war",
+    "watch",
+    "water",
+    "wave",
+    "way",
+    "we",
+    "weapon",
+    "wear",
+    "week",
+    "weight",
+    "well",
+    "west",
+    "western",
+    "whale",
+    "what",
+    "whatever",
+    "wheat",
+    "wheel",
+    "when",
+    "where",
+    "whether",
+    "which",
+    "while",
+    "white",
+    "who",
+    "whole",
+    "whom",
+    "whose",
+    "why",
+    "wide",
+    "wife",
+    "will",
+    "willow",
+    "win",
+    "wind",
+    "window",
+    "wish",
+    "with",
+    "within",
+    "without",
+    "wolf",
+    "woman",
+    "wonder",
+    "wool",
+    "word",
+    "work",
+    "worker",
+    "world",
+    "worry",
+    "would",
+    "write",
+    "writer",
+    "wrong",
+    "yard",
+    "yeah",
+    "year",
+    "yes",
+    "yet",
+    "you",
+    "young",
+    "your",
+    "yourself",
+    "zebra",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::range;
+    use crate::Seed;
+
+    fn samples<G: Generator>(g: G) -> Vec<G::Item> {
+        let mut r = R::from_seed(Seed::from(0x7e47));
+        (0..500).map(|_| g.gen(&mut r)).collect()
+    }
+
+    #[test]
+    fn lists_valid() {
+        for list in [WORDS, FIRST_NAMES, LAST_NAMES] {
+            assert!(list.iter().all(|w| !w.is_empty() && !w.contains(' ')));
+        }
+        assert!(WORDS.len() >= 1000);
+    }
+
+    #[test]
+    fn words_from_list() {
+        for s in samples(words(range(0..6))) {
+            assert!(s.split(' ').count() <= 6);
+            assert!(
+                s.is_empty() || s.split(' ').all(|w| WORDS.contains(&w)),
+                "{}",
+                s
+            );
+        }
+        assert_eq!(samples(word()), samples(word()));
+    }
+
+    #[test]
+    fn sentences_and_names() {
+        for s in samples(sentence()) {
+            assert!(s.ends_with('.'));
+            assert!(s.starts_with(|c: char| c.is_ascii_uppercase()), "{}", s);
+            let nb_words = s.split(' ').count();
+            assert!((3..=12).contains(&nb_words), "{}", s);
+        }
+        let mut r = R::from_seed(Seed::from(0x7e47));
+        r.set_size(Some(1));
+        for _ in 0..100 {
+            let s = sentence().gen(&mut r);
+            assert_eq!(s.split(' ').count(), 3, "{}", s);
+        }
+        for n in samples(name()) {
+            let (first, last) = n.split_once(' ').unwrap();
+            assert!(FIRST_NAMES.contains(&first) && LAST_NAMES.contains(&last));
+        }
+    }
+}
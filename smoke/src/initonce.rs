@@ -27,34 +27,44 @@ impl<T> InitOnce<T> {
 
     /// Load a value from the structure. if the structure is not initialized, then
     /// f is called once to compute the value and initialize the structure.
+    ///
+    /// If f panics, the structure is left uninitialized, and the next load calls
+    /// its own function again.
     pub fn load<F>(&self, f: F) -> &T
     where
         F: FnOnce() -> T,
     {
-        let status = self
-            .status
-            .compare_exchange(
-                STATUS_UNINIT,
-                STATUS_INITING,
-                Ordering::SeqCst,
-                Ordering::SeqCst,
-            )
-            .unwrap_or_else(|st| st);
-        if status == STATUS_UNINIT {
-            // call F to write to cell and set the status to done
-            let value = f();
-            let cp = &self.content as *const UnsafeCell<MaybeUninit<T>> as *const MaybeUninit<T>
-                as *mut MaybeUninit<T>;
-            // write to the cell
-            unsafe {
-                let cp_ref = &mut *cp;
-                cp_ref.as_mut_ptr().write(value)
-            }
-            self.status.store(STATUS_DONE, Ordering::SeqCst);
-        } else if status == STATUS_INITING {
-            // wait to be done
-            while self.status.load(Ordering::SeqCst) != STATUS_DONE {
-                spin_loop()
+        loop {
+            let status = self
+                .status
+                .compare_exchange(
+                    STATUS_UNINIT,
+                    STATUS_INITING,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .unwrap_or_else(|st| st);
+            if status == STATUS_UNINIT {
+                // reset the status if F panics, so that the waiters don't spin forever
+                let guard = ResetOnUnwind(&self.status);
+                // call F to write to cell and set the status to done
+                let value = f();
+                core::mem::forget(guard);
+                let cp = self.content.get();
+                // write to the cell
+                unsafe {
+                    let cp_ref = &mut *cp;
+                    cp_ref.as_mut_ptr().write(value)
+                }
+                self.status.store(STATUS_DONE, Ordering::SeqCst);
+                break;
+            } else if status == STATUS_INITING {
+                // wait to be done, or to be reset by a panicking initialization
+                while self.status.load(Ordering::SeqCst) == STATUS_INITING {
+                    spin_loop()
+                }
+            } else {
+                break;
             }
         }
 
@@ -65,3 +75,19 @@ impl<T> InitOnce<T> {
         }
     }
 }
+
+struct ResetOnUnwind<'a>(&'a AtomicUsize);
+
+impl Drop for ResetOnUnwind<'_> {
+    fn drop(&mut self) {
+        self.0.store(STATUS_UNINIT, Ordering::SeqCst);
+    }
+}
+
+impl<T> Drop for InitOnce<T> {
+    fn drop(&mut self) {
+        if *self.status.get_mut() == STATUS_DONE {
+            unsafe { self.content.get_mut().assume_init_drop() }
+        }
+    }
+}
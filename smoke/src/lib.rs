@@ -10,15 +10,27 @@
 //!
 //! The tests and generator frameworks can be used independently
 
+mod config;
+#[cfg(feature = "async")]
+pub mod executor;
 pub mod generator;
+pub mod model;
 pub mod property;
 mod rand;
+pub mod repro;
 mod run;
+pub mod shrink;
 pub mod ux;
 
 mod initonce;
 
-pub use generator::Generator;
+pub use config::{ConfigError, InvalidVar, RunConfig};
+pub use generator::{Generator, GeneratorInPlace};
 pub use property::Property;
-pub use rand::{NumPrimitive, Seed, R};
-pub use run::{forall, run, Context, Ensure, Testable};
+pub use rand::{NumPrimitive, Seed, GENERATION_VERSION, R};
+#[cfg(feature = "async")]
+pub use run::EnsureAsync;
+pub use run::{
+    forall, run, Context, Ensure, EnsureInPlace, EnsureIndexed, Heartbeat, SizeStrategy, Testable,
+};
+pub use shrink::Shrink;
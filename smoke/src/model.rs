@@ -0,0 +1,479 @@
+//! Model based testing of stateful systems
+//!
+//! A `StateMachine` describes the commands that can be applied to a system under
+//! test, and to a simple model of this system. Sequences of commands are generated
+//! from the evolving model, then applied to both the model and a fresh instance of
+//! the real system, comparing their outputs and checking an invariant after each
+//! command.
+
+use super::generator::{gen_at, indexed_generation, BoxGenerator, Generator};
+use super::property::{Outcome, Property};
+use super::rand::R;
+use super::run::{forall, Ensure};
+use super::ux::{Element, Elements, Value};
+use std::fmt::Debug;
+
+/// The description of a stateful system under test, and of its model
+pub trait StateMachine {
+    /// A command applied to the model and to the real system
+    type Command: Debug + Clone + 'static;
+    /// The state of the model
+    type Model;
+    /// The real system under test
+    type Real;
+    /// The observable result of a command, compared between the model and the real system
+    type Output: Debug + PartialEq;
+
+    /// Initial state of the model
+    fn init_model(&self) -> Self::Model;
+
+    /// New instance of the real system, matching the initial state of the model
+    fn init_real(&self) -> Self::Real;
+
+    /// Generator of the next command, given the current state of the model
+    fn commands(&self, model: &Self::Model) -> BoxGenerator<Self::Command>;
+
+    /// Whether `command` is valid in the current state of the model, the
+    /// generated commands which are not valid are skipped
+    fn precondition(&self, _model: &Self::Model, _command: &Self::Command) -> bool {
+        true
+    }
+
+    /// Apply a command to the model, returning the expected output
+    fn apply_model(&self, model: &mut Self::Model, command: &Self::Command) -> Self::Output;
+
+    /// Apply a command to the real system, returning its output
+    fn apply_real(&self, real: &mut Self::Real, command: &Self::Command) -> Self::Output;
+
+    /// Property holding between the model and the real system after each command
+    fn invariant(&self, model: &Self::Model, real: &Self::Real) -> impl Property;
+}
+
+/// Generate sequences of valid commands of a state machine
+#[derive(Clone)]
+pub struct Commands<M, SZ> {
+    machine: M,
+    nb_commands: SZ,
+}
+
+impl<M, SZ> Commands<M, SZ>
+where
+    M: StateMachine,
+    SZ: Generator<Item = usize>,
+{
+    fn generate(&self, idx: Option<u64>, r: &mut R) -> Vec<M::Command> {
+        let sz = gen_at(&self.nb_commands, idx, &mut r.sub());
+        let sz = r.bounded_size(sz);
+        let mut model = self.machine.init_model();
+        let mut commands = Vec::with_capacity(sz);
+        let mut commands_r = r.sub();
+        for _ in 0..sz {
+            let command = gen_at(&self.machine.commands(&model), idx, &mut commands_r);
+            if self.machine.precondition(&model, &command) {
+                self.machine.apply_model(&mut model, &command);
+                commands.push(command);
+            }
+        }
+        commands
+    }
+}
+
+impl<M, SZ> Generator for Commands<M, SZ>
+where
+    M: StateMachine,
+    SZ: Generator<Item = usize>,
+{
+    type Item = Vec<M::Command>;
+
+    indexed_generation!();
+}
+
+/// generate sequences of commands of `machine`, of a length specified by the generator
+/// in parameter
+///
+/// Each command is generated from the state of the model after the previous commands,
+/// and the commands not satisfying the precondition are skipped, so that the sequences
+/// may be shorter than the generated length.
+pub fn commands<M, SZ>(machine: M, nb_commands: SZ) -> Commands<M, SZ>
+where
+    M: StateMachine,
+    SZ: Generator<Item = usize>,
+{
+    Commands {
+        machine,
+        nb_commands,
+    }
+}
+
+/// Property of the execution of a sequence of commands on the model and on the real system
+pub struct Execution {
+    outcome: Outcome,
+}
+
+impl Property for Execution {
+    fn result(&self) -> Outcome {
+        self.outcome.clone()
+    }
+}
+
+/// Failure of a command of a sequence
+struct StepFailure {
+    step: usize,
+    cause: &'static str,
+    value: Value,
+}
+
+/// Replay of a sequence of commands
+enum Replay {
+    Passed,
+    /// A command doesn't satisfy its precondition
+    Invalid,
+    Failed(StepFailure),
+}
+
+fn replay<M: StateMachine>(machine: &M, commands: &[M::Command]) -> Replay {
+    let mut model = machine.init_model();
+    let mut real = machine.init_real();
+    for (step, command) in commands.iter().enumerate() {
+        if !machine.precondition(&model, command) {
+            return Replay::Invalid;
+        }
+        let expected = machine.apply_model(&mut model, command);
+        let actual = machine.apply_real(&mut real, command);
+        if expected != actual {
+            let mut outputs = Elements::new();
+            outputs.append("model", Value::from_debug(&expected));
+            outputs.append("real", Value::from_debug(&actual));
+            return Replay::Failed(StepFailure {
+                step,
+                cause: "outputs",
+                value: outputs.into(),
+            });
+        }
+        if let Outcome::Failed(e) = machine.invariant(&model, &real).result() {
+            return Replay::Failed(StepFailure {
+                step,
+                cause: "invariant",
+                value: Value::sub(e),
+            });
+        }
+    }
+    Replay::Passed
+}
+
+fn execution_failure<C: Debug>(commands: &[C], failure: StepFailure) -> Elements {
+    let step = failure.step;
+    let mut output = Elements::new();
+    output.append("step", Value::from(step));
+    output.append("prefix", Value::from_debug(&commands[..=step]));
+    output.append("command", Value::from_debug(&commands[step]));
+    output.append(failure.cause, failure.value);
+    output
+}
+
+/// Apply `commands` to a new model and a new real system, stopping at the first
+/// command whose outputs differ or after which the invariant doesn't hold
+///
+/// The failure reports the index of the failing command and the prefix of the
+/// commands up to the failing one.
+pub fn execute<M: StateMachine>(machine: &M, commands: &[M::Command]) -> Execution {
+    let outcome = match replay(machine, commands) {
+        Replay::Passed => Outcome::Passed,
+        Replay::Invalid => Outcome::Discarded,
+        Replay::Failed(failure) => {
+            let output = execution_failure(commands, failure);
+            Outcome::Failed(Element::new("state machine", output.into()))
+        }
+    };
+    Execution { outcome }
+}
+
+/// Maximum number of replays of the commands when shrinking a failing sequence
+const MAX_SHRINK_REPLAYS: usize = 1000;
+
+/// Shrink a failing sequence of commands, by removing chunks of commands as long
+/// as the remaining sequence is valid and still failing
+///
+/// The chunks are removed in a deterministic order, from the halves of the sequence
+/// down to single commands, so that a failing sequence always shrinks to the same
+/// sequence. The shrinking is bounded to 1000 replays of the commands.
+pub fn shrink_commands<M: StateMachine>(machine: &M, commands: &[M::Command]) -> Vec<M::Command> {
+    let mut current = commands.to_vec();
+    let mut replays = 0;
+    let mut chunk = (current.len() / 2).max(1);
+    while replays < MAX_SHRINK_REPLAYS && !current.is_empty() {
+        let mut removed = false;
+        let mut start = 0;
+        while start < current.len() && replays < MAX_SHRINK_REPLAYS {
+            let end = (start + chunk).min(current.len());
+            let candidate: Vec<M::Command> = current[..start]
+                .iter()
+                .chain(current[end..].iter())
+                .cloned()
+                .collect();
+            replays += 1;
+            match replay(machine, &candidate) {
+                Replay::Failed(_) => {
+                    current = candidate;
+                    removed = true;
+                }
+                Replay::Passed | Replay::Invalid => start += chunk,
+            }
+        }
+        if !removed {
+            if chunk == 1 {
+                break;
+            }
+            chunk /= 2;
+        }
+    }
+    current
+}
+
+/// Similar to `execute`, but a failing sequence is shrunk with `shrink_commands`,
+/// and the failure of the shrunk sequence is reported
+fn execute_shrinking<M: StateMachine>(machine: &M, commands: &[M::Command]) -> Execution {
+    match replay(machine, commands) {
+        Replay::Passed => Execution {
+            outcome: Outcome::Passed,
+        },
+        Replay::Invalid => Execution {
+            outcome: Outcome::Discarded,
+        },
+        Replay::Failed(failure) => {
+            let shrunk = shrink_commands(machine, commands);
+            let mut output = match replay(machine, &shrunk) {
+                Replay::Failed(shrunk_failure) => execution_failure(&shrunk, shrunk_failure),
+                // only the failing sequences are kept when shrinking
+                Replay::Passed | Replay::Invalid => execution_failure(commands, failure),
+            };
+            output.append("original length", Value::from(commands.len()));
+            Execution {
+                outcome: Outcome::Failed(Element::new("state machine", output.into())),
+            }
+        }
+    }
+}
+
+/// Runner of the model based test of a state machine
+pub type StateMachineTest<M, SZ> =
+    Ensure<Commands<M, SZ>, Box<dyn Fn(Vec<<M as StateMachine>::Command>) -> Execution>>;
+
+/// Check that the real system behaves like the model, on sequences of commands of
+/// a length specified by the generator in parameter
+///
+/// The failing sequences are shrunk with `shrink_commands` before being reported.
+///
+/// ```
+/// use smoke::{run, Generator, Property, Testable, generator::{range, one_of, BoxGenerator}};
+/// use smoke::model::{ensure_state_machine, StateMachine};
+/// use smoke::property::equal;
+///
+/// #[derive(Clone)]
+/// struct Counter;
+///
+/// impl StateMachine for Counter {
+///     type Command = bool;
+///     type Model = u64;
+///     type Real = std::cell::Cell<u64>;
+///     type Output = u64;
+///
+///     fn init_model(&self) -> u64 { 0 }
+///     fn init_real(&self) -> Self::Real { std::cell::Cell::new(0) }
+///     fn commands(&self, _: &u64) -> BoxGenerator<bool> { one_of(&[true, false]).into_boxed() }
+///     fn apply_model(&self, model: &mut u64, increment: &bool) -> u64 {
+///         *model += u64::from(*increment);
+///         *model
+///     }
+///     fn apply_real(&self, real: &mut Self::Real, increment: &bool) -> u64 {
+///         real.set(real.get() + u64::from(*increment));
+///         real.get()
+///     }
+///     fn invariant(&self, model: &u64, real: &Self::Real) -> impl Property {
+///         equal(*model, real.get())
+///     }
+/// }
+///
+/// run(|ctx| ensure_state_machine(Counter, range(0..32)).run(ctx));
+/// ```
+pub fn ensure_state_machine<M, SZ>(machine: M, nb_commands: SZ) -> StateMachineTest<M, SZ>
+where
+    M: StateMachine + Clone + 'static,
+    SZ: Generator<Item = usize>,
+{
+    let runner = machine.clone();
+    forall(commands(machine, nb_commands)).ensure_named(
+        "state machine",
+        Box::new(move |commands: Vec<M::Command>| execute_shrinking(&runner, &commands)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{num, range, Generator};
+    use crate::property::equal;
+    use crate::run::{Context, Testable};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Put(u8, u8),
+        Get(u8),
+        Remove(u8),
+    }
+
+    /// A small cache, optionally forgetting to invalidate the removed keys
+    struct Cache {
+        entries: Vec<(u8, u8)>,
+        buggy_remove: bool,
+    }
+
+    #[derive(Clone)]
+    struct CacheMachine {
+        buggy_remove: bool,
+    }
+
+    impl StateMachine for CacheMachine {
+        type Command = Op;
+        type Model = BTreeMap<u8, u8>;
+        type Real = Cache;
+        type Output = Option<u8>;
+
+        fn init_model(&self) -> Self::Model {
+            BTreeMap::new()
+        }
+
+        fn init_real(&self) -> Self::Real {
+            Cache {
+                entries: Vec::new(),
+                buggy_remove: self.buggy_remove,
+            }
+        }
+
+        fn commands(&self, model: &Self::Model) -> BoxGenerator<Op> {
+            let keys: Vec<u8> = model.keys().copied().chain(0..4).collect();
+            let key = range(0..keys.len() - 1).map(move |i| keys[i]);
+            let op = range(0u8..2).and(key).and(num::<u8>());
+            op.map(|((op, key), value)| match op {
+                0 => Op::Put(key, value),
+                1 => Op::Get(key),
+                _ => Op::Remove(key),
+            })
+            .into_boxed()
+        }
+
+        fn precondition(&self, model: &Self::Model, command: &Op) -> bool {
+            match command {
+                Op::Remove(k) => model.contains_key(k),
+                Op::Put(..) | Op::Get(_) => true,
+            }
+        }
+
+        fn apply_model(&self, model: &mut Self::Model, command: &Op) -> Option<u8> {
+            match command {
+                Op::Put(k, v) => model.insert(*k, *v),
+                Op::Get(k) => model.get(k).copied(),
+                Op::Remove(k) => model.remove(k),
+            }
+        }
+
+        fn apply_real(&self, real: &mut Cache, command: &Op) -> Option<u8> {
+            let position = |k: &u8| real.entries.iter().position(|(key, _)| key == k);
+            match command {
+                Op::Put(k, v) => match position(k) {
+                    Some(i) => Some(std::mem::replace(&mut real.entries[i].1, *v)),
+                    None => {
+                        real.entries.push((*k, *v));
+                        None
+                    }
+                },
+                Op::Get(k) => position(k).map(|i| real.entries[i].1),
+                Op::Remove(k) => match position(k) {
+                    Some(i) if real.buggy_remove => Some(real.entries[i].1),
+                    Some(i) => Some(real.entries.remove(i).1),
+                    None => None,
+                },
+            }
+        }
+
+        fn invariant(&self, model: &Self::Model, real: &Cache) -> impl Property {
+            equal(model.len(), real.entries.len())
+        }
+    }
+
+    #[test]
+    fn generated_commands_are_valid() {
+        let machine = CacheMachine {
+            buggy_remove: false,
+        };
+        let mut r = R::from_seed(crate::Seed::from(7));
+        let gen = commands(machine.clone(), range(0..50));
+        let mut nb_removes = 0;
+        for _ in 0..20 {
+            let commands = gen.gen(&mut r);
+            assert!(commands.len() <= 50);
+            let mut model = machine.init_model();
+            for command in &commands {
+                assert!(
+                    machine.precondition(&model, command),
+                    "{:?} invalid in {:?}",
+                    command,
+                    model
+                );
+                if let Op::Remove(_) = command {
+                    nb_removes += 1;
+                }
+                machine.apply_model(&mut model, command);
+            }
+        }
+        // the removals are only valid for the keys inserted by the previous commands
+        assert!(nb_removes > 0);
+    }
+
+    #[test]
+    fn state_machine() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        let correct = CacheMachine {
+            buggy_remove: false,
+        };
+        let results = ensure_state_machine(correct, range(0..50)).test(&ctx);
+        assert_eq!(results.nb_failed, 0);
+
+        let buggy = CacheMachine { buggy_remove: true };
+        let results = ensure_state_machine(buggy, range(0..50)).test(&ctx);
+        assert!(results.nb_failed > 0);
+        let failure = &results.failures[0];
+        assert_eq!(failure.property.as_deref(), Some("state machine"));
+        assert!(failure.message.contains("prefix: ["));
+        assert!(failure.message.contains("Remove("));
+    }
+
+    #[test]
+    fn shrinking() {
+        let buggy = CacheMachine { buggy_remove: true };
+        let gen = commands(buggy.clone(), range(40..50));
+        let mut r = R::from_seed(crate::Seed::from(11));
+        let failing = std::iter::repeat_with(|| gen.gen(&mut r))
+            .find(|c| matches!(replay(&buggy, c), Replay::Failed(_)))
+            .unwrap();
+        let shrunk = shrink_commands(&buggy, &failing);
+        // the smallest valid and failing sequence is a put followed by its removal
+        assert_eq!(shrunk.len(), 2, "{:?}", shrunk);
+        assert!(matches!((&shrunk[0], &shrunk[1]), (Op::Put(k1, _), Op::Remove(k2)) if k1 == k2));
+        assert_eq!(
+            format!("{:?}", shrink_commands(&buggy, &failing)),
+            format!("{:?}", shrunk)
+        );
+
+        match execute_shrinking(&buggy, &failing).result() {
+            Outcome::Failed(e) => {
+                let report = e.display(0);
+                assert!(report.contains("step: 1\n"), "{}", report);
+                assert!(report.contains(&format!("original length: {}\n", failing.len())));
+            }
+            o => panic!("unexpected {:?}", o),
+        }
+    }
+}
@@ -1,9 +1,15 @@
-use crate::ux::{Element, Elements, Value};
+use super::failure::FailureBuilder;
+use crate::ux::{Element, Value};
 
+/// The result of a property
+///
+/// A `Discarded` outcome means the property doesn't apply to the tested
+/// input, which is neither a success nor a failure.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Outcome {
     Passed,
     Failed(Element),
+    Discarded,
 }
 
 /// A generic expressible property
@@ -11,12 +17,38 @@ pub trait Property {
     /// Get the result of this property
     fn result(&self) -> Outcome;
 
+    /// Report the coverage conditions of this property, as the label, whether the
+    /// condition is met and the required percentage of tests meeting the condition
+    ///
+    /// Only combinators and `cover` need to implement this
+    fn coverage(&self, _report: &mut dyn FnMut(&str, bool, f64)) {}
+
+    /// Report the values checked by this property, as the tree it would report on
+    /// failure, whether it passes or not, or None if the values are unknown
+    ///
+    /// This is only used to detect the properties not depending on their input
+    /// (see `Ensure::with_triviality_check`), the relations and the combinators
+    /// implement it.
+    fn probe(&self) -> Option<Element> {
+        None
+    }
+
+    /// Report why this property was discarded, after `result` returned `Discarded`,
+    /// the reason being recorded as a skip in the results
+    ///
+    /// Only combinators and the properties discarding the tests for a reason worth
+    /// reporting (e.g. `matches_snapshot` creating its snapshot) implement it.
+    fn discard_reason(&self) -> Option<String> {
+        None
+    }
+
     /// Simple logical And combinator, this property and the next one must pass to pass
     fn and<O>(self, other: O) -> And<Self, O>
     where
         Self: Sized,
     {
         And {
+            fail_fast: false,
             prop_a: self,
             prop_b: other,
         }
@@ -36,6 +68,8 @@ pub trait Property {
     */
 
     /// Simple logical Or combinator, this property or the next one must pass to pass
+    ///
+    /// The next property is not evaluated when this property passes
     fn or<O>(self, other: O) -> Or<Self, O>
     where
         Self: Sized,
@@ -45,17 +79,163 @@ pub trait Property {
             prop_b: other,
         }
     }
+
+    /// Only check this property when the precondition is true, otherwise the
+    /// property is discarded: it doesn't count as a success nor a failure
+    ///
+    /// ```
+    /// use smoke::{Property, property::equal};
+    ///
+    /// let (a, b) = (10u32, 5u32);
+    /// let property = equal(a / b * b + a % b, a).when(b != 0);
+    /// ```
+    fn when(self, precondition: bool) -> When<Self>
+    where
+        Self: Sized,
+    {
+        When {
+            precondition,
+            prop: self,
+        }
+    }
+
+    /// Require that at least `required_percent` of the tests meet `condition`,
+    /// otherwise the test run fails, reporting the observed percentage of `label`
+    ///
+    /// This doesn't change the result of the property itself, but make sure that
+    /// the generated inputs exercise the interesting cases:
+    ///
+    /// ```
+    /// use smoke::{Property, property::equal};
+    ///
+    /// let v = vec![1, 2, 2, 3];
+    /// let mut dedup = v.clone();
+    /// dedup.dedup();
+    /// let property = equal(dedup.len() <= v.len(), true)
+    ///     .cover("has duplicates", dedup.len() < v.len(), 5.0);
+    /// ```
+    fn cover(self, label: &str, condition: bool, required_percent: f64) -> Cover<Self>
+    where
+        Self: Sized,
+    {
+        Cover {
+            label: label.to_string(),
+            condition,
+            required_percent,
+            prop: self,
+        }
+    }
+
+    /// Label this property, so that its failure is reported under a node named `name`
+    ///
+    /// This is useful to describe what each part of a combined property is checking:
+    ///
+    /// ```
+    /// use smoke::{Property, property::less_equal};
+    ///
+    /// let r = 30;
+    /// let property = less_equal(20, r)
+    ///     .label("lower bound")
+    ///     .and(less_equal(r, 40).label("upper bound"));
+    /// ```
+    fn label(self, name: &str) -> Labeled<Self>
+    where
+        Self: Sized,
+    {
+        Labeled {
+            name: name.to_string(),
+            prop: self,
+        }
+    }
 }
 
 /// A Generic Boxed Property
 pub struct BoxProperty(Box<dyn Property>);
 
+impl Property for BoxProperty {
+    fn result(&self) -> Outcome {
+        self.0.result()
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        self.0.coverage(report)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        self.0.probe()
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.0.discard_reason()
+    }
+}
+
+/// A property checked by plain code, failing with the message of its error
+///
+/// This is the property of the closures of `Forall::ensure_result`
+pub struct Checked(pub Result<(), String>);
+
+impl Property for Checked {
+    fn result(&self) -> Outcome {
+        match &self.0 {
+            Ok(()) => Outcome::Passed,
+            Err(message) => {
+                Outcome::Failed(Element::new("check failed", Value::from(message.as_str())))
+            }
+        }
+    }
+}
+
+fn construction_failure<E: std::fmt::Debug>(e: &E) -> Element {
+    Element::new("property construction failed", Value::from_debug(e))
+}
+
+/// A property whose construction may have failed, failing with the error
+impl<P: Property, E: std::fmt::Debug> Property for Result<P, E> {
+    fn result(&self) -> Outcome {
+        match self {
+            Ok(p) => p.result(),
+            Err(e) => Outcome::Failed(construction_failure(e)),
+        }
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        if let Ok(p) = self {
+            p.coverage(report)
+        }
+    }
+
+    fn probe(&self) -> Option<Element> {
+        match self {
+            Ok(p) => p.probe(),
+            Err(e) => Some(construction_failure(e)),
+        }
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.as_ref().ok().and_then(|p| p.discard_reason())
+    }
+}
+
 /// Logical And between properties
 pub struct And<A, B> {
+    fail_fast: bool,
     prop_a: A,
     prop_b: B,
 }
 
+impl<A, B> And<A, B> {
+    /// Don't evaluate the second property when the first one fails
+    ///
+    /// The failure then reports the second property as "not evaluated", instead
+    /// of its result. This is useful when the second property is only well-defined
+    /// (or expensive to check) when the first one holds.
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+}
+
 impl<A, B> Property for And<A, B>
 where
     A: Property,
@@ -63,24 +243,62 @@ where
 {
     fn result(&self) -> Outcome {
         fn failure_element(left: Value, right: Value) -> Outcome {
-            let mut output = Elements::new();
-            output.append("left", left);
-            output.append("right", right);
-            Outcome::Failed(Element::new("and", output.into()))
+            FailureBuilder::new()
+                .field("left", left)
+                .field("right", right)
+                .build("and")
         }
-        match (self.prop_a.result(), self.prop_b.result()) {
-            (Outcome::Passed, Outcome::Passed) => Outcome::Passed,
-            (Outcome::Failed(f1), Outcome::Passed) => {
-                failure_element(Value::sub(f1), "passed".into())
+        fn leg_value(outcome: Outcome) -> Value {
+            match outcome {
+                Outcome::Passed => "passed".into(),
+                Outcome::Discarded => "discarded".into(),
+                Outcome::Failed(f) => Value::sub(f),
             }
-            (Outcome::Passed, Outcome::Failed(f2)) => {
-                failure_element("passed".into(), Value::sub(f2))
+        }
+        let outcome_a = self.prop_a.result();
+        if self.fail_fast {
+            if let Outcome::Failed(_) = outcome_a {
+                return failure_element(leg_value(outcome_a), "not evaluated".into());
             }
-            (Outcome::Failed(f1), Outcome::Failed(f2)) => {
-                failure_element(Value::sub(f1), Value::sub(f2))
+        }
+        match (outcome_a, self.prop_b.result()) {
+            (Outcome::Passed, Outcome::Passed) => Outcome::Passed,
+            (o1 @ Outcome::Failed(_), o2) | (o1, o2 @ Outcome::Failed(_)) => {
+                failure_element(leg_value(o1), leg_value(o2))
             }
+            (Outcome::Discarded, _) | (_, Outcome::Discarded) => Outcome::Discarded,
         }
     }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        self.prop_a.coverage(report);
+        self.prop_b.coverage(report);
+    }
+
+    fn probe(&self) -> Option<Element> {
+        probe_pair("and", &self.prop_a, &self.prop_b)
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.prop_a
+            .discard_reason()
+            .or_else(|| self.prop_b.discard_reason())
+    }
+}
+
+/// Probe of a combination of 2 properties, known when any of them is known
+fn probe_pair<A: Property, B: Property>(name: &str, prop_a: &A, prop_b: &B) -> Option<Element> {
+    fn leg_value(probe: Option<Element>) -> Value {
+        probe.map(Value::sub).unwrap_or_else(|| "unknown".into())
+    }
+    let (probe_a, probe_b) = (prop_a.probe(), prop_b.probe());
+    if probe_a.is_none() && probe_b.is_none() {
+        return None;
+    }
+    let probe = FailureBuilder::new()
+        .field("left", leg_value(probe_a))
+        .field("right", leg_value(probe_b));
+    Some(probe.element(name))
 }
 
 /*
@@ -104,15 +322,250 @@ where
     B: Property,
 {
     fn result(&self) -> Outcome {
-        match (self.prop_a.result(), self.prop_b.result()) {
-            (Outcome::Passed, _) => Outcome::Passed,
+        // the right property is only evaluated when the left one doesn't pass
+        let failure_a = match self.prop_a.result() {
+            Outcome::Passed => return Outcome::Passed,
+            Outcome::Discarded => None,
+            Outcome::Failed(f1) => Some(f1),
+        };
+        match (failure_a, self.prop_b.result()) {
             (_, Outcome::Passed) => Outcome::Passed,
-            (Outcome::Failed(f1), Outcome::Failed(f2)) => {
-                let mut output = Elements::new();
-                output.append("left", Value::sub(f1));
-                output.append("right", Value::sub(f2));
-                Outcome::Failed(Element::new("or", output.into()))
-            }
+            (None, _) | (_, Outcome::Discarded) => Outcome::Discarded,
+            (Some(f1), Outcome::Failed(f2)) => FailureBuilder::new()
+                .field("left", Value::sub(f1))
+                .field("right", Value::sub(f2))
+                .build("or"),
+        }
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        self.prop_a.coverage(report);
+        self.prop_b.coverage(report);
+    }
+
+    fn probe(&self) -> Option<Element> {
+        probe_pair("or", &self.prop_a, &self.prop_b)
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.prop_a
+            .discard_reason()
+            .or_else(|| self.prop_b.discard_reason())
+    }
+}
+
+/// Property with a precondition, discarded when the precondition doesn't hold
+pub struct When<P> {
+    precondition: bool,
+    prop: P,
+}
+
+impl<P: Property> Property for When<P> {
+    fn result(&self) -> Outcome {
+        if self.precondition {
+            self.prop.result()
+        } else {
+            Outcome::Discarded
+        }
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        self.prop.coverage(report)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        if self.precondition {
+            self.prop.probe()
+        } else {
+            None
+        }
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        if self.precondition {
+            self.prop.discard_reason()
+        } else {
+            None
         }
     }
 }
+
+/// Property with a label reported in its failure
+pub struct Labeled<P> {
+    name: String,
+    prop: P,
+}
+
+impl<P: Property> Property for Labeled<P> {
+    fn result(&self) -> Outcome {
+        match self.prop.result() {
+            Outcome::Passed => Outcome::Passed,
+            Outcome::Discarded => Outcome::Discarded,
+            Outcome::Failed(f) => Outcome::Failed(Element::new(&self.name, Value::sub(f))),
+        }
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        self.prop.coverage(report)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        let probe = self.prop.probe()?;
+        Some(Element::new(&self.name, Value::sub(probe)))
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.prop.discard_reason()
+    }
+}
+
+/// Property with a coverage requirement
+pub struct Cover<P> {
+    label: String,
+    condition: bool,
+    required_percent: f64,
+    prop: P,
+}
+
+impl<P: Property> Property for Cover<P> {
+    fn result(&self) -> Outcome {
+        self.prop.result()
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        report(&self.label, self.condition, self.required_percent);
+        self.prop.coverage(report)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        self.prop.probe()
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.prop.discard_reason()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{equal, less_equal};
+
+    fn bounds(r: u32) -> impl Property {
+        less_equal(20, r)
+            .label("lower bound")
+            .and(less_equal(r, 40).label("upper bound"))
+    }
+
+    fn failure_display(p: impl Property) -> String {
+        match p.result() {
+            Outcome::Failed(e) => e.display(0),
+            o => panic!("property expected to fail: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn labeled_passed() {
+        assert_eq!(bounds(30).result(), Outcome::Passed);
+        assert_eq!(equal(1, 1).label("unused").result(), Outcome::Passed);
+    }
+
+    #[test]
+    fn labeled_failure_tree() {
+        assert_eq!(
+            failure_display(bounds(10)),
+            "and: \n  left: \n    lower bound: \n      <=: \n        left: 20\n        right: 10\n  right: passed\n"
+        );
+        assert_eq!(
+            failure_display(bounds(50)),
+            "and: \n  left: passed\n  right: \n    upper bound: \n      <=: \n        left: 50\n        right: 40\n"
+        );
+    }
+
+    fn passed() -> impl Property {
+        equal(1, 1)
+    }
+
+    fn failed() -> impl Property {
+        equal(1, 2)
+    }
+
+    fn discarded() -> impl Property {
+        equal(1, 1).when(false)
+    }
+
+    fn is_failed(o: Outcome) -> bool {
+        matches!(o, Outcome::Failed(_))
+    }
+
+    #[test]
+    fn when() {
+        assert_eq!(passed().when(true).result(), Outcome::Passed);
+        assert!(is_failed(failed().when(true).result()));
+        assert_eq!(failed().when(false).result(), Outcome::Discarded);
+        assert_eq!(discarded().label("x").result(), Outcome::Discarded);
+    }
+
+    #[test]
+    fn and_propagation() {
+        assert_eq!(discarded().and(passed()).result(), Outcome::Discarded);
+        assert_eq!(passed().and(discarded()).result(), Outcome::Discarded);
+        assert_eq!(discarded().and(discarded()).result(), Outcome::Discarded);
+        assert!(is_failed(discarded().and(failed()).result()));
+        assert!(is_failed(failed().and(discarded()).result()));
+        match discarded().and(failed()).result() {
+            Outcome::Failed(e) => assert_eq!(
+                e.display(0),
+                "and: \n  left: discarded\n  right: \n    ==: \n      left: 1\n      right: 2\n"
+            ),
+            o => panic!("unexpected {:?}", o),
+        }
+    }
+
+    struct Panicking;
+
+    impl Property for Panicking {
+        fn result(&self) -> Outcome {
+            panic!("property evaluated")
+        }
+    }
+
+    #[test]
+    fn short_circuit() {
+        assert_eq!(passed().or(Panicking).result(), Outcome::Passed);
+        match failed().and(Panicking).fail_fast().result() {
+            Outcome::Failed(e) => assert_eq!(
+                e.display(0),
+                "and: \n  left: \n    ==: \n      left: 1\n      right: 2\n  right: not evaluated\n"
+            ),
+            o => panic!("unexpected {:?}", o),
+        }
+        assert!(is_failed(failed().or(failed()).result()));
+    }
+
+    #[test]
+    fn probe_passing() {
+        let probe = bounds(30).probe().unwrap();
+        assert_eq!(
+            probe.display(0),
+            "and: \n  left: \n    lower bound: \n      <=: \n        left: 20\n        right: 30\n  right: \n    upper bound: \n      <=: \n        left: 30\n        right: 40\n"
+        );
+        assert_eq!(discarded().probe(), None);
+        assert_eq!(Panicking.or(discarded()).probe(), None);
+        // the probes don't evaluate the properties
+        assert_eq!(Ok::<_, ()>(Panicking).probe(), None);
+        assert_eq!(
+            Err::<Panicking, _>("invalid").probe().unwrap().display(0),
+            "property construction failed: \"invalid\"\n"
+        );
+    }
+
+    #[test]
+    fn or_propagation() {
+        assert_eq!(discarded().or(passed()).result(), Outcome::Passed);
+        assert_eq!(passed().or(discarded()).result(), Outcome::Passed);
+        assert_eq!(discarded().or(failed()).result(), Outcome::Discarded);
+        assert_eq!(failed().or(discarded()).result(), Outcome::Discarded);
+        assert_eq!(discarded().or(discarded()).result(), Outcome::Discarded);
+    }
+}
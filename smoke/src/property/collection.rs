@@ -1,3 +1,6 @@
+use super::api::{Outcome, Property};
+use super::failure::FailureBuilder;
+use crate::ux::{Element, IntoValue, Value};
 use std::collections::{HashMap, HashSet};
 
 pub trait Collection {
@@ -11,3 +14,132 @@ impl<T> Collection for Vec<T> {}
 impl<T, H> Collection for HashSet<T, H> {}
 
 impl<K, V, H> Collection for HashMap<K, V, H> {}
+
+/// Structural equality of 2 slices, reporting the first differing index
+fn slice_eq<T: Eq + std::fmt::Debug>(left: &[T], right: &[T]) -> Outcome {
+    let index = match left.iter().zip(right.iter()).position(|(l, r)| l != r) {
+        Some(index) => index,
+        None if left.len() == right.len() => return Outcome::Passed,
+        None => left.len().min(right.len()),
+    };
+    let describe = |s: &[T]| match s.get(index) {
+        None => Value::from("missing"),
+        Some(e) => e.to_value(),
+    };
+    let mut failure = FailureBuilder::new()
+        .field("index", index)
+        .field("left", describe(left))
+        .field("right", describe(right));
+    if left.len() != right.len() {
+        failure = failure
+            .field("left length", left.len())
+            .field("right length", right.len());
+    }
+    failure.build("==")
+}
+
+/// Probe of the equality of 2 slices, with the whole slices
+fn slice_probe<T: std::fmt::Debug>(left: &[T], right: &[T]) -> Element {
+    FailureBuilder::new()
+        .field("left", Value::from_debug(left))
+        .field("right", Value::from_debug(right))
+        .element("==")
+}
+
+/// Equality between 2 slices, reporting the first differing element on failure
+pub struct SliceEq<'a, T> {
+    left: &'a [T],
+    right: &'a [T],
+}
+
+impl<'a, T: Eq + std::fmt::Debug> Property for SliceEq<'a, T> {
+    fn result(&self) -> Outcome {
+        slice_eq(self.left, self.right)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        Some(slice_probe(self.left, self.right))
+    }
+}
+
+/// Equality between 2 vectors, reporting the first differing element on failure
+pub struct VecEq<T> {
+    left: Vec<T>,
+    right: Vec<T>,
+}
+
+impl<T: Eq + std::fmt::Debug> Property for VecEq<T> {
+    fn result(&self) -> Outcome {
+        slice_eq(&self.left, &self.right)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        Some(slice_probe(&self.left, &self.right))
+    }
+}
+
+/// Check that 2 slices are equal
+///
+/// Contrary to `equal`, the failure only report the first index where
+/// the slices differ, and the elements at this index, instead of
+/// the whole slices
+pub fn equal_slice<'a, T: Eq>(left: &'a [T], right: &'a [T]) -> SliceEq<'a, T> {
+    SliceEq { left, right }
+}
+
+/// Check that 2 vectors are equal
+///
+/// Contrary to `equal`, the failure only report the first index where
+/// the vectors differ, and the elements at this index, instead of
+/// the whole vectors
+pub fn equal_vec<T: Eq>(left: Vec<T>, right: Vec<T>) -> VecEq<T> {
+    VecEq { left, right }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ux::Elements;
+
+    /// The failure tree of a failed property, with the failed operation as root key
+    fn failure(p: impl Property) -> Elements {
+        match p.result() {
+            Outcome::Failed(e) => match Value::sub(e) {
+                Value::Tree(elements) => elements,
+                _ => unreachable!(),
+            },
+            o => panic!("property expected to fail: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn equal_vec_passed() {
+        assert_eq!(
+            equal_vec(vec![1, 2, 3], vec![1, 2, 3]).result(),
+            Outcome::Passed
+        );
+        assert_eq!(equal_slice::<u8>(&[], &[]).result(), Outcome::Passed);
+    }
+
+    #[test]
+    fn equal_vec_differ_at_index() {
+        let left: Vec<u32> = (0..100).collect();
+        let mut right = left.clone();
+        right[3] = 1000;
+        let f = failure(equal_vec(left, right));
+        assert_eq!(f.find_path(&["==", "index"]), Some(&Value::Int(3)));
+        assert_eq!(f.find_path(&["==", "left"]), Some(&Value::Int(3)));
+        assert_eq!(f.find_path(&["==", "right"]), Some(&Value::Int(1000)));
+        assert_eq!(f.find_path(&["==", "left length"]), None);
+        assert_eq!(f.display(0), "==: \n  index: 3\n  left: 3\n  right: 1000\n");
+    }
+
+    #[test]
+    fn equal_slice_differ_in_length() {
+        let f = failure(equal_slice(&["a", "b"], &["a", "b", "c"]));
+        assert_eq!(
+            f.display(0),
+            "==: \n  index: 2\n  left: missing\n  right: \"c\"\n  left length: 2\n  right length: 3\n"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::api::{Outcome, Property};
-use crate::ux::{Element, Elements};
+use super::failure::FailureBuilder;
+use crate::ux::{Element, IntoValue};
 use std::cmp::Ordering;
 
 struct NamedOp<T> {
@@ -32,6 +33,14 @@ const LE_OP: NamedOp<Ordering> = NamedOp {
     op: |o| o == Ordering::Less || o == Ordering::Equal,
 };
 
+/// Element reporting the values of a relation
+fn relation_element<T: std::fmt::Debug>(name: &str, left: &T, right: &T) -> Element {
+    FailureBuilder::new()
+        .field("left", left.to_value())
+        .field("right", right.to_value())
+        .element(name)
+}
+
 /// Relation between 2 values based on the Eq trait
 pub struct RelationEq<T> {
     left: T,
@@ -44,14 +53,13 @@ impl<T: Eq + std::fmt::Debug> Property for RelationEq<T> {
         if (self.op.op)(self.left == self.right) {
             Outcome::Passed
         } else {
-            let mut output = Elements::new();
-            let l_value = format!("{:?}", self.left);
-            let r_value = format!("{:?}", self.right);
-            output.append("left", l_value.into());
-            output.append("right", r_value.into());
-            Outcome::Failed(Element::new(self.op.name, output.into()))
+            Outcome::Failed(relation_element(self.op.name, &self.left, &self.right))
         }
     }
+
+    fn probe(&self) -> Option<Element> {
+        Some(relation_element(self.op.name, &self.left, &self.right))
+    }
 }
 
 /// Relation between 2 values based on the Ord trait
@@ -66,14 +74,68 @@ impl<T: Ord + std::fmt::Debug> Property for RelationOrd<T> {
         if (self.op.op)(self.left.cmp(&self.right)) {
             Outcome::Passed
         } else {
-            let mut output = Elements::new();
-            let l_value = format!("{:?}", self.left);
-            let r_value = format!("{:?}", self.right);
-            output.append("left", l_value.into());
-            output.append("right", r_value.into());
-            Outcome::Failed(Element::new(self.op.name, output.into()))
+            Outcome::Failed(relation_element(self.op.name, &self.left, &self.right))
         }
     }
+
+    fn probe(&self) -> Option<Element> {
+        Some(relation_element(self.op.name, &self.left, &self.right))
+    }
+}
+
+/// Failure of a relation between 2 values that are not comparable (e.g. NaN)
+fn incomparable<T: std::fmt::Debug>(name: &str, left: &T, right: &T) -> Outcome {
+    FailureBuilder::new()
+        .field("relation", name)
+        .field("left", left.to_value())
+        .field("right", right.to_value())
+        .build("incomparable")
+}
+
+/// Relation between 2 values based on the PartialEq trait
+pub struct RelationPartialEq<T> {
+    left: T,
+    right: T,
+}
+
+impl<T: PartialEq + std::fmt::Debug> Property for RelationPartialEq<T> {
+    fn result(&self) -> Outcome {
+        // a value not equal to itself (e.g. NaN) can't be compared
+        #[allow(clippy::eq_op)]
+        let reflexive = self.left == self.left && self.right == self.right;
+        if !reflexive {
+            incomparable(EQ_OP.name, &self.left, &self.right)
+        } else if self.left == self.right {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(relation_element(EQ_OP.name, &self.left, &self.right))
+        }
+    }
+
+    fn probe(&self) -> Option<Element> {
+        Some(relation_element(EQ_OP.name, &self.left, &self.right))
+    }
+}
+
+/// Relation between 2 values based on the PartialOrd trait
+pub struct RelationPartialOrd<T> {
+    left: T,
+    right: T,
+    op: &'static NamedOp<Ordering>,
+}
+
+impl<T: PartialOrd + std::fmt::Debug> Property for RelationPartialOrd<T> {
+    fn result(&self) -> Outcome {
+        match self.left.partial_cmp(&self.right) {
+            None => incomparable(self.op.name, &self.left, &self.right),
+            Some(o) if (self.op.op)(o) => Outcome::Passed,
+            Some(_) => Outcome::Failed(relation_element(self.op.name, &self.left, &self.right)),
+        }
+    }
+
+    fn probe(&self) -> Option<Element> {
+        Some(relation_element(self.op.name, &self.left, &self.right))
+    }
 }
 
 /// Check that 2 elements are equal
@@ -129,3 +191,150 @@ pub fn less_equal<T: Ord>(left: T, right: T) -> RelationOrd<T> {
         op: &LE_OP,
     }
 }
+
+/// Check that 2 elements are equal, for types only partially equivalent
+///
+/// Elements not equal to themselves (e.g. NaN) are reported as incomparable
+pub fn partial_equal<T: PartialEq>(left: T, right: T) -> RelationPartialEq<T> {
+    RelationPartialEq { left, right }
+}
+
+/// Check that the left element is greater than the right element, for types only
+/// partially ordered
+///
+/// Elements without an ordering (e.g. NaN) are reported as incomparable
+pub fn partial_greater<T: PartialOrd>(left: T, right: T) -> RelationPartialOrd<T> {
+    RelationPartialOrd {
+        left,
+        right,
+        op: &GT_OP,
+    }
+}
+
+/// Check that the left element is greater or equal than the right element, for types
+/// only partially ordered
+///
+/// Elements without an ordering (e.g. NaN) are reported as incomparable
+pub fn partial_greater_equal<T: PartialOrd>(left: T, right: T) -> RelationPartialOrd<T> {
+    RelationPartialOrd {
+        left,
+        right,
+        op: &GE_OP,
+    }
+}
+
+/// Check that the left element is less than the right element, for types only
+/// partially ordered
+///
+/// Elements without an ordering (e.g. NaN) are reported as incomparable
+///
+/// ```
+/// use smoke::property::partial_less;
+///
+/// let property = partial_less(0.1 + 0.2, 0.31);
+/// ```
+pub fn partial_less<T: PartialOrd>(left: T, right: T) -> RelationPartialOrd<T> {
+    RelationPartialOrd {
+        left,
+        right,
+        op: &LT_OP,
+    }
+}
+
+/// Check that the left element is less or equal than the right element, for types
+/// only partially ordered
+///
+/// Elements without an ordering (e.g. NaN) are reported as incomparable
+pub fn partial_less_equal<T: PartialOrd>(left: T, right: T) -> RelationPartialOrd<T> {
+    RelationPartialOrd {
+        left,
+        right,
+        op: &LE_OP,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ux::{Elements, Value};
+
+    /// The failure tree of a failed property, with the failed operation as root key
+    fn failure(p: impl Property) -> Elements {
+        match p.result() {
+            Outcome::Failed(e) => match Value::sub(e) {
+                Value::Tree(elements) => elements,
+                _ => unreachable!(),
+            },
+            o => panic!("property expected to fail: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(equal(1, 1).result(), Outcome::Passed);
+        assert_eq!(not_equal(1, 2).result(), Outcome::Passed);
+        assert_eq!(greater(2, 1).result(), Outcome::Passed);
+        assert_eq!(greater_equal(1, 1).result(), Outcome::Passed);
+        assert_eq!(less(1, 2).result(), Outcome::Passed);
+        assert_eq!(less_equal(2, 2).result(), Outcome::Passed);
+        assert!(matches!(equal(1, 2).result(), Outcome::Failed(_)));
+        assert!(matches!(less(2, 2).result(), Outcome::Failed(_)));
+        let f = failure(equal(3u32, 4u32));
+        assert_eq!(f.find_path(&["==", "left"]), Some(&Value::Int(3)));
+        assert_eq!(f.find_path(&["==", "right"]), Some(&Value::Int(4)));
+        let f = failure(equal("3", "4"));
+        assert_eq!(f.find_path(&["==", "left"]), Some(&Value::from("\"3\"")));
+    }
+
+    #[test]
+    fn partial_comparisons() {
+        assert_eq!(partial_equal(0.5, 0.5).result(), Outcome::Passed);
+        assert_eq!(partial_greater(1.0, -0.0).result(), Outcome::Passed);
+        assert_eq!(partial_greater_equal(0.0, -0.0).result(), Outcome::Passed);
+        assert_eq!(
+            partial_less(f64::NEG_INFINITY, f64::MIN).result(),
+            Outcome::Passed
+        );
+        assert_eq!(partial_less_equal(1.5, 1.5).result(), Outcome::Passed);
+        match partial_less(2.0, 1.0).result() {
+            Outcome::Failed(e) => assert_eq!(e.display(0), "<: \n  left: 2.0\n  right: 1.0\n"),
+            o => panic!("unexpected {:?}", o),
+        }
+        match partial_greater(f64::NAN, 1.0).result() {
+            Outcome::Failed(e) => assert_eq!(
+                e.display(0),
+                "incomparable: \n  relation: >\n  left: NaN\n  right: 1.0\n"
+            ),
+            o => panic!("unexpected {:?}", o),
+        }
+        match partial_equal(f64::NAN, f64::NAN).result() {
+            Outcome::Failed(e) => assert_eq!(e.key(), "incomparable"),
+            o => panic!("unexpected {:?}", o),
+        }
+        match partial_equal(1.0, 2.0).result() {
+            Outcome::Failed(e) => assert_eq!(e.key(), "=="),
+            o => panic!("unexpected {:?}", o),
+        }
+    }
+
+    #[test]
+    fn comparisons_discarded() {
+        assert_eq!(equal(1, 2).when(false).result(), Outcome::Discarded);
+        assert_eq!(less(3, 2).when(false).result(), Outcome::Discarded);
+        assert_eq!(
+            less(1, 2).when(true).and(equal(0, 1).when(false)).result(),
+            Outcome::Discarded
+        );
+        assert_eq!(
+            greater(1, 2).when(false).or(greater(2, 1)).result(),
+            Outcome::Passed
+        );
+        match equal(1, 2).and(less(1, 2).when(false)).result() {
+            Outcome::Failed(e) => assert_eq!(
+                e.display(0),
+                "and: \n  left: \n    ==: \n      left: 1\n      right: 2\n  right: discarded\n"
+            ),
+            o => panic!("unexpected {:?}", o),
+        }
+    }
+}
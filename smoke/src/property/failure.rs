@@ -0,0 +1,114 @@
+use super::api::Outcome;
+use crate::ux::{Element, Elements, Value};
+
+/// Builder of the failure tree of a property, for the `Property` implementations
+///
+/// The fields are reported in the order they are added, and the nested fields are
+/// built with their own builder, e.g. for a custom primality property reporting
+/// the smallest divisor found:
+///
+/// ```
+/// use smoke::property::{FailureBuilder, Outcome, Property};
+///
+/// struct IsPrime(u64);
+///
+/// impl Property for IsPrime {
+///     fn result(&self) -> Outcome {
+///         let n = self.0;
+///         let divisor = (2..).take_while(|d| d * d <= n).find(|d| n % d == 0);
+///         match divisor {
+///             None if n >= 2 => Outcome::Passed,
+///             None => FailureBuilder::new()
+///                 .field("value", n)
+///                 .field("reason", "lower than 2")
+///                 .build("is_prime"),
+///             Some(d) => FailureBuilder::new()
+///                 .field("value", n)
+///                 .nested("smallest divisor", |b| b.field("divisor", d).field("quotient", n / d))
+///                 .build("is_prime"),
+///         }
+///     }
+/// }
+///
+/// assert_eq!(IsPrime(97).result(), Outcome::Passed);
+/// match IsPrime(91).result() {
+///     Outcome::Failed(e) => assert_eq!(
+///         e.display(0),
+///         "is_prime: \n  value: 91\n  smallest divisor: \n    divisor: 7\n    quotient: 13\n"
+///     ),
+///     o => panic!("91 is not prime: {:?}", o),
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FailureBuilder {
+    fields: Elements,
+}
+
+impl FailureBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the field `key`, with a value such as a string, a boolean, an integer,
+    /// or `Value::from_debug` of any other value
+    pub fn field(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.fields.append(key, value.into());
+        self
+    }
+
+    /// Add the field `key`, with the fields added by `build` to a new builder
+    pub fn nested<F>(self, key: &str, build: F) -> Self
+    where
+        F: FnOnce(FailureBuilder) -> FailureBuilder,
+    {
+        self.field(key, build(FailureBuilder::new()))
+    }
+
+    /// The failure tree, under the name of the failed operation
+    pub fn element(self, op_name: &str) -> Element {
+        Element::new(op_name, self.fields.into())
+    }
+
+    /// The failed outcome, under the name of the failed operation
+    pub fn build(self, op_name: &str) -> Outcome {
+        Outcome::Failed(self.element(op_name))
+    }
+}
+
+/// The fields of the builder, as a tree value
+impl From<FailureBuilder> for Value {
+    fn from(builder: FailureBuilder) -> Self {
+        Value::Tree(builder.fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_tree() {
+        let outcome = FailureBuilder::new()
+            .field("name", "sample")
+            .field("valid", false)
+            .nested("sizes", |b| {
+                b.field("min", 1u32)
+                    .nested("max", |b| b.field("bytes", 4096u64))
+            })
+            .field("input", Value::from_debug(&[1.5]))
+            .build("decode");
+        let e = match outcome {
+            Outcome::Failed(e) => e,
+            o => panic!("failure expected: {:?}", o),
+        };
+        assert_eq!(e.key(), "decode");
+        assert_eq!(
+            e.display(0),
+            "decode: \n  name: sample\n  valid: false\n  sizes: \n    min: 1\n    max: \n      bytes: 4096\n  input: [1.5]\n"
+        );
+        assert_eq!(
+            FailureBuilder::new().element("empty").display(0),
+            "empty: \n"
+        );
+    }
+}
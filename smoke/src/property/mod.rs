@@ -3,8 +3,14 @@
 mod api;
 mod collection;
 mod comparison;
+mod failure;
+mod snapshot;
+mod variant;
 
 pub use api::*;
 
-pub use collection::Collection;
+pub use collection::{equal_slice, equal_vec, Collection, SliceEq, VecEq};
 pub use comparison::*;
+pub use failure::FailureBuilder;
+pub use snapshot::{matches_snapshot, MatchesSnapshot};
+pub use variant::*;
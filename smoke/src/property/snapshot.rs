@@ -0,0 +1,232 @@
+use super::api::{Outcome, Property};
+use super::failure::FailureBuilder;
+use crate::config::update_snapshots;
+use crate::ux::Value;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of differing lines reported by a snapshot failure
+const MAX_DIFF_LINES: usize = 10;
+
+/// Lines differing between the snapshot and the actual value, by line number
+///
+/// The values only differing by their line endings (`\r\n` or `\n`) or their
+/// trailing newline have the same lines, and are reported as such.
+fn line_diff(expected_str: &str, actual_str: &str) -> FailureBuilder {
+    let expected: Vec<&str> = expected_str.lines().collect();
+    let actual: Vec<&str> = actual_str.lines().collect();
+    let line = |lines: &[&str], i: usize| match lines.get(i) {
+        None => Value::from("missing"),
+        Some(l) => Value::from_debug(l),
+    };
+    let differing: Vec<usize> = (0..expected.len().max(actual.len()))
+        .filter(|i| expected.get(*i) != actual.get(*i))
+        .take(MAX_DIFF_LINES)
+        .collect();
+    let mut diff = FailureBuilder::new();
+    for i in differing.iter().copied() {
+        diff = diff.nested(&format!("line {}", i + 1), |change| {
+            change
+                .field("snapshot", line(&expected, i))
+                .field("actual", line(&actual, i))
+        });
+    }
+    if differing.is_empty() && expected_str != actual_str {
+        diff = diff.nested("line endings or trailing newline differ", |change| {
+            change
+                .field("snapshot", Value::from_debug(expected_str))
+                .field("actual", Value::from_debug(actual_str))
+        });
+    }
+    diff
+}
+
+fn snapshot_failure(path: &Path, reason: Value) -> Outcome {
+    FailureBuilder::new()
+        .field("path", path.display().to_string())
+        .field("reason", reason)
+        .build("snapshot")
+}
+
+/// Compare `actual` with the snapshot stored at `path`, or when `update` is set,
+/// store `actual` in the snapshot if it differs, discarding the test with the
+/// reason of the discard
+fn snapshot_outcome(path: &Path, actual: &str, update: bool) -> (Outcome, Option<String>) {
+    let stored = match std::fs::read_to_string(path) {
+        Ok(stored) => Some(stored),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return (snapshot_failure(path, Value::from(e.to_string())), None),
+    };
+    let outcome = match stored {
+        Some(stored) if stored == actual => Outcome::Passed,
+        _ if update => {
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(path, actual));
+            match written {
+                // a created or updated snapshot is not a test of the value
+                Ok(()) => {
+                    let change = if stored.is_none() {
+                        "created"
+                    } else {
+                        "updated"
+                    };
+                    let reason = format!("snapshot {}: {}", change, path.display());
+                    return (Outcome::Discarded, Some(reason));
+                }
+                Err(e) => snapshot_failure(path, Value::from(e.to_string())),
+            }
+        }
+        None => snapshot_failure(
+            path,
+            Value::from("missing snapshot, set SMOKE_UPDATE_SNAPSHOTS to create it"),
+        ),
+        Some(stored) => snapshot_failure(path, line_diff(&stored, actual).into()),
+    };
+    (outcome, None)
+}
+
+/// Path of the snapshot `name`, in the tests/snapshots directory of the crate
+/// being tested
+fn snapshot_path(name: &str) -> PathBuf {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    Path::new(&root)
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.snap", name))
+}
+
+/// Equality of a value with its snapshot, see `matches_snapshot`
+pub struct MatchesSnapshot {
+    path: PathBuf,
+    actual: String,
+    update: bool,
+    discard_reason: RefCell<Option<String>>,
+}
+
+impl Property for MatchesSnapshot {
+    fn result(&self) -> Outcome {
+        let (outcome, discard_reason) = snapshot_outcome(&self.path, &self.actual, self.update);
+        *self.discard_reason.borrow_mut() = discard_reason;
+        outcome
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.discard_reason.borrow().clone()
+    }
+}
+
+/// Check that `actual` is identical to the snapshot `name`, stored in the file
+/// `tests/snapshots/<name>.snap` of the crate being tested
+///
+/// The failures report the differing lines. When the SMOKE_UPDATE_SNAPSHOTS
+/// environment variable is set, the missing or different snapshots are written
+/// instead, and the tests creating or updating them are reported as skipped, with
+/// the path of the snapshot.
+///
+/// With a generator, each input needs its own snapshot, e.g. with a name derived
+/// from the iteration index, and a fixed seed so that the inputs stay the same:
+///
+/// ```no_run
+/// use smoke::{forall, generator::num, property::matches_snapshot, Context, Seed, Testable};
+///
+/// let mut ctx = Context::new();
+/// ctx.set_seed(Seed::from(1));
+/// ctx.set_nb_tests(20);
+/// forall(num::<u32>())
+///     .ensure_indexed(|idx, n| matches_snapshot(&format!("hex-{}", idx), &format!("{:x}", n)))
+///     .run(&mut ctx);
+/// ```
+pub fn matches_snapshot(name: &str, actual: &str) -> MatchesSnapshot {
+    MatchesSnapshot {
+        path: snapshot_path(name),
+        actual: actual.to_string(),
+        update: update_snapshots(),
+        discard_reason: RefCell::new(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("smoke-snapshots-{}", std::process::id()));
+        dir.join(name).join("value.snap")
+    }
+
+    fn failure_display((outcome, _): (Outcome, Option<String>)) -> String {
+        match outcome {
+            Outcome::Failed(e) => e.display(0),
+            o => panic!("snapshot expected to fail: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn snapshot_lifecycle() {
+        let path = temp_path("lifecycle");
+        let _ = std::fs::remove_file(&path);
+
+        let missing = failure_display(snapshot_outcome(&path, "a\nb\n", false));
+        assert!(missing.contains("missing snapshot"));
+        assert!(!path.exists());
+
+        assert_eq!(
+            snapshot_outcome(&path, "a\nb\n", true),
+            (
+                Outcome::Discarded,
+                Some(format!("snapshot created: {}", path.display()))
+            )
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+        assert_eq!(
+            snapshot_outcome(&path, "a\nb\n", false),
+            (Outcome::Passed, None)
+        );
+        assert_eq!(
+            snapshot_outcome(&path, "a\nb\n", true),
+            (Outcome::Passed, None)
+        );
+
+        let diff = failure_display(snapshot_outcome(&path, "a\nc\nd\n", false));
+        assert_eq!(
+            diff,
+            format!(
+                "snapshot: \n  path: {}\n  reason: \n    line 2: \n      snapshot: \"b\"\n      actual: \"c\"\n    line 3: \n      snapshot: missing\n      actual: \"d\"\n",
+                path.display()
+            )
+        );
+
+        assert_eq!(
+            snapshot_outcome(&path, "a\nc\nd\n", true),
+            (
+                Outcome::Discarded,
+                Some(format!("snapshot updated: {}", path.display()))
+            )
+        );
+        assert_eq!(
+            snapshot_outcome(&path, "a\nc\nd\n", false),
+            (Outcome::Passed, None)
+        );
+
+        let endings = failure_display(snapshot_outcome(&path, "a\r\nc\r\nd", false));
+        assert_eq!(
+            endings,
+            format!(
+                "snapshot: \n  path: {}\n  reason: \n    line endings or trailing newline differ: \n      snapshot: \"a\\nc\\nd\\n\"\n      actual: \"a\\r\\nc\\r\\nd\"\n",
+                path.display()
+            )
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn snapshot_path_in_crate() {
+        let path = snapshot_path("serializer-v1");
+        assert_eq!(
+            path,
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/serializer-v1.snap")
+        );
+    }
+}
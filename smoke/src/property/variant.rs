@@ -0,0 +1,331 @@
+use super::api::{Outcome, Property};
+use super::failure::FailureBuilder;
+use crate::ux::{Element, Value};
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+fn unexpected_variant_element(name: &str, variant: &str, value: &dyn Debug) -> Element {
+    FailureBuilder::new()
+        .field(variant, Value::from_debug(value))
+        .element(name)
+}
+
+fn unexpected_variant(name: &str, variant: &str, value: &dyn Debug) -> Outcome {
+    Outcome::Failed(unexpected_variant_element(name, variant, value))
+}
+
+/// Check that a result is `Ok`
+pub struct IsOk<B, T, E> {
+    result: B,
+    variant: PhantomData<Result<T, E>>,
+}
+
+impl<B, T, E> Property for IsOk<B, T, E>
+where
+    B: Borrow<Result<T, E>>,
+    E: Debug,
+{
+    fn result(&self) -> Outcome {
+        match self.result.borrow() {
+            Ok(_) => Outcome::Passed,
+            Err(e) => unexpected_variant("is ok", "err", e),
+        }
+    }
+}
+
+/// Check that a result is `Err`
+pub struct IsErr<B, T, E> {
+    result: B,
+    variant: PhantomData<Result<T, E>>,
+}
+
+impl<B, T, E> Property for IsErr<B, T, E>
+where
+    B: Borrow<Result<T, E>>,
+    T: Debug,
+{
+    fn result(&self) -> Outcome {
+        match self.result.borrow() {
+            Ok(v) => unexpected_variant("is err", "ok", v),
+            Err(_) => Outcome::Passed,
+        }
+    }
+}
+
+/// Check that a result is `Ok` and that its value satisfies a property
+pub struct OkSatisfies<B, T, E, F> {
+    result: B,
+    f: F,
+    variant: PhantomData<Result<T, E>>,
+}
+
+impl<B, T, E, F, P> Property for OkSatisfies<B, T, E, F>
+where
+    B: Borrow<Result<T, E>>,
+    E: Debug,
+    F: Fn(&T) -> P,
+    P: Property,
+{
+    fn result(&self) -> Outcome {
+        match self.result.borrow() {
+            Ok(v) => (self.f)(v).result(),
+            Err(e) => unexpected_variant("ok satisfies", "err", e),
+        }
+    }
+
+    fn probe(&self) -> Option<Element> {
+        match self.result.borrow() {
+            Ok(v) => (self.f)(v).probe(),
+            Err(e) => Some(unexpected_variant_element("ok satisfies", "err", e)),
+        }
+    }
+}
+
+/// Check that a result, owned or borrowed, is `Ok`, reporting the error otherwise
+///
+/// ```
+/// use smoke::property::is_ok;
+///
+/// let property = is_ok("12".parse::<u32>());
+/// ```
+pub fn is_ok<B, T, E>(result: B) -> IsOk<B, T, E>
+where
+    B: Borrow<Result<T, E>>,
+{
+    IsOk {
+        result,
+        variant: PhantomData,
+    }
+}
+
+/// Check that a result, owned or borrowed, is `Err`, reporting the value otherwise
+///
+/// ```
+/// use smoke::property::is_err;
+///
+/// let parsed = "-12".parse::<u32>();
+/// let property = is_err(&parsed);
+/// ```
+pub fn is_err<B, T, E>(result: B) -> IsErr<B, T, E>
+where
+    B: Borrow<Result<T, E>>,
+{
+    IsErr {
+        result,
+        variant: PhantomData,
+    }
+}
+
+/// Check that a result, owned or borrowed, is `Ok`, and that the property built
+/// by `f` from its value holds
+///
+/// ```
+/// use smoke::property::{ok_satisfies, less};
+///
+/// let property = ok_satisfies("12".parse::<u32>(), |v| less(*v, 100));
+/// ```
+pub fn ok_satisfies<B, T, E, F, P>(result: B, f: F) -> OkSatisfies<B, T, E, F>
+where
+    B: Borrow<Result<T, E>>,
+    F: Fn(&T) -> P,
+    P: Property,
+{
+    OkSatisfies {
+        result,
+        f,
+        variant: PhantomData,
+    }
+}
+
+/// Check that an option is `Some`
+pub struct IsSome<B, T> {
+    option: B,
+    variant: PhantomData<Option<T>>,
+}
+
+impl<B, T> Property for IsSome<B, T>
+where
+    B: Borrow<Option<T>>,
+{
+    fn result(&self) -> Outcome {
+        match self.option.borrow() {
+            Some(_) => Outcome::Passed,
+            None => Outcome::Failed(Element::new("is some", "none".into())),
+        }
+    }
+}
+
+/// Check that an option is `None`
+pub struct IsNone<B, T> {
+    option: B,
+    variant: PhantomData<Option<T>>,
+}
+
+impl<B, T> Property for IsNone<B, T>
+where
+    B: Borrow<Option<T>>,
+    T: Debug,
+{
+    fn result(&self) -> Outcome {
+        match self.option.borrow() {
+            Some(v) => unexpected_variant("is none", "some", v),
+            None => Outcome::Passed,
+        }
+    }
+}
+
+/// Check that an option is `Some` and that its value satisfies a property
+pub struct SomeSatisfies<B, T, F> {
+    option: B,
+    f: F,
+    variant: PhantomData<Option<T>>,
+}
+
+impl<B, T, F, P> Property for SomeSatisfies<B, T, F>
+where
+    B: Borrow<Option<T>>,
+    F: Fn(&T) -> P,
+    P: Property,
+{
+    fn result(&self) -> Outcome {
+        match self.option.borrow() {
+            Some(v) => (self.f)(v).result(),
+            None => Outcome::Failed(Element::new("some satisfies", "none".into())),
+        }
+    }
+
+    fn probe(&self) -> Option<Element> {
+        match self.option.borrow() {
+            Some(v) => (self.f)(v).probe(),
+            None => Some(Element::new("some satisfies", "none".into())),
+        }
+    }
+}
+
+/// Check that an option, owned or borrowed, is `Some`
+///
+/// ```
+/// use smoke::property::is_some;
+///
+/// let property = is_some("smoke".find('m'));
+/// ```
+pub fn is_some<B, T>(option: B) -> IsSome<B, T>
+where
+    B: Borrow<Option<T>>,
+{
+    IsSome {
+        option,
+        variant: PhantomData,
+    }
+}
+
+/// Check that an option, owned or borrowed, is `None`, reporting the value otherwise
+///
+/// ```
+/// use smoke::property::is_none;
+///
+/// let position = "smoke".find('z');
+/// let property = is_none(&position);
+/// ```
+pub fn is_none<B, T>(option: B) -> IsNone<B, T>
+where
+    B: Borrow<Option<T>>,
+{
+    IsNone {
+        option,
+        variant: PhantomData,
+    }
+}
+
+/// Check that an option, owned or borrowed, is `Some`, and that the property built
+/// by `f` from its value holds
+///
+/// ```
+/// use smoke::property::{some_satisfies, equal};
+///
+/// let property = some_satisfies("smoke".find('m'), |idx| equal(*idx, 1));
+/// ```
+pub fn some_satisfies<B, T, F, P>(option: B, f: F) -> SomeSatisfies<B, T, F>
+where
+    B: Borrow<Option<T>>,
+    F: Fn(&T) -> P,
+    P: Property,
+{
+    SomeSatisfies {
+        option,
+        f,
+        variant: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{equal, less};
+
+    fn failure_display(p: impl Property) -> String {
+        match p.result() {
+            Outcome::Failed(e) => e.display(0),
+            o => panic!("property expected to fail: {:?}", o),
+        }
+    }
+
+    #[test]
+    fn result_variants() {
+        let ok: Result<u32, String> = Ok(12);
+        let err: Result<u32, String> = Err("invalid".to_string());
+        assert_eq!(is_ok(&ok).result(), Outcome::Passed);
+        assert_eq!(is_err(err.clone()).result(), Outcome::Passed);
+        assert_eq!(
+            failure_display(is_ok(&err)),
+            "is ok: \n  err: \"invalid\"\n"
+        );
+        assert_eq!(failure_display(is_err(ok)), "is err: \n  ok: 12\n");
+    }
+
+    #[test]
+    fn ok_satisfies_property() {
+        let ok: Result<u32, String> = Ok(12);
+        let err: Result<u32, String> = Err("invalid".to_string());
+        assert_eq!(
+            ok_satisfies(&ok, |v| less(*v, 20)).result(),
+            Outcome::Passed
+        );
+        assert_eq!(
+            failure_display(ok_satisfies(&ok, |v| equal(*v, 20))),
+            "==: \n  left: 12\n  right: 20\n"
+        );
+        assert_eq!(
+            failure_display(ok_satisfies(err, |v| less(*v, 20))),
+            "ok satisfies: \n  err: \"invalid\"\n"
+        );
+    }
+
+    #[test]
+    fn option_variants() {
+        let some = Some(12u32);
+        let none: Option<u32> = None;
+        assert_eq!(is_some(&some).result(), Outcome::Passed);
+        assert_eq!(is_none(none).result(), Outcome::Passed);
+        assert_eq!(failure_display(is_some(none)), "is some: none\n");
+        assert_eq!(failure_display(is_none(&some)), "is none: \n  some: 12\n");
+    }
+
+    #[test]
+    fn some_satisfies_property() {
+        let some = Some(12u32);
+        assert_eq!(
+            some_satisfies(&some, |v| less(*v, 20)).result(),
+            Outcome::Passed
+        );
+        assert_eq!(
+            failure_display(some_satisfies(some, |v| equal(*v, 20))),
+            "==: \n  left: 12\n  right: 20\n"
+        );
+        assert_eq!(
+            failure_display(some_satisfies(None::<u32>, |v| less(*v, 20))),
+            "some satisfies: none\n"
+        );
+    }
+}
@@ -13,6 +13,15 @@ use core::num::{
     NonZeroIsize, NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
 };
 
+/// Version of the deterministic generation
+///
+/// For a given seed, the random generator and the generators of this crate always
+/// produce the same values, so that a seed printed by a failing run can be used to
+/// reproduce the failure. Any change that modify the values produced for a given seed
+/// (in `R`, `R::sub`, or in any generator) must bump this version, so that seeds
+/// recorded with a previous version are not blindly reused.
+pub const GENERATION_VERSION: u32 = 4;
+
 /// Seed of random generation
 ///
 /// There should be only one instance of this for a given instance of a test suite,
@@ -29,7 +38,64 @@ pub struct Seed(u128);
 /// from another pseudo random generator using `.sub()`
 /// as to create a hierarchy (or a tree) of generator.
 ///
-pub struct R(u64, u64);
+/// It also carries the size parameter bounding the size of the generated
+/// collections, which is inherited by the sub generators.
+///
+/// Cloning a generator snapshots its state: the clone produces the same sequence
+/// as the original from that point, independently of the original.
+pub struct R {
+    state: u64,
+    inc: u64,
+    size: Option<usize>,
+    #[cfg(feature = "arbitrary")]
+    bytes: Option<ByteSource>,
+}
+
+/// A source of randomness backed by a byte slice, shared by a generator and all
+/// its sub generators, consuming the bytes in order of use
+#[cfg(feature = "arbitrary")]
+#[derive(Clone)]
+struct ByteSource {
+    data: std::sync::Arc<[u8]>,
+    pos: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(feature = "arbitrary")]
+impl ByteSource {
+    /// A source of the same data, starting at the current position but consumed
+    /// independently of this source
+    fn snapshot(&self) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        ByteSource {
+            data: self.data.clone(),
+            pos: std::sync::Arc::new(AtomicUsize::new(self.pos.load(Ordering::Relaxed))),
+        }
+    }
+
+    /// Next 4 bytes of the source as a u32, padded with zeros when exhausted
+    fn next(&self) -> u32 {
+        use std::sync::atomic::Ordering;
+        let pos = self.pos.fetch_add(4, Ordering::Relaxed);
+        let mut buf = [0u8; 4];
+        if pos < self.data.len() {
+            let available = &self.data[pos..self.data.len().min(pos + 4)];
+            buf[..available.len()].copy_from_slice(available);
+        }
+        u32::from_le_bytes(buf)
+    }
+}
+
+impl Clone for R {
+    fn clone(&self) -> Self {
+        R {
+            state: self.state,
+            inc: self.inc,
+            size: self.size,
+            #[cfg(feature = "arbitrary")]
+            bytes: self.bytes.as_ref().map(ByteSource::snapshot),
+        }
+    }
+}
 
 impl Seed {
     /// Create a new random seed, using the system time and the thread-id.
@@ -57,6 +123,36 @@ impl Seed {
         let r = (r1 << 64) | r2;
         Seed::from(r)
     }
+
+    /// Derive deterministically the `index`-th child seed from this seed
+    ///
+    /// This gives reproducible seeds per shard or per property of a parallel run,
+    /// without going through a random generator: the derivation is a constant time
+    /// mix of the seed and the index, and the children of a seed are all distinct.
+    ///
+    /// ```
+    /// use smoke::Seed;
+    ///
+    /// let run = Seed::from(0x1234);
+    /// let shards: Vec<Seed> = (0..4).map(|shard| run.derive(shard)).collect();
+    /// assert_eq!(shards[2], run.derive(2));
+    /// ```
+    pub fn derive(&self, index: u64) -> Self {
+        let hi = (self.0 >> 64) as u64;
+        let lo = self.0 as u64;
+        // for a given seed, `index` -> `key` -> `derived_hi` are bijections
+        let key = mix64(index.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ lo);
+        let derived_hi = mix64(hi ^ key);
+        let derived_lo = mix64(lo ^ derived_hi ^ index);
+        Seed((u128::from(derived_hi) << 64) | u128::from(derived_lo))
+    }
+}
+
+/// Finalizer of splitmix64, a bijection spreading every input bit to all the output bits
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 impl From<u128> for Seed {
@@ -111,19 +207,93 @@ impl R {
     }
 
     pub fn sub(&mut self) -> Self {
-        let r0 = self.0;
-        let r1 = self.1;
+        let r0 = self.state;
+        let r1 = self.inc;
+        #[cfg(feature = "arbitrary")]
+        if let Some(bytes) = &self.bytes {
+            return R {
+                state: r0,
+                inc: r1,
+                size: self.size,
+                bytes: Some(bytes.clone()),
+            };
+        }
         let n = self.next();
-        R(r0.wrapping_mul(n as u64), r1.wrapping_add(n as u64))
+        R {
+            state: r0.wrapping_mul(n as u64),
+            inc: r1.wrapping_add(n as u64),
+            size: self.size,
+            #[cfg(feature = "arbitrary")]
+            bytes: None,
+        }
     }
 
     pub fn from_seed(seed: Seed) -> Self {
-        R((seed.0 >> 64) as u64, seed.0 as u64)
+        R {
+            state: (seed.0 >> 64) as u64,
+            inc: seed.0 as u64,
+            size: None,
+            #[cfg(feature = "arbitrary")]
+            bytes: None,
+        }
+    }
+
+    /// Create a generator using the bytes of `data` as the source of randomness,
+    /// instead of a pseudo random generator
+    ///
+    /// The bytes are consumed in order by this generator and all its sub generators,
+    /// and zeros are generated once all the bytes are consumed, so that the same
+    /// bytes always generate the same values. This allows to drive the generators
+    /// from a fuzzer input:
+    ///
+    /// ```
+    /// use smoke::{Generator, R, generator::{num, range, vector}};
+    ///
+    /// let generator = vector(range(0..16), num::<u16>());
+    /// let fuzz_input = [3, 0, 0, 0, 1, 2, 3, 4, 5, 6];
+    /// let v = generator.gen(&mut R::from_bytes(&fuzz_input));
+    /// assert_eq!(v.len(), 3);
+    /// ```
+    #[cfg(feature = "arbitrary")]
+    pub fn from_bytes(data: &[u8]) -> Self {
+        R {
+            state: 0,
+            inc: 0,
+            size: None,
+            bytes: Some(ByteSource {
+                data: data.into(),
+                pos: Default::default(),
+            }),
+        }
+    }
+
+    /// Size parameter bounding the size of generated collections, if any
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    /// Set the size parameter bounding the size of generated collections
+    pub fn set_size(&mut self, size: Option<usize>) {
+        self.size = size
+    }
+
+    /// Bound a generated collection size by the size parameter
+    pub fn bounded_size(&self, sz: usize) -> usize {
+        match self.size {
+            None => sz,
+            Some(max) => sz.min(max),
+        }
     }
 
     pub(crate) fn next(&mut self) -> u32 {
-        let old_state = self.0;
-        self.0 = old_state.wrapping_mul(MUL_FACTOR).wrapping_add(self.1 | 1);
+        #[cfg(feature = "arbitrary")]
+        if let Some(bytes) = &self.bytes {
+            return bytes.next();
+        }
+        let old_state = self.state;
+        self.state = old_state
+            .wrapping_mul(MUL_FACTOR)
+            .wrapping_add(self.inc | 1);
         let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
         let rot = (old_state >> 59) as u32;
         xor_shifted.rotate_right(rot)
@@ -146,6 +316,13 @@ impl R {
         }
     }
 
+    /// Generate a new vector of `n` random bytes
+    pub fn gen_bytes(&mut self, n: usize) -> Vec<u8> {
+        let mut buf = vec![0; n];
+        self.next_bytes(&mut buf);
+        buf
+    }
+
     pub fn ascii(&mut self) -> char {
         loop {
             let v = self.next() % 0x80;
@@ -168,6 +345,44 @@ impl R {
         (self.next() % 2) == 1
     }
 
+    /// Choose an index of `weights`, with a probability proportional to its weight
+    ///
+    /// The entries with a weight of 0 are never chosen, and the sum of the weights
+    /// must be greater than 0.
+    ///
+    /// ```
+    /// use smoke::{R, Seed};
+    ///
+    /// let mut r = R::from_seed(Seed::from(1));
+    /// // "b" is chosen 3 times more often than "a", and "c" is never chosen
+    /// let idx = r.weighted_index(&[1, 3, 0]);
+    /// assert!(idx < 2);
+    /// ```
+    pub fn weighted_index(&mut self, weights: &[usize]) -> usize {
+        let cumulative: Vec<usize> = weights
+            .iter()
+            .scan(0usize, |sum, w| {
+                *sum += w;
+                Some(*sum)
+            })
+            .collect();
+        self.cumulative_weighted_index(&cumulative)
+    }
+
+    /// Similar to `weighted_index`, from the cumulative sums of the weights
+    pub(crate) fn cumulative_weighted_index(&mut self, cumulative: &[usize]) -> usize {
+        let total = cumulative.last().copied().unwrap_or(0);
+        assert!(total > 0, "the sum of the weights must be greater than 0");
+        let nb = self.num_range(0, total - 1);
+        cumulative.partition_point(|c| *c <= nb)
+    }
+
+    /// Return true with a probability of `numerator / denominator`
+    pub fn gen_ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+        assert!(denominator > 0 && numerator <= denominator);
+        self.num_range(0, denominator - 1) < numerator
+    }
+
     pub fn num<T: NumPrimitive>(&mut self) -> T {
         T::num(self)
     }
@@ -252,6 +467,32 @@ impl NumPrimitive for u32 {
     }
 }
 
+/// Value between min_value and max_value (both included) for the types wider than
+/// the 32 bits output of the random generator.
+///
+/// Wide ranges use rejection sampling, as the modulo of a random value by a range
+/// close to the type maximum is very biased toward the low values
+macro_rules! wide_num_range {
+    ($ty:ty, $r:ident, $min_value:ident, $max_value:ident) => {{
+        assert!($min_value <= $max_value);
+        let diff = ($max_value - $min_value).wrapping_add(1);
+        if diff == 0 {
+            // the whole domain of the type
+            <$ty>::num($r)
+        } else if diff > 0xffff_ffff {
+            let zone = <$ty>::MAX - (<$ty>::MAX - diff + 1) % diff;
+            loop {
+                let v = <$ty>::num($r);
+                if v <= zone {
+                    break $min_value + v % diff;
+                }
+            }
+        } else {
+            $min_value + ($r.next() as $ty % diff)
+        }
+    }};
+}
+
 impl NumPrimitive for u64 {
     fn num(r: &mut R) -> Self {
         let v1 = r.next() as u64;
@@ -259,14 +500,7 @@ impl NumPrimitive for u64 {
         v1 << 32 | v2
     }
     fn num_range(r: &mut R, min_value: Self, max_value: Self) -> Self {
-        assert!(min_value <= max_value);
-        let diff = max_value - min_value + 1;
-        if diff > 0xffff_ffff {
-            let v = Self::num(r) % diff;
-            min_value + v
-        } else {
-            min_value + (r.next() as Self % diff)
-        }
+        wide_num_range!(Self, r, min_value, max_value)
     }
 }
 
@@ -279,36 +513,30 @@ impl NumPrimitive for u128 {
         v1 << 96 | v2 << 64 | v3 << 32 | v4
     }
     fn num_range(r: &mut R, min_value: Self, max_value: Self) -> Self {
-        assert!(min_value <= max_value);
-        let diff = max_value - min_value + 1;
-        if diff > 0xffff_ffff {
-            let v = Self::num(r) % diff;
-            min_value + v
-        } else {
-            min_value + (r.next() as Self % diff)
-        }
+        wide_num_range!(Self, r, min_value, max_value)
     }
 }
 
+/// usize (and isize) values are generated as u64 (and i64) values, whatever the width
+/// of the target, so that a seed yields the same sizes and indices on all platforms:
+/// `num` truncates the 64 bits value to the target width, and `num_range` generates the
+/// u64 value in the range, which fits in the target width.
 impl NumPrimitive for usize {
     fn num(r: &mut R) -> Self {
-        if std::mem::size_of::<usize>() <= 4 {
-            u32::num(r) as usize
-        } else if std::mem::size_of::<usize>() == 8 {
-            u64::num(r) as usize
-        } else {
-            u128::num(r) as usize
-        }
+        u64::num(r) as usize
+    }
+    fn num_range(r: &mut R, min_value: Self, max_value: Self) -> Self {
+        u64::num_range(r, min_value as u64, max_value as u64) as usize
+    }
+}
+
+impl NumPrimitive for isize {
+    fn num(r: &mut R) -> Self {
+        i64::num(r) as isize
     }
     fn num_range(r: &mut R, min_value: Self, max_value: Self) -> Self {
         assert!(min_value <= max_value);
-        let diff = max_value - min_value + 1;
-        if diff > 0xffff_ffff {
-            let v = Self::num(r) % diff;
-            min_value + v
-        } else {
-            min_value + (r.next() as Self % diff)
-        }
+        i64::num_range(r, min_value as i64, max_value as i64) as isize
     }
 }
 
@@ -347,7 +575,6 @@ define_NumPrimitive_impl_signed!(i16, u16);
 define_NumPrimitive_impl_signed!(i32, u32);
 define_NumPrimitive_impl_signed!(i64, u64);
 define_NumPrimitive_impl_signed!(i128, u128);
-define_NumPrimitive_impl_signed!(isize, usize);
 
 // retry Ty -> NonZeroTy convertion based implementation
 
@@ -391,6 +618,19 @@ define_NumPrimitive_impl_nonzero!(NonZeroIsize, isize);
 mod tests {
     use super::*;
 
+    #[test]
+    fn clone_same_sequence() {
+        let mut r = R::from_seed(Seed::from(0x1234));
+        r.next();
+        let mut cloned = r.clone();
+        let original: Vec<u64> = (0..16).map(|_| r.num()).collect();
+        let replayed: Vec<u64> = (0..16).map(|_| cloned.num()).collect();
+        assert_eq!(original, replayed);
+        // the clone is independent of the original
+        cloned.next();
+        assert_ne!(r.num::<u64>(), cloned.num::<u64>());
+    }
+
     #[test]
     fn string_seed() {
         assert_eq!(
@@ -407,4 +647,91 @@ mod tests {
             Seed::from(0x10000000_01020304_12412414_09080706)
         )
     }
+
+    #[test]
+    fn seed_derive() {
+        let parent = Seed::from(0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+        assert_eq!(parent.derive(7), parent.derive(7));
+        assert_ne!(parent.derive(0), parent);
+        assert_ne!(parent.derive(1), Seed::from(0x42).derive(1));
+
+        let mut children: Vec<u128> = (0..10_000).map(|i| parent.derive(i).0).collect();
+        children.sort_unstable();
+        children.dedup();
+        assert_eq!(children.len(), 10_000);
+        // the high bits of the children, selecting the random generator stream, differ too
+        let mut streams: Vec<u64> = children.iter().map(|c| (c >> 64) as u64).collect();
+        streams.sort_unstable();
+        streams.dedup();
+        assert_eq!(streams.len(), 10_000);
+        // even from a degenerate seed
+        assert_ne!(Seed::from(0).derive(0), Seed::from(0).derive(1));
+    }
+
+    #[test]
+    fn wide_range_uniform() {
+        let mut r = R::from_seed(Seed::from(0x5eed));
+        let max = u64::MAX / 3 * 2;
+        let bucket_size = max / 4 + 1;
+        let mut buckets = [0u32; 4];
+        for _ in 0..10_000 {
+            let v = r.num_range(0, max);
+            buckets[(v / bucket_size) as usize] += 1;
+        }
+        for b in buckets.iter() {
+            assert!((2250..2750).contains(b), "buckets {:?}", buckets);
+        }
+    }
+
+    #[test]
+    fn full_range() {
+        let mut r = R::from_seed(Seed::from(0x5eed));
+        let _ = r.num_range(0, u64::MAX);
+        assert_eq!(r.num_range(u128::MAX, u128::MAX), u128::MAX);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn from_bytes_deterministic() {
+        use crate::generator::{num, range, vector};
+        use crate::Generator;
+
+        let g = vector(range(0..8), num::<u32>()).and(num::<u8>());
+        let data: Vec<u8> = (0..64).collect();
+        let v = g.gen(&mut R::from_bytes(&data));
+        assert_eq!(v, g.gen(&mut R::from_bytes(&data)));
+
+        let exhausted = g.gen(&mut R::from_bytes(&[2]));
+        assert_eq!(exhausted, (vec![0, 0], 0));
+    }
+
+    #[test]
+    fn weighted_index_distribution() {
+        let mut r = R::from_seed(Seed::from(0x5eed));
+        let weights = [1, 0, 3, 0, 6];
+        let mut counts = [0u32; 5];
+        for _ in 0..100_000 {
+            counts[r.weighted_index(&weights)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert_eq!(counts[3], 0);
+        assert!((9_000..11_000).contains(&counts[0]), "counts {:?}", counts);
+        assert!((28_500..31_500).contains(&counts[2]), "counts {:?}", counts);
+        assert!((58_500..61_500).contains(&counts[4]), "counts {:?}", counts);
+    }
+
+    #[test]
+    #[should_panic(expected = "the sum of the weights must be greater than 0")]
+    fn weighted_index_zero_weights() {
+        R::from_seed(Seed::from(1)).weighted_index(&[0, 0]);
+    }
+
+    #[test]
+    fn gen_bytes() {
+        let seed = Seed::from(0x1234);
+        let mut buf = [0u8; 16];
+        R::from_seed(seed).next_bytes(&mut buf);
+        assert_eq!(R::from_seed(seed).gen_bytes(16), buf);
+        assert!(R::from_seed(seed).gen_bytes(0).is_empty());
+    }
 }
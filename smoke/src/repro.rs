@@ -0,0 +1,219 @@
+//! Reproduction of the failures as Rust unit tests
+//!
+//! When the SMOKE_EMIT_REPRO environment variable is set (or with
+//! `Context::set_emit_repro`), the failures of the named properties come with a
+//! ready to paste `#[test]` function, calling a check function named after the
+//! property with the failing input. The input is written as a Rust literal for
+//! the properties using `Ensure::with_rust_literal`, and as a comment of its
+//! `Debug` representation otherwise.
+
+/// Values that can be written as a Rust expression evaluating to themselves
+pub trait ToRustLiteral {
+    /// Rust expression of this value, e.g. `42u32` or `vec![Some(1u8), None]`
+    fn to_rust_literal(&self) -> String;
+}
+
+macro_rules! define_ToRustLiteral_integer {
+    ($($t:ty),*) => {
+        $(
+            impl ToRustLiteral for $t {
+                fn to_rust_literal(&self) -> String {
+                    format!("{}{}", self, stringify!($t))
+                }
+            }
+        )*
+    };
+}
+
+define_ToRustLiteral_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+macro_rules! define_ToRustLiteral_float {
+    ($($t:ident),*) => {
+        $(
+            impl ToRustLiteral for $t {
+                fn to_rust_literal(&self) -> String {
+                    if self.is_nan() {
+                        format!("{}::NAN", stringify!($t))
+                    } else if self.is_infinite() && *self > 0.0 {
+                        format!("{}::INFINITY", stringify!($t))
+                    } else if self.is_infinite() {
+                        format!("{}::NEG_INFINITY", stringify!($t))
+                    } else {
+                        format!("{:?}{}", self, stringify!($t))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+define_ToRustLiteral_float!(f32, f64);
+
+impl ToRustLiteral for bool {
+    fn to_rust_literal(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// The `Debug` escapes of chars and strings are valid Rust escapes
+impl ToRustLiteral for char {
+    fn to_rust_literal(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl ToRustLiteral for str {
+    fn to_rust_literal(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl ToRustLiteral for String {
+    fn to_rust_literal(&self) -> String {
+        format!("String::from({:?})", self)
+    }
+}
+
+impl<T: ToRustLiteral + ?Sized> ToRustLiteral for &T {
+    fn to_rust_literal(&self) -> String {
+        (**self).to_rust_literal()
+    }
+}
+
+impl<T: ToRustLiteral> ToRustLiteral for Option<T> {
+    fn to_rust_literal(&self) -> String {
+        match self {
+            None => "None".to_string(),
+            Some(v) => format!("Some({})", v.to_rust_literal()),
+        }
+    }
+}
+
+fn join_literals<'a, T: ToRustLiteral + 'a>(elements: impl Iterator<Item = &'a T>) -> String {
+    elements
+        .map(|e| e.to_rust_literal())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<T: ToRustLiteral> ToRustLiteral for Vec<T> {
+    fn to_rust_literal(&self) -> String {
+        format!("vec![{}]", join_literals(self.iter()))
+    }
+}
+
+impl<T: ToRustLiteral, const N: usize> ToRustLiteral for [T; N] {
+    fn to_rust_literal(&self) -> String {
+        format!("[{}]", join_literals(self.iter()))
+    }
+}
+
+impl ToRustLiteral for () {
+    fn to_rust_literal(&self) -> String {
+        "()".to_string()
+    }
+}
+
+macro_rules! define_ToRustLiteral_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: ToRustLiteral),+> ToRustLiteral for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn to_rust_literal(&self) -> String {
+                let ($($name,)+) = self;
+                let elements = [$($name.to_rust_literal()),+];
+                if elements.len() == 1 {
+                    format!("({},)", elements[0])
+                } else {
+                    format!("({})", elements.join(", "))
+                }
+            }
+        }
+    };
+}
+
+define_ToRustLiteral_tuple!(A);
+define_ToRustLiteral_tuple!(A, B);
+define_ToRustLiteral_tuple!(A, B, C);
+define_ToRustLiteral_tuple!(A, B, C, D);
+define_ToRustLiteral_tuple!(A, B, C, D, E);
+define_ToRustLiteral_tuple!(A, B, C, D, E, F);
+
+/// Identifier of the check function of a property, from the name of the property
+fn check_function(property: Option<&str>) -> String {
+    let name = match property {
+        None => return "check".to_string(),
+        Some(name) => name,
+    };
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert_str(0, "check_");
+    }
+    ident
+}
+
+/// `#[test]` function reproducing a failure of `property`, with the input written
+/// by `literal`, or as a comment of its `debug` representation without literal
+pub(crate) fn repro_test(property: Option<&str>, literal: Option<String>, debug: &str) -> String {
+    let check = check_function(property);
+    let input = match literal {
+        Some(literal) => format!("    let input = {};\n", literal),
+        None => format!(
+            "    // input: {}\n    let input = todo!(\"literal of the input\");\n",
+            debug
+        ),
+    };
+    format!(
+        "#[test]\nfn {}_repro() {{\n{}    {}(input);\n}}\n",
+        check, input, check
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals() {
+        assert_eq!(42u32.to_rust_literal(), "42u32");
+        assert_eq!(i64::MIN.to_rust_literal(), "-9223372036854775808i64");
+        assert_eq!(1.5f64.to_rust_literal(), "1.5f64");
+        assert_eq!(f32::NEG_INFINITY.to_rust_literal(), "f32::NEG_INFINITY");
+        assert_eq!('\''.to_rust_literal(), "'\\''");
+        assert_eq!("a\"b\n".to_rust_literal(), "\"a\\\"b\\n\"");
+        assert_eq!(
+            "\u{0}é".to_string().to_rust_literal(),
+            "String::from(\"\\0é\")"
+        );
+        assert_eq!(
+            vec![Some(1u8), None].to_rust_literal(),
+            "vec![Some(1u8), None]"
+        );
+        assert_eq!((true,).to_rust_literal(), "(true,)");
+        assert_eq!(
+            (1i8, vec!['x'], [0u16; 2]).to_rust_literal(),
+            "(1i8, vec!['x'], [0u16, 0u16])"
+        );
+    }
+
+    #[test]
+    fn repro_functions() {
+        assert_eq!(
+            repro_test(Some("Parse round-trip"), Some("42u32".to_string()), "42"),
+            "#[test]\nfn parse_round_trip_repro() {\n    let input = 42u32;\n    parse_round_trip(input);\n}\n"
+        );
+        assert_eq!(
+            repro_test(None, None, "Point { x: 1 }"),
+            "#[test]\nfn check_repro() {\n    // input: Point { x: 1 }\n    let input = todo!(\"literal of the input\");\n    check(input);\n}\n"
+        );
+        assert_eq!(check_function(Some("2 lists")), "check_2_lists");
+    }
+}
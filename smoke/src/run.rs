@@ -1,65 +1,464 @@
-use super::generator::Generator;
+use super::config::RunConfig;
+use super::generator::{ArcGenerator, GenError, Generator, GeneratorInPlace};
 use super::initonce::InitOnce;
 use super::property::{self, Property};
-use super::rand::Seed;
-use super::ux::{TestResults, TestRunStatus};
+use super::rand::{Seed, GENERATION_VERSION};
+use super::repro::{repro_test, ToRustLiteral};
+use super::ux::{
+    DebugFormatter, Describe, Element, FailureFormatter, InputStats, TestResults, TestRunStatus,
+};
 use super::R;
-use std::panic::{catch_unwind, set_hook, take_hook, AssertUnwindSafe, PanicInfo};
-use std::time::{Duration, SystemTime};
+use std::any::{Any, TypeId};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::panic::{
+    catch_unwind, resume_unwind, set_hook, take_hook, AssertUnwindSafe, PanicHookInfo,
+};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-const DEFAULT_NB_TESTS: u64 = 1_000;
+#[cfg(feature = "async")]
+use super::executor::{BlockOn, Deadline, Executor};
+#[cfg(feature = "async")]
+use std::future::Future;
 
-const ENV_SEED: &str = "SMOKE_SEED";
-const ENV_NB_TESTS: &str = "SMOKE_NB_TESTS";
-const ENV_NO_PANIC_CATCH: &str = "SMOKE_NO_PANIC_CATCH";
+/// Above this total duration, the timing summary is reported even when not verbose,
+/// and the failures report the progress of the test when they happened
+const TIMING_REPORT_THRESHOLD: Duration = Duration::from_secs(10);
 
-pub struct PanicError(String);
+/// Default interval of the progress report of long tests
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
 
-use crate::generator::SuchThatRetryFailure;
+pub struct PanicError {
+    message: String,
+    details: Option<PanicDetails>,
+}
+
+/// Location and backtrace of a panic caught by smoke
+struct PanicDetails {
+    location: Option<String>,
+    backtrace: Option<String>,
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "panic: \"{}\"", self.message)?;
+        if let Some(details) = &self.details {
+            if let Some(location) = &details.location {
+                writeln!(f, "location: {}", location)?;
+            }
+            if let Some(backtrace) = &details.backtrace {
+                write!(f, "backtrace:\n{}", backtrace)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+use crate::generator::{GenerationFailure, SuchThatRetryFailure};
 
 use std::fmt;
 
 static INSTANCE_SEED: InitOnce<Seed> = InitOnce::init();
 
-fn run_catch_panic<F, R>(f: F) -> Result<R, PanicError>
+type PanicHook = Box<dyn Fn(&PanicHookInfo) + Sync + Send + 'static>;
+
+/// The panic hook installed by the running `run` calls
+///
+/// The hook is installed by the first `run` and the previous hook is restored by the
+/// last one, so that overlapping `run` calls on different threads don't clobber the
+/// hook of the user.
+struct PanicHookState {
+    runs: usize,
+    previous: Option<Arc<PanicHook>>,
+}
+
+static PANIC_HOOK: Mutex<PanicHookState> = Mutex::new(PanicHookState {
+    runs: 0,
+    previous: None,
+});
+
+thread_local! {
+    /// Whether the panics of this thread are caught by smoke, and shouldn't be printed
+    static SILENCE_PANICS: Cell<bool> = const { Cell::new(false) };
+    /// Details of the last panic caught by smoke on this thread
+    static CAUGHT_PANIC: RefCell<Option<PanicDetails>> = const { RefCell::new(None) };
+}
+
+/// Scope of a `run`, restoring the previous panic hook when dropped
+///
+/// The installed hook only silences the panics caught by smoke, recording their
+/// location and their backtrace (when enabled by `RUST_BACKTRACE`) for the failure
+/// report, any other panic is forwarded to the previous hook.
+struct PanicHookScope;
+
+impl PanicHookScope {
+    fn enter() -> Self {
+        let mut state = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        if state.runs == 0 {
+            let previous = Arc::new(take_hook());
+            state.previous = Some(previous.clone());
+            set_hook(Box::new(move |info| {
+                if SILENCE_PANICS.with(Cell::get) {
+                    record_panic(info)
+                } else {
+                    previous(info)
+                }
+            }));
+        }
+        state.runs += 1;
+        PanicHookScope
+    }
+}
+
+impl Drop for PanicHookScope {
+    fn drop(&mut self) {
+        let mut state = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        state.runs -= 1;
+        if state.runs == 0 {
+            // the installed hook holds the other reference to the previous hook
+            drop(take_hook());
+            if let Some(previous) = state.previous.take() {
+                match Arc::try_unwrap(previous) {
+                    Ok(previous) => set_hook(previous),
+                    Err(previous) => set_hook(Box::new(move |info| previous(info))),
+                }
+            }
+        }
+    }
+}
+
+fn record_panic(info: &PanicHookInfo) {
+    let backtrace = Backtrace::capture();
+    let details = PanicDetails {
+        location: info.location().map(|l| l.to_string()),
+        backtrace: match backtrace.status() {
+            BacktraceStatus::Captured => Some(backtrace.to_string()),
+            _ => None,
+        },
+    };
+    CAUGHT_PANIC.with(|caught| *caught.borrow_mut() = Some(details));
+}
+
+/// Catch the panics of `f` without printing them
+fn catch_silently<F, R>(f: F) -> std::thread::Result<R>
+where
+    F: FnOnce() -> R,
+{
+    let silenced = SILENCE_PANICS.with(|s| s.replace(true));
+    let result = catch_unwind(AssertUnwindSafe(f));
+    SILENCE_PANICS.with(|s| s.set(silenced));
+    result
+}
+
+fn run_catch_panic<F, R>(catch_panics: bool, f: F) -> Result<R, PanicError>
 where
     F: FnOnce() -> R,
 {
-    let no_catch_panic = std::env::var(ENV_NO_PANIC_CATCH).is_ok();
-    if no_catch_panic {
+    if !catch_panics {
         Ok(f())
     } else {
-        match catch_unwind(AssertUnwindSafe(f)) {
+        CAUGHT_PANIC.with(|caught| caught.borrow_mut().take());
+        match catch_silently(f) {
             Err(e) => {
-                if let Some(SuchThatRetryFailure) = e.downcast_ref::<SuchThatRetryFailure>() {
-                    Err(PanicError("such that retry failure".to_string()))
-                } else if let Some(e) = e.downcast_ref::<&'static str>() {
-                    Err(PanicError((*e).to_string()))
-                } else if let Some(e) = e.downcast_ref::<String>() {
-                    Err(PanicError(e.clone()))
-                } else {
-                    Err(PanicError("unknown type of panic error".to_string()))
-                }
+                let message =
+                    if let Some(SuchThatRetryFailure) = e.downcast_ref::<SuchThatRetryFailure>() {
+                        "such that retry failure".to_string()
+                    } else if let Some(GenerationFailure(e)) = e.downcast_ref() {
+                        format!("generation failed: {}", e)
+                    } else if let Some(e) = e.downcast_ref::<&'static str>() {
+                        (*e).to_string()
+                    } else if let Some(e) = e.downcast_ref::<String>() {
+                        e.clone()
+                    } else {
+                        "unknown type of panic error".to_string()
+                    };
+                // the details are only recorded by the panic hook installed by `run`
+                let details = CAUGHT_PANIC.with(|caught| caught.borrow_mut().take());
+                Err(PanicError { message, details })
             }
             Ok(prop_result) => Ok(prop_result),
         }
     }
 }
 
+/// Generation of an input, where the exhaustion of a `such_that` filter, either reported
+/// as an error or as a panic by generators not forwarding the errors, discards this
+/// iteration, and a failed generation (from `assert_ok`) fails it. In both cases None
+/// is returned, the iteration being recorded in the results
+fn generate<F, T>(result: &mut TestResults, max_failures: Option<usize>, f: F) -> Option<T>
+where
+    F: FnOnce() -> Result<T, GenError>,
+{
+    match catch_silently(f) {
+        Ok(Ok(t)) => return Some(t),
+        Ok(Err(GenError::Exhausted)) => result.add_skipped(),
+        Err(e) if e.is::<SuchThatRetryFailure>() => result.add_skipped(),
+        Err(e) => match e.downcast::<GenerationFailure>() {
+            Ok(failure) => result.add_failed_capped(max_failures, || {
+                format!(
+                    "input: {}\ngeneration failed: {}\n",
+                    DISCARDED_INPUT, failure.0
+                )
+            }),
+            Err(e) => resume_unwind(e),
+        },
+    }
+    None
+}
+
+const DISCARDED_INPUT: &str = "<discarded during generation>";
+
 #[derive(Clone)]
 pub struct Forall<G> {
     generator: G,
 }
 
+/// Method `ensureN` of `Forall`, for the generators of N-tuples, where the property
+/// closure receives each element of the tuple as separate arguments
+macro_rules! generate_ensure_n {
+    ($(#[$doc:meta])* $fct_name:ident, $(($arg:ident, $type_param:ident)),*) => {
+        $(#[$doc])*
+        pub fn $fct_name<$($type_param,)* P, Prop>(self, prop: Prop) -> Ensure<G, impl Fn(($($type_param,)*)) -> P>
+        where
+            G: Generator<Item = ($($type_param,)*)>,
+            P: Property,
+            Prop: Fn($(&$type_param),*) -> P,
+            $($type_param: fmt::Debug + 'static,)*
+        {
+            self.ensure(move |($($arg,)*)| prop($(&$arg),*))
+        }
+    };
+}
+
 impl<G> Forall<G> {
     pub fn ensure<T, P, F>(self, f: F) -> Ensure<G, F>
     where
         G: Generator<Item = T>,
         P: Property,
         F: Fn(T) -> P,
-        T: fmt::Debug + Clone + 'static,
+        T: fmt::Debug + 'static,
+    {
+        Ensure {
+            name: None,
+            seed: None,
+            skip: None,
+            describe: None,
+            formatter: Box::new(DebugFormatter),
+            triviality_check: false,
+            literal: None,
+            reference_check: None,
+            generator: self.generator,
+            property_closure: f,
+        }
+    }
+
+    /// Similar to `ensure`, but the construction of the property can fail, the
+    /// error failing the test with its `Debug` representation
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u8>()).ensure_try(|x| {
+    ///     let parsed = x.to_string().parse::<u8>()?;
+    ///     Ok::<_, std::num::ParseIntError>(equal(parsed, x))
+    /// });
+    /// ```
+    pub fn ensure_try<T, P, E, F>(self, f: F) -> Ensure<G, F>
+    where
+        G: Generator<Item = T>,
+        P: Property,
+        E: fmt::Debug,
+        F: Fn(T) -> Result<P, E>,
+        T: fmt::Debug + 'static,
+    {
+        self.ensure(f)
+    }
+
+    /// Similar to `ensure`, for plain checks returning an error message on failure
+    /// instead of a property
+    ///
+    /// ```
+    /// use smoke::{generator::num, forall};
+    ///
+    /// let property = forall(num::<u32>()).ensure_result(|x| {
+    ///     if x.checked_mul(2).map_or(true, |d| d / 2 == x) {
+    ///         Ok(())
+    ///     } else {
+    ///         Err(format!("{} * 2 / 2 != {}", x, x))
+    ///     }
+    /// });
+    /// ```
+    pub fn ensure_result<T, F>(self, f: F) -> Ensure<G, impl Fn(T) -> property::Checked>
+    where
+        G: Generator<Item = T>,
+        F: Fn(T) -> Result<(), String>,
+        T: fmt::Debug + 'static,
+    {
+        self.ensure(move |x| property::Checked(f(x)))
+    }
+
+    /// Similar to `ensure`, but the property is named, so that
+    /// its failures are attributed to it in the test report
+    ///
+    /// ```
+    /// use smoke::{Generator, generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u32>().and(num::<u32>()))
+    ///     .ensure_named("commutativity", |(a, b)| equal(a ^ b, b ^ a));
+    /// ```
+    pub fn ensure_named<T, P, F>(self, name: &str, f: F) -> Ensure<G, F>
+    where
+        G: Generator<Item = T>,
+        P: Property,
+        F: Fn(T) -> P,
+        T: fmt::Debug + 'static,
     {
         Ensure {
+            name: Some(name.to_string()),
+            seed: None,
+            skip: None,
+            describe: None,
+            formatter: Box::new(DebugFormatter),
+            triviality_check: false,
+            literal: None,
+            reference_check: None,
+            generator: self.generator,
+            property_closure: f,
+        }
+    }
+
+    generate_ensure_n! {
+        /// Similar to `ensure`, for generators of pairs, where the property closure
+        /// receives each element of the pair as separate arguments
+        ///
+        /// The failure report still contains the whole generated pair
+        ///
+        /// ```
+        /// use smoke::{generator::{num, tuple2}, property::equal, forall};
+        ///
+        /// let property = forall(tuple2(num::<u32>(), num::<u32>()))
+        ///     .ensure2(|a, b| equal(a.wrapping_add(*b), b.wrapping_add(*a)));
+        /// ```
+        ensure2, (a, A), (b, B)
+    }
+
+    generate_ensure_n! {
+        /// Similar to `ensure`, for generators of triples, where the property closure
+        /// receives each element of the triple as separate arguments
+        ///
+        /// The failure report still contains the whole generated triple
+        ///
+        /// ```
+        /// use smoke::{generator::{num, tuple3}, property::equal, forall};
+        ///
+        /// let property = forall(tuple3(num::<u8>(), num::<u8>(), num::<u8>()))
+        ///     .ensure3(|a, b, c| equal(a ^ b ^ c, c ^ b ^ a));
+        /// ```
+        ensure3, (a, A), (b, B), (c, C)
+    }
+
+    generate_ensure_n! {
+        /// Similar to `ensure3`, for generators of 4-tuples
+        ensure4, (a, A), (b, B), (c, C), (d, D)
+    }
+
+    generate_ensure_n! {
+        /// Similar to `ensure3`, for generators of 5-tuples
+        ensure5, (a, A), (b, B), (c, C), (d, D), (e, E)
+    }
+
+    generate_ensure_n! {
+        /// Similar to `ensure3`, for generators of 6-tuples
+        ///
+        /// ```
+        /// use smoke::{generator::{num, tuple6}, property::equal, forall};
+        ///
+        /// let property = forall(tuple6(num::<u8>(), num::<u8>(), num::<u8>(), num::<u8>(), num::<u8>(), num::<u8>()))
+        ///     .ensure6(|a, b, c, d, e, f| equal(a ^ b ^ c ^ d ^ e ^ f, f ^ e ^ d ^ c ^ b ^ a));
+        /// ```
+        ensure6, (a, A), (b, B), (c, C), (d, D), (e, E), (f, F)
+    }
+
+    /// Similar to `ensure`, but the property closure also receives the index
+    /// of the current iteration, from 0 to the number of tests (excluded)
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::less, forall};
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure_indexed(|idx, _| less(idx, 1_000_000));
+    /// ```
+    pub fn ensure_indexed<T, P, F>(self, f: F) -> EnsureIndexed<G, F>
+    where
+        G: Generator<Item = T>,
+        P: Property,
+        F: Fn(u64, T) -> P,
+        T: fmt::Debug + 'static,
+    {
+        EnsureIndexed {
+            formatter: Box::new(DebugFormatter),
+            generator: self.generator,
+            property_closure: f,
+        }
+    }
+
+    /// Similar to `ensure`, but the item is generated in place into a buffer
+    /// re-used across all the iterations, and the property closure receives a
+    /// reference to it.
+    ///
+    /// This avoid the allocation cost of generating collections on each iteration,
+    /// and for the same seed, the generated values are identical to `ensure`.
+    ///
+    /// ```
+    /// use smoke::{generator::{range, num, vector}, property::equal, forall};
+    ///
+    /// let property = forall(vector(range(0..100), num::<u8>()))
+    ///     .ensure_in_place(|v| equal(v.len(), v.iter().count()));
+    /// ```
+    pub fn ensure_in_place<T, P, F>(self, f: F) -> EnsureInPlace<G, F>
+    where
+        G: GeneratorInPlace<Item = T>,
+        P: Property,
+        F: Fn(&T) -> P,
+        T: fmt::Debug + Default + 'static,
+    {
+        EnsureInPlace {
+            name: None,
+            seed: None,
+            generator: self.generator,
+            property_closure: f,
+        }
+    }
+
+    /// Similar to `ensure`, for async properties: the property closure returns a
+    /// future of the property, driven to completion by an `Executor`
+    ///
+    /// By default, the futures are driven by the minimal `BlockOn` executor,
+    /// use `with_executor` to use the executor of a runtime.
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure_async(|x| async move { equal(x, x) });
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn ensure_async<T, P, Fut, F>(self, f: F) -> EnsureAsync<G, F, BlockOn>
+    where
+        G: Generator<Item = T>,
+        P: Property,
+        Fut: Future<Output = P>,
+        F: Fn(T) -> Fut,
+        T: fmt::Debug + 'static,
+    {
+        EnsureAsync {
+            name: None,
+            seed: None,
+            formatter: Box::new(DebugFormatter),
+            timeout: None,
+            executor: BlockOn,
             generator: self.generator,
             property_closure: f,
         }
@@ -85,15 +484,408 @@ where
 pub struct Context {
     seed: Seed,
     nb_tests: u64,
+    verbose: bool,
+    print_seed: bool,
+    emit_repro: bool,
+    catch_panics: bool,
+    max_failures: Option<usize>,
+    size_strategy: SizeStrategy,
+    heartbeat: Heartbeat,
     test_results: TestResults,
+    generators: HashMap<TypeId, Box<dyn Any>>,
 }
 
 /// A testable statement binding a generator with a property
 pub struct Ensure<G: Generator, F> {
+    name: Option<String>,
+    seed: Option<Seed>,
+    skip: Option<String>,
+    describe: Option<fn(&G::Item, &mut InputStats)>,
+    formatter: Box<dyn FailureFormatter<G::Item>>,
+    triviality_check: bool,
+    literal: Option<fn(&G::Item) -> String>,
+    reference_check: Option<ReferenceCheck<G::Item>>,
+    generator: G,
+    property_closure: F,
+}
+
+impl<G: Generator, F> Ensure<G, F> {
+    /// Use a specific seed for this property, instead of the seed of the context
+    ///
+    /// This is useful to pin a single property to a known seed for reproduction,
+    /// whilst the other properties of the run keep using the context seed.
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall, Seed};
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure(|x| equal(x, x))
+    ///     .with_seed(Seed::from(0x1234));
+    /// ```
+    pub fn with_seed(mut self, seed: Seed) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Skip this property as a whole when `condition` is true, recording `reason`
+    /// in the results instead of running any test
+    ///
+    /// This is useful for properties that only make sense on some platforms, or
+    /// when an external resource is available:
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure(|x| equal(x.to_le(), x))
+    ///     .skip_if(cfg!(target_endian = "big"), "little endian only");
+    /// ```
+    pub fn skip_if(mut self, condition: bool, reason: &str) -> Self {
+        if condition {
+            self.skip = Some(reason.to_string());
+        }
+        self
+    }
+
+    /// Collect summary statistics of the generated inputs, reported after the run
+    ///
+    /// This is useful to check that the generators produce the expected
+    /// distribution of inputs, even when all the tests pass.
+    ///
+    /// ```
+    /// use smoke::{generator::{num, range, vector}, property::equal, forall};
+    ///
+    /// let property = forall(vector(range(0..64), num::<u8>()))
+    ///     .ensure(|v| equal(v.len(), v.iter().count()))
+    ///     .with_input_stats();
+    /// ```
+    pub fn with_input_stats(mut self) -> Self
+    where
+        G::Item: Describe,
+    {
+        self.describe = Some(<G::Item as Describe>::describe);
+        self
+    }
+
+    /// Format the inputs of the failed tests with `formatter`, instead of their
+    /// `Debug` representation
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u8>())
+    ///     .ensure(|x| equal(x.reverse_bits().reverse_bits(), x))
+    ///     .with_formatter(|x: &u8| format!("{:08b}", x));
+    /// ```
+    pub fn with_formatter<Fmt>(mut self, formatter: Fmt) -> Self
+    where
+        Fmt: FailureFormatter<G::Item> + 'static,
+    {
+        self.formatter = Box::new(formatter);
+        self
+    }
+
+    /// Warn when the property appears not to depend on its input
+    ///
+    /// A property checking the same values for all the inputs (e.g. a match arm
+    /// returning `equal(0, 0)`) passes without testing anything. With this check,
+    /// the values checked by the property (see `Property::probe`) are compared
+    /// across the iterations, and the results carry a warning when they are
+    /// identical for all the iterations whereas the inputs differ.
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure(|x| equal(x.rotate_left(3).rotate_right(3), x))
+    ///     .with_triviality_check();
+    /// ```
+    pub fn with_triviality_check(mut self) -> Self {
+        self.triviality_check = true;
+        self
+    }
+
+    /// Write the failing inputs as Rust literals in the reproduction tests emitted
+    /// on failure (see `Context::set_emit_repro`), instead of comments of their
+    /// `Debug` representation
+    ///
+    /// ```
+    /// use smoke::{generator::{num, vector, range}, property::equal, forall};
+    ///
+    /// let property = forall(vector(range(0..8), num::<u8>()))
+    ///     .ensure_named("reverse", |v| equal(v.iter().rev().rev().count(), v.len()))
+    ///     .with_rust_literal();
+    /// ```
+    pub fn with_rust_literal(mut self) -> Self
+    where
+        G::Item: ToRustLiteral,
+    {
+        self.literal = Some(<G::Item as ToRustLiteral>::to_rust_literal);
+        self
+    }
+
+    /// Also compare `implementation` with an expensive `reference` implementation,
+    /// on one iteration every `every_n` iterations
+    ///
+    /// The iterations checked are the ones whose index is a multiple of `every_n`,
+    /// so a rerun with the same seed checks the same inputs. The outputs of both
+    /// implementations are compared with `equal`, the comparison failing the
+    /// iteration under the "reference check" label, and the number of iterations
+    /// checked is reported in the results.
+    ///
+    /// ```
+    /// use smoke::{generator::{num, vector, range}, property::equal, forall};
+    ///
+    /// let property = forall(vector(range(0..64), num::<u8>()))
+    ///     .ensure(|v| equal(v.iter().rev().count(), v.len()))
+    ///     .with_reference_check(
+    ///         10,
+    ///         |v: &Vec<u8>| v.iter().map(|b| *b as u32).sum::<u32>(),
+    ///         |v: &Vec<u8>| v.iter().fold(0u32, |acc, b| acc + *b as u32),
+    ///     );
+    /// ```
+    pub fn with_reference_check<O, I, Ref>(
+        mut self,
+        every_n: u64,
+        implementation: I,
+        reference: Ref,
+    ) -> Self
+    where
+        O: Eq + fmt::Debug + 'static,
+        I: Fn(&G::Item) -> O + 'static,
+        Ref: Fn(&G::Item) -> O + 'static,
+    {
+        assert!(
+            every_n > 0,
+            "with_reference_check: every_n must be positive"
+        );
+        self.reference_check = Some(ReferenceCheck {
+            every_n,
+            check: Box::new(move |input| {
+                property::equal(implementation(input), reference(input))
+                    .label("reference check")
+                    .result()
+            }),
+        });
+        self
+    }
+}
+
+/// Comparison of an implementation with a reference implementation, on the
+/// iterations whose index is a multiple of `every_n`
+struct ReferenceCheck<T> {
+    every_n: u64,
+    check: Box<dyn Fn(&T) -> property::Outcome>,
+}
+
+impl<T> ReferenceCheck<T> {
+    fn applies(&self, idx: u64) -> bool {
+        idx % self.every_n == 0
+    }
+}
+
+/// A property with the outcome of the reference check of its iteration, if any
+///
+/// The failure of the property takes precedence over the failure of the reference
+/// check, and the discarded iterations stay discarded.
+struct ReferenceChecked<P> {
+    property: P,
+    reference: Option<property::Outcome>,
+}
+
+impl<P: Property> Property for ReferenceChecked<P> {
+    fn result(&self) -> property::Outcome {
+        match (self.property.result(), &self.reference) {
+            (property::Outcome::Passed, Some(failed @ property::Outcome::Failed(_))) => {
+                failed.clone()
+            }
+            (outcome, _) => outcome,
+        }
+    }
+
+    fn coverage(&self, report: &mut dyn FnMut(&str, bool, f64)) {
+        self.property.coverage(report)
+    }
+
+    fn probe(&self) -> Option<Element> {
+        self.property.probe()
+    }
+
+    fn discard_reason(&self) -> Option<String> {
+        self.property.discard_reason()
+    }
+}
+
+/// Comparison of the values checked by a property across the iterations of a test,
+/// to detect the properties not depending on their input
+#[derive(Default)]
+struct TrivialityCheck {
+    /// Probe and formatted input of the first probed iteration
+    first: Option<(Element, String)>,
+    constant: bool,
+    inputs_differ: bool,
+}
+
+impl TrivialityCheck {
+    fn record<F: FnOnce() -> String>(&mut self, probe: Option<Element>, input: F) {
+        let probe = match probe {
+            // unknown probes, notably of the discarded iterations, are ignored
+            None => return,
+            Some(probe) => probe,
+        };
+        match &self.first {
+            None => {
+                self.first = Some((probe, input()));
+                self.constant = true;
+            }
+            Some((first_probe, first_input)) => {
+                if !self.constant {
+                    return;
+                }
+                if *first_probe != probe {
+                    self.constant = false;
+                } else if !self.inputs_differ && *first_input != input() {
+                    self.inputs_differ = true;
+                }
+            }
+        }
+    }
+
+    fn warning(&self) -> Option<String> {
+        match &self.first {
+            Some((probe, _)) if self.constant && self.inputs_differ => Some(format!(
+                "property appears input-independent, all the tests checked:\n{}",
+                probe.display(2)
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// A testable statement binding a generator with a property receiving the iteration index
+pub struct EnsureIndexed<G: Generator, F> {
+    formatter: Box<dyn FailureFormatter<G::Item>>,
+    generator: G,
+    property_closure: F,
+}
+
+impl<G: Generator, F> EnsureIndexed<G, F> {
+    /// Format the inputs of the failed tests with `formatter`, instead of their
+    /// `Debug` representation, see `Ensure::with_formatter`
+    pub fn with_formatter<Fmt>(mut self, formatter: Fmt) -> Self
+    where
+        Fmt: FailureFormatter<G::Item> + 'static,
+    {
+        self.formatter = Box::new(formatter);
+        self
+    }
+}
+
+/// A testable statement binding an in place generator with a property
+pub struct EnsureInPlace<G: GeneratorInPlace, F> {
+    name: Option<String>,
+    seed: Option<Seed>,
+    generator: G,
+    property_closure: F,
+}
+
+impl<G: GeneratorInPlace, F> EnsureInPlace<G, F> {
+    /// Name this property in the results, like `Forall::ensure_named`
+    ///
+    /// ```
+    /// use smoke::{generator::{range, num, vector}, property::equal, forall};
+    ///
+    /// let property = forall(vector(range(0..100), num::<u8>()))
+    ///     .ensure_in_place(|v| equal(v.len(), v.iter().count()))
+    ///     .named("length");
+    /// ```
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Use a specific seed for this property, instead of the seed of the context,
+    /// see `Ensure::with_seed`
+    pub fn with_seed(mut self, seed: Seed) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// A testable statement binding a generator with an async property
+#[cfg(feature = "async")]
+pub struct EnsureAsync<G: Generator, F, E> {
+    name: Option<String>,
+    seed: Option<Seed>,
+    formatter: Box<dyn FailureFormatter<G::Item>>,
+    timeout: Option<Duration>,
+    executor: E,
     generator: G,
     property_closure: F,
 }
 
+#[cfg(feature = "async")]
+impl<G: Generator, F, E> EnsureAsync<G, F, E> {
+    /// Name this property in the results, like `Forall::ensure_named`
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure_async(|x| async move { equal(x, x) })
+    ///     .named("async identity");
+    /// ```
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Use a specific seed for this property, instead of the seed of the context,
+    /// see `Ensure::with_seed`
+    pub fn with_seed(mut self, seed: Seed) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Format the inputs of the failed tests with `formatter`, instead of their
+    /// `Debug` representation, see `Ensure::with_formatter`
+    pub fn with_formatter<Fmt>(mut self, formatter: Fmt) -> Self
+    where
+        Fmt: FailureFormatter<G::Item> + 'static,
+    {
+        self.formatter = Box::new(formatter);
+        self
+    }
+
+    /// Fail the iterations whose property doesn't complete within `timeout`
+    ///
+    /// ```
+    /// use smoke::{generator::num, property::equal, forall};
+    /// use std::time::Duration;
+    ///
+    /// let property = forall(num::<u32>())
+    ///     .ensure_async(|x| async move { equal(x, x) })
+    ///     .with_timeout(Duration::from_secs(1));
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Drive the futures of the property with `executor`
+    pub fn with_executor<E2: Executor>(self, executor: E2) -> EnsureAsync<G, F, E2> {
+        EnsureAsync {
+            name: self.name,
+            seed: self.seed,
+            formatter: self.formatter,
+            timeout: self.timeout,
+            executor,
+            generator: self.generator,
+            property_closure: self.property_closure,
+        }
+    }
+}
+
 /// Any tests to run with a testing context
 pub trait Testable {
     fn test(&self, context: &Context) -> TestResults;
@@ -112,121 +904,1758 @@ where
     T: fmt::Debug + 'static,
 {
     fn test(&self, context: &Context) -> TestResults {
-        let mut r = R::from_seed(context.seed);
-
-        let nb_tests = context.nb_tests;
-
-        let start = SystemTime::now();
-
-        let mut result = TestResults::new();
-
         let generator = &self.generator;
         let property_closure = &self.property_closure;
-        for _ in 0..nb_tests {
-            let mut test_rng = r.sub();
-
-            let input = generator.gen(&mut test_rng);
-            let to_report = format!("{:?}", &input);
-            match run_catch_panic(|| property_closure(input)) {
-                Err(PanicError(p)) => {
-                    result.add_failed(format!("input: {}\npanic: \"{}\"\n", to_report, p))
-                }
-                Ok(p) => match p.result() {
-                    property::Outcome::Passed => result.add_success(),
-                    property::Outcome::Failed(t) => result.add_failed(format!(
-                        "input = {}\nproperty failed:\n{}",
-                        to_report,
-                        t.display(2),
-                    )),
-                },
+        if let Some(reason) = &self.skip {
+            let mut result = TestResults::new();
+            result.add_skip(reason);
+            if let Some(name) = &self.name {
+                result.set_property_name(name);
             }
+            return result;
         }
-        let finished = SystemTime::now();
-        let duration = finished
-            .duration_since(start)
-            .unwrap_or_else(|_| Duration::default());
-        result.set_duration(duration);
-        result
-    }
-}
-
-impl Context {
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
-        use std::str::FromStr;
-        let seed = match std::env::var(ENV_SEED) {
-            Ok(v) => Seed::from_str(&v).expect("invalid seed format"),
-            Err(_) => *INSTANCE_SEED.load(Seed::generate),
-        };
-        let nb_tests = match std::env::var(ENV_NB_TESTS) {
-            Ok(v) => v.parse().expect("invalid seed format"),
-            Err(_) => DEFAULT_NB_TESTS,
-        };
-        Self {
+        let seed = self.seed.unwrap_or(context.seed);
+        let mut stats = self.describe.map(|_| InputStats::new());
+        let mut triviality = TrivialityCheck::default();
+        let sub_stream = generator.needs_sub_stream();
+        // the property consumes the input, so the input is regenerated from a
+        // snapshot of the random generator when it needs to be rendered
+        let mut result = test_iterations(
+            context,
             seed,
-            nb_tests,
-            test_results: TestResults::new(),
+            sub_stream,
+            |(idx, snapshot)| debug_input(&regenerate(generator, *idx, snapshot)),
+            |idx, test_rng, result| {
+                let snapshot = test_rng.clone();
+                let input = generate(result, context.max_failures, || {
+                    generator.try_gen_indexed(idx, test_rng)
+                })?;
+                if let (Some(describe), Some(stats)) = (self.describe, &mut stats) {
+                    describe(&input, stats)
+                }
+                let to_report = || {
+                    self.formatter
+                        .format_input(&regenerate(generator, idx, &snapshot))
+                };
+                let reference_check = self
+                    .reference_check
+                    .as_ref()
+                    .filter(|reference_check| reference_check.applies(idx));
+                if reference_check.is_some() {
+                    result.nb_reference_checks += 1;
+                }
+                let outcome = run_catch_panic(context.catch_panics, || {
+                    let reference =
+                        reference_check.map(|reference_check| (reference_check.check)(&input));
+                    ReferenceChecked {
+                        property: property_closure(input),
+                        reference,
+                    }
+                });
+                if let (true, Ok(property)) = (self.triviality_check, &outcome) {
+                    triviality.record(property.probe(), to_report);
+                }
+                let nb_failures = result.failures.len();
+                record_outcome(result, context.max_failures, to_report, outcome);
+                if context.emit_repro {
+                    append_repro(result, nb_failures, self.name.as_deref(), || {
+                        let input = regenerate(generator, idx, &snapshot);
+                        (
+                            self.literal.map(|literal| literal(&input)),
+                            debug_input(&input),
+                        )
+                    });
+                }
+                Some((idx, snapshot))
+            },
+        );
+        result.input_stats.extend(stats);
+        if let Some(warning) = triviality.warning() {
+            result.add_warning(&warning);
+        }
+        if let Some(name) = &self.name {
+            result.set_property_name(name);
         }
+        result
     }
+}
 
-    pub fn seed(&self) -> Seed {
-        self.seed
+impl<T, G, F, P> Testable for EnsureIndexed<G, F>
+where
+    G: Generator<Item = T>,
+    P: Property,
+    F: Fn(u64, T) -> P,
+    T: fmt::Debug + 'static,
+{
+    fn test(&self, context: &Context) -> TestResults {
+        let generator = &self.generator;
+        let property_closure = &self.property_closure;
+        test_iterations(
+            context,
+            context.seed,
+            generator.needs_sub_stream(),
+            |(idx, snapshot)| debug_input(&regenerate(generator, *idx, snapshot)),
+            |idx, test_rng, result| {
+                let snapshot = test_rng.clone();
+                let input = generate(result, context.max_failures, || {
+                    generator.try_gen_indexed(idx, test_rng)
+                })?;
+                record_outcome(
+                    result,
+                    context.max_failures,
+                    || {
+                        self.formatter
+                            .format_input(&regenerate(generator, idx, &snapshot))
+                    },
+                    run_catch_panic(context.catch_panics, || property_closure(idx, input)),
+                );
+                Some((idx, snapshot))
+            },
+        )
     }
+}
 
-    pub fn set_seed(&mut self, seed: Seed) {
-        self.seed = seed;
+impl<T, G, F, P> Testable for EnsureInPlace<G, F>
+where
+    G: GeneratorInPlace<Item = T>,
+    P: Property,
+    F: Fn(&T) -> P,
+    T: fmt::Debug + Default + 'static,
+{
+    fn test(&self, context: &Context) -> TestResults {
+        let generator = &self.generator;
+        let property_closure = &self.property_closure;
+        let mut input = T::default();
+        // the buffer is overwritten by the next iteration, so the input is rendered eagerly
+        let mut result = test_iterations(
+            context,
+            self.seed.unwrap_or(context.seed),
+            true,
+            String::clone,
+            |_, test_rng, result| {
+                generate(result, context.max_failures, || {
+                    generator.gen_into(test_rng, &mut input);
+                    Ok(())
+                })?;
+                let to_report = debug_input(&input);
+                let nb_failures = result.failures.len();
+                record_outcome(
+                    result,
+                    context.max_failures,
+                    || to_report.clone(),
+                    run_catch_panic(context.catch_panics, || property_closure(&input)),
+                );
+                if context.emit_repro {
+                    append_repro(result, nb_failures, self.name.as_deref(), || {
+                        (None, to_report.clone())
+                    });
+                }
+                Some(to_report)
+            },
+        );
+        if let Some(name) = &self.name {
+            result.set_property_name(name);
+        }
+        result
     }
+}
 
-    pub fn nb_tests(&self) -> u64 {
-        self.nb_tests
-    }
+fn debug_input<T: fmt::Debug>(input: &T) -> String {
+    format!("{:?}", input)
+}
 
-    pub fn set_nb_tests(&mut self, nb_tests: u64) {
-        self.nb_tests = nb_tests;
+/// Regenerate the input of an iteration from the snapshot of the random generator
+/// taken before its generation
+fn regenerate<G: Generator>(generator: &G, idx: u64, snapshot: &R) -> G::Item {
+    match generator.try_gen_indexed(idx, &mut snapshot.clone()) {
+        Ok(input) => input,
+        Err(_) => panic!("the input of iteration {} cannot be regenerated", idx),
     }
 }
 
-/// Create a new context to execute tests into
+/// Append the reproduction test of the input, rendered by `input` as its literal
+/// and its `Debug` representation, to the failure recorded since `nb_failures`, if any
+fn append_repro<F>(result: &mut TestResults, nb_failures: usize, name: Option<&str>, input: F)
+where
+    F: FnOnce() -> (Option<String>, String),
+{
+    if result.failures.len() > nb_failures {
+        let (literal, debug) = input();
+        let repro = repro_test(name, literal, &debug);
+        if let Some(failure) = result.failures.last_mut() {
+            failure
+                .message
+                .push_str(&format!("reproduction:\n{}", repro));
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T, G, F, E, Fut, P> Testable for EnsureAsync<G, F, E>
+where
+    G: Generator<Item = T>,
+    P: Property,
+    Fut: Future<Output = P>,
+    F: Fn(T) -> Fut,
+    E: Executor,
+    T: fmt::Debug + 'static,
+{
+    fn test(&self, context: &Context) -> TestResults {
+        let generator = &self.generator;
+        let property_closure = &self.property_closure;
+        let mut result = test_iterations(
+            context,
+            self.seed.unwrap_or(context.seed),
+            generator.needs_sub_stream(),
+            |(idx, snapshot)| debug_input(&regenerate(generator, *idx, snapshot)),
+            |idx, test_rng, result| {
+                let snapshot = test_rng.clone();
+                let input = generate(result, context.max_failures, || {
+                    generator.try_gen_indexed(idx, test_rng)
+                })?;
+                let to_report = || {
+                    self.formatter
+                        .format_input(&regenerate(generator, idx, &snapshot))
+                };
+                let nb_failures = result.failures.len();
+                // the panics are caught around the whole poll loop of the executor
+                let outcome = run_catch_panic(context.catch_panics, || {
+                    let future = property_closure(input);
+                    match self.timeout {
+                        None => Some(self.executor.block_on(future)),
+                        Some(timeout) => self.executor.block_on(Deadline::new(future, timeout)),
+                    }
+                });
+                match (outcome, self.timeout) {
+                    (Ok(None), Some(timeout)) => result
+                        .add_failed_capped(context.max_failures, || {
+                            format!("input: {}\ntimeout: {:?}\n", to_report(), timeout)
+                        }),
+                    (outcome, _) => record_outcome(
+                        result,
+                        context.max_failures,
+                        to_report,
+                        outcome.map(|p| p.expect("completed without timeout")),
+                    ),
+                }
+                if context.emit_repro {
+                    append_repro(result, nb_failures, self.name.as_deref(), || {
+                        (None, debug_input(&regenerate(generator, idx, &snapshot)))
+                    });
+                }
+                Some((idx, snapshot))
+            },
+        );
+        if let Some(name) = &self.name {
+            result.set_property_name(name);
+        }
+        result
+    }
+}
+
+/// Run the iterations of a test, timing each of them individually
+///
+/// The iteration closure receives the iteration index and returns the tested
+/// input, or None if discarded during generation; `render` is only called
+/// to report the input of the slowest iteration
+///
+/// Without `sub_stream`, the iterations draw directly from the random generator
+/// of the test instead of a sub-stream derived for each iteration, for the
+/// generators not needing one (see `Generator::needs_sub_stream`)
+fn test_iterations<I, Rd, F>(
+    context: &Context,
+    seed: Seed,
+    sub_stream: bool,
+    render: Rd,
+    mut iteration: F,
+) -> TestResults
+where
+    Rd: Fn(&I) -> String,
+    F: FnMut(u64, &mut R, &mut TestResults) -> Option<I>,
+{
+    let mut r = R::from_seed(seed);
+
+    let mut result = TestResults::new();
+
+    let run_start = Instant::now();
+    let mut last_heartbeat = run_start;
+    for idx in 0..context.nb_tests {
+        let mut sub_rng;
+        let test_rng = if sub_stream {
+            sub_rng = r.sub();
+            &mut sub_rng
+        } else {
+            &mut r
+        };
+        test_rng.set_size(context.size_strategy.size(idx, context.nb_tests));
+
+        let nb_failures = result.failures.len();
+        let start = Instant::now();
+        let input = iteration(idx, test_rng, &mut result);
+        result.add_iteration_with(start.elapsed(), || match &input {
+            None => DISCARDED_INPUT.to_string(),
+            Some(input) => render(input),
+        });
+
+        let elapsed = run_start.elapsed();
+        if result.failures.len() > nb_failures && elapsed > TIMING_REPORT_THRESHOLD {
+            if let Some(failure) = result.failures.last_mut() {
+                failure.message.push_str(&format!(
+                    "failed at {}\n",
+                    progress(idx + 1, context.nb_tests, elapsed)
+                ));
+            }
+        }
+        if context.heartbeat.is_due(idx + 1, last_heartbeat.elapsed()) {
+            eprintln!("smoke: {}", progress(idx + 1, context.nb_tests, elapsed));
+            last_heartbeat = Instant::now();
+        }
+    }
+    result.check_coverage();
+    result
+}
+
+/// Progress of a run, as `X of Y after Z seconds (~N cases/sec)`
+fn progress(iterations: u64, nb_tests: u64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 {
+        iterations as f64 / secs
+    } else {
+        0.0
+    };
+    format!(
+        "iteration {} of {} after {:.1} seconds (~{:.0} cases/sec)",
+        iterations, nb_tests, secs, rate
+    )
+}
+
+/// Record the outcome of a test, where the input is only rendered with `to_report`
+/// for the failures kept in the results
+fn record_outcome<P, F>(
+    result: &mut TestResults,
+    max_failures: Option<usize>,
+    to_report: F,
+    outcome: Result<P, PanicError>,
+) where
+    P: Property,
+    F: FnOnce() -> String,
+{
+    match outcome {
+        Err(p) => {
+            result.add_failed_capped(max_failures, || format!("input: {}\n{}", to_report(), p))
+        }
+        Ok(p) => {
+            let outcome = p.result();
+            if outcome != property::Outcome::Discarded {
+                p.coverage(&mut |label, hit, required| result.add_coverage(label, hit, required));
+            }
+            match outcome {
+                property::Outcome::Passed => result.add_success(),
+                property::Outcome::Discarded => {
+                    result.add_skipped();
+                    if let Some(reason) = p.discard_reason() {
+                        result.add_skip(&reason)
+                    }
+                }
+                property::Outcome::Failed(t) => result.add_failed_capped(max_failures, || {
+                    format!(
+                        "input = {}\nproperty failed:\n{}",
+                        to_report(),
+                        t.display(2)
+                    )
+                }),
+            }
+        }
+    }
+}
+
+/// The configuration of the environment, panicking with all the invalid variables
+fn env_config() -> RunConfig {
+    match RunConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+impl Context {
+    /// A context configured by the environment variables (see `RunConfig::from_env`)
+    ///
+    /// If any of the variables is invalid then a runtime error is thrown, listing
+    /// all the invalid variables
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::from_config(&env_config())
+    }
+
+    /// A context with the given configuration, using a random seed shared by all
+    /// the contexts of the process when the configuration has no seed
+    pub fn from_config(config: &RunConfig) -> Self {
+        Self {
+            seed: config
+                .seed()
+                .unwrap_or_else(|| *INSTANCE_SEED.load(Seed::generate)),
+            nb_tests: config.nb_tests(),
+            verbose: config.verbose(),
+            print_seed: config.print_seed(),
+            emit_repro: config.emit_repro(),
+            catch_panics: config.catch_panics(),
+            max_failures: config.max_failures(),
+            size_strategy: SizeStrategy::Unbounded,
+            heartbeat: Heartbeat::Every(HEARTBEAT_INTERVAL),
+            test_results: TestResults::new(),
+            generators: HashMap::new(),
+        }
+    }
+
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: Seed) {
+        self.seed = seed;
+    }
+
+    pub fn nb_tests(&self) -> u64 {
+        self.nb_tests
+    }
+
+    /// Set the number of tests of each property, which must be greater than 0,
+    /// as running no tests would silently skip all the properties
+    pub fn set_nb_tests(&mut self, nb_tests: u64) {
+        assert!(nb_tests > 0, "the number of tests must be greater than 0");
+        self.nb_tests = nb_tests;
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// Set the verbosity of the run report, which is also enabled by setting
+    /// the SMOKE_VERBOSE environment variable
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn print_seed(&self) -> bool {
+        self.print_seed
+    }
+
+    /// Set whether `run` prints the seed even when all the tests pass, so that any
+    /// run can be replayed, which is also enabled by setting the SMOKE_PRINT_SEED
+    /// environment variable
+    ///
+    /// With the environment variable, the seed is printed before running the tests,
+    /// otherwise after running them.
+    pub fn set_print_seed(&mut self, print_seed: bool) {
+        self.print_seed = print_seed;
+    }
+
+    pub fn emit_repro(&self) -> bool {
+        self.emit_repro
+    }
+
+    /// Set whether the failures come with a `#[test]` function reproducing them,
+    /// which is also enabled by setting the SMOKE_EMIT_REPRO environment variable
+    ///
+    /// The test calls a check function named after the property, with the failing
+    /// input written as a Rust literal (see `Ensure::with_rust_literal`), or as a
+    /// comment of its `Debug` representation for the in place and async properties.
+    /// The properties receiving the iteration index (`Forall::ensure_indexed`)
+    /// don't emit any, as the test wouldn't reproduce the index.
+    pub fn set_emit_repro(&mut self, emit_repro: bool) {
+        self.emit_repro = emit_repro;
+    }
+
+    pub fn catch_panics(&self) -> bool {
+        self.catch_panics
+    }
+
+    /// Set whether the panics of the properties are caught and reported as failures,
+    /// which is disabled by setting the SMOKE_NO_PANIC_CATCH environment variable
+    pub fn set_catch_panics(&mut self, catch_panics: bool) {
+        self.catch_panics = catch_panics;
+    }
+
+    pub fn max_failures(&self) -> Option<usize> {
+        self.max_failures
+    }
+
+    /// Set the maximum number of failures kept in the results of each property,
+    /// the next failures are only counted, without formatting their input
+    ///
+    /// This is also set by the SMOKE_MAX_FAILURES environment variable
+    pub fn set_max_failures(&mut self, max_failures: Option<usize>) {
+        self.max_failures = max_failures;
+    }
+
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat
+    }
+
+    /// Set how often the progress of a test is reported on stderr, which is
+    /// every 10 seconds by default
+    pub fn set_heartbeat(&mut self, heartbeat: Heartbeat) {
+        self.heartbeat = heartbeat;
+    }
+
+    pub fn size_strategy(&self) -> SizeStrategy {
+        self.size_strategy
+    }
+
+    /// Set how the size parameter bounding the generated collections evolves
+    /// along the iterations of a test
+    pub fn set_size_strategy(&mut self, size_strategy: SizeStrategy) {
+        self.size_strategy = size_strategy;
+    }
+
+    /// Register the generator of the values of type T, replacing the previously
+    /// registered one, so that the properties of the run can share it
+    ///
+    /// ```
+    /// use smoke::{run, forall, Generator, Testable, generator::range, property::{equal, less}};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// struct User {
+    ///     age: u8,
+    /// }
+    ///
+    /// run(|ctx| {
+    ///     ctx.register::<User>(range(0..120).map(|age| User { age }).into_shared());
+    ///
+    ///     forall(ctx.generator::<User>())
+    ///         .ensure(|user| less(user.age, 121))
+    ///         .run(ctx);
+    ///     forall(ctx.generator::<User>())
+    ///         .ensure(|user| equal(user.clone().age, user.age))
+    ///         .run(ctx);
+    /// });
+    /// ```
+    pub fn register<T: 'static>(&mut self, generator: ArcGenerator<T>) {
+        self.generators
+            .insert(TypeId::of::<T>(), Box::new(generator));
+    }
+
+    /// Get the generator registered for the values of type T
+    ///
+    /// If no generator is registered for T then a runtime error is thrown
+    pub fn generator<T: 'static>(&self) -> ArcGenerator<T> {
+        match self.generators.get(&TypeId::of::<T>()) {
+            Some(generator) => generator
+                .downcast_ref::<ArcGenerator<T>>()
+                .expect("generator registered with its type")
+                .clone(),
+            None => panic!("no generator registered for {}", std::any::type_name::<T>()),
+        }
+    }
+}
+
+/// Periodic report of the progress of long tests on stderr, so that long runs
+/// are not considered inactive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heartbeat {
+    /// No progress report
+    Never,
+    /// Report the progress when this duration elapsed since the last report
+    Every(Duration),
+    /// Report the progress every given number of iterations
+    EveryIterations(u64),
+}
+
+impl Heartbeat {
+    fn is_due(&self, iterations: u64, since_last: Duration) -> bool {
+        match *self {
+            Heartbeat::Never => false,
+            Heartbeat::Every(interval) => since_last >= interval,
+            Heartbeat::EveryIterations(n) => n > 0 && iterations % n == 0,
+        }
+    }
+}
+
+/// Size parameter of each iteration of a test
+///
+/// Collection generators (vectors, strings) bound their generated size by the
+/// size parameter, so that the first iterations can use small inputs and the later
+/// ones larger inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeStrategy {
+    /// No bound, the size is only defined by the size generators
+    Unbounded,
+    /// The same bound for every iteration
+    Constant(usize),
+    /// A bound growing linearly from `start` at the first iteration to `end` at the last one
+    Linear { start: usize, end: usize },
+}
+
+impl SizeStrategy {
+    /// Size parameter of the iteration `idx` of a test of `nb_tests` iterations
+    pub fn size(&self, idx: u64, nb_tests: u64) -> Option<usize> {
+        match *self {
+            SizeStrategy::Unbounded => None,
+            SizeStrategy::Constant(size) => Some(size),
+            SizeStrategy::Linear { start, end } => {
+                if nb_tests <= 1 {
+                    return Some(start);
+                }
+                let (start, end) = (start as i128, end as i128);
+                let offset = (end - start) * idx as i128 / (nb_tests - 1) as i128;
+                Some((start + offset) as usize)
+            }
+        }
+    }
+}
+
+/// Create a new context to execute tests into
+///
+/// Return the results of all the tests on success, and panic with the summary
+/// of the results if any test failed.
+///
+/// When the SMOKE_SOAK environment variable is set to a number of passes, the tests
+/// are executed once per seed derived from the seed of the run, the failures
+/// reporting the derived seed to reproduce them with SMOKE_SEED.
 ///
 /// ```
 /// use smoke::{run, forall, Generator, Property, Testable, generator::num, property::greater};
 ///
-/// run(|ctx| {
+/// let results = run(|ctx| {
 ///     forall(num::<u32>())
-///         .ensure(|n| greater(n + 1, n))
+///         .ensure(|n| greater(n.saturating_add(1), n.saturating_sub(1)))
 ///         .run(ctx);
 ///     // other test instances
 /// });
+/// assert_eq!(results.nb_failed, 0);
 /// ```
 ///
-pub fn run<F>(f: F)
+pub fn run<F>(f: F) -> TestResults
 where
-    F: Fn(&mut Context) -> (),
+    F: Fn(&mut Context),
 {
-    let mut ctx = Context::new();
-
-    fn dont_print_panic(_: &PanicInfo) {}
-
-    set_hook(Box::new(dont_print_panic));
+    let config = env_config();
+    let soak = config.soak();
+    let mut ctx = Context::from_config(&config);
+    let mut stdout = std::io::stdout();
+    let seed_printed = write_seed(&mut stdout, &ctx);
 
-    // execute the user tests
-    f(&mut ctx);
-
-    let _ = take_hook();
+    // execute the user tests, restoring the panic hook of the user even if they panic
+    let scope = PanicHookScope::enter();
+    let start = Instant::now();
+    let executed = catch_unwind(AssertUnwindSafe(|| execute(&mut ctx, &f, soak)));
+    ctx.test_results.wall_clock = Some(start.elapsed());
+    drop(scope);
+    let passes = match executed {
+        Ok(passes) => passes,
+        Err(e) => resume_unwind(e),
+    };
 
     // print result
+    for (pass, (seed, results)) in passes.iter().enumerate() {
+        println!("soak pass {}/{} seed {}: {}", pass + 1, soak, seed, results);
+    }
+    if !seed_printed && ctx.test_results.to_status() != TestRunStatus::Failed {
+        write_seed(&mut stdout, &ctx);
+    }
     let tr = ctx.test_results;
+    if ctx.verbose || tr.duration > TIMING_REPORT_THRESHOLD {
+        if let Some(report) = timing_report(&tr) {
+            print!("{}", report)
+        }
+    }
     match tr.to_status() {
-        TestRunStatus::Passed => println!("Passed {} tests", tr.nb_tests),
-        TestRunStatus::Skipped => {}
+        TestRunStatus::Passed => {
+            println!("{}", tr);
+            for stats in tr.input_stats.iter() {
+                print!("{}", stats)
+            }
+        }
+        TestRunStatus::Skipped => {
+            if !tr.skips.is_empty() {
+                println!("{}", tr)
+            }
+        }
         TestRunStatus::Failed => {
-            for (i, failure) in tr.failures.iter().enumerate() {
-                println!("# Failure {}\n{}", i, failure)
+            print!("{:#}", tr);
+            write_failure_seed(&mut stdout, ctx.seed, seed_printed);
+            panic!("\n{}", tr);
+        }
+    }
+    tr
+}
+
+fn seed_line(seed: Seed) -> String {
+    format!("seed: {} (generation version {})", seed, GENERATION_VERSION)
+}
+
+/// Write the seed line to `out` if the context requires it, returning whether it was written
+fn write_seed<W: std::io::Write>(out: &mut W, ctx: &Context) -> bool {
+    if !ctx.print_seed {
+        return false;
+    }
+    // failing to print the seed isn't worth failing the run
+    let _ = writeln!(out, "{}", seed_line(ctx.seed));
+    true
+}
+
+/// Write the seed line of a failed run to `out`, unless it was already written
+/// before running the tests
+fn write_failure_seed<W: std::io::Write>(out: &mut W, seed: Seed, seed_printed: bool) {
+    if !seed_printed {
+        let _ = writeln!(out, "{}", seed_line(seed));
+    }
+}
+
+/// Execute the user tests with the seed of the context, or in soak mode (more than
+/// one pass), once per seed derived from the seed of the context
+///
+/// The results of all the passes are aggregated in the context, and the results
+/// of each pass in soak mode are returned along with their seed.
+fn execute<F>(ctx: &mut Context, f: &F, soak: u64) -> Vec<(Seed, TestResults)>
+where
+    F: Fn(&mut Context),
+{
+    if soak == 1 {
+        f(ctx);
+        return Vec::new();
+    }
+    let base_seed = ctx.seed;
+    let mut passes = Vec::with_capacity(soak as usize);
+    for n in 0..soak {
+        let seed = base_seed.derive(n);
+        ctx.seed = seed;
+        let aggregated = std::mem::take(&mut ctx.test_results);
+        f(ctx);
+        let mut pass = std::mem::replace(&mut ctx.test_results, aggregated);
+        for failure in pass.failures.iter_mut() {
+            failure.message.push_str(&format!("seed: {}\n", seed));
+        }
+        ctx.test_results.add_subtests(&pass);
+        passes.push((seed, pass));
+    }
+    ctx.seed = base_seed;
+    passes
+}
+
+fn timing_report(tr: &TestResults) -> Option<String> {
+    let summary = tr.timing_summary()?;
+    let mut output = format!(
+        "timing: {} iterations in {:?} (min {:?}, median {:?}, p99 {:?}, max {:?})\n",
+        summary.iterations, summary.total, summary.min, summary.median, summary.p99, summary.max
+    );
+    if let Some((duration, input)) = &tr.slowest_input {
+        output.push_str(&format!("slowest input ({:?}): {}\n", duration, input));
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{num, range, string, vector};
+    use std::cell::RefCell;
+
+    #[test]
+    fn ensure_in_place_same_values() {
+        let mut ctx = Context::new();
+        ctx.set_seed(Seed::from(0x1234_5678_9abc_def0));
+        ctx.set_nb_tests(50);
+
+        let gen = || vector(range(0..20), string::ascii(range(0..10)));
+
+        let generated = RefCell::new(Vec::new());
+        forall(gen())
+            .ensure(|v| {
+                generated.borrow_mut().push(v);
+                property::equal(0, 0)
+            })
+            .test(&ctx);
+
+        let generated_in_place = RefCell::new(Vec::new());
+        forall(gen())
+            .ensure_in_place(|v| {
+                generated_in_place.borrow_mut().push(v.clone());
+                property::equal(0, 0)
+            })
+            .test(&ctx);
+
+        assert_eq!(generated.borrow().len(), 50);
+        assert_eq!(generated.into_inner(), generated_in_place.into_inner());
+
+        let failing = |ctx: &Context| {
+            forall(gen())
+                .ensure_in_place(|v| property::equal(v.len(), v.len() + 1))
+                .named("in place")
+                .with_seed(Seed::from(0x5eed))
+                .test(ctx)
+        };
+        let results = failing(&ctx);
+        assert_eq!(results.nb_failed, 50);
+        assert!(results
+            .failures
+            .iter()
+            .all(|f| f.property.as_deref() == Some("in place")));
+        ctx.set_seed(Seed::from(0xdead));
+        assert_eq!(failing(&ctx).failures, results.failures);
+    }
+
+    #[test]
+    fn named_failures_in_report() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(20);
+        forall(range(0u32..1000).and(range(0u32..1000)))
+            .ensure_named("commutativity", |(a, b)| property::equal(a + b, b + a))
+            .run(&mut ctx);
+        forall(range(0u32..1000))
+            .ensure_named("off by one", |a| property::equal(a, a + 1))
+            .run(&mut ctx);
+
+        let tr = &ctx.test_results;
+        assert_eq!(tr.nb_failed, 20);
+        assert!(tr
+            .failures
+            .iter()
+            .all(|f| f.property.as_deref() == Some("off by one")));
+        let report = format!("{:#}", tr);
+        assert!(report.contains("property: off by one\n"));
+        assert!(!report.contains("commutativity"));
+    }
+
+    #[test]
+    fn ensure_with_seed() {
+        let mut ctx = Context::new();
+        ctx.set_seed(Seed::from(1));
+        ctx.set_nb_tests(10);
+
+        let pinned_seed = Seed::from(0xdead_beef);
+        // num doesn't need a sub-stream per iteration
+        let expected = |seed| {
+            let mut r = R::from_seed(seed);
+            (0..10)
+                .map(|_| num::<u64>().gen(&mut r))
+                .collect::<Vec<_>>()
+        };
+
+        let pinned = RefCell::new(Vec::new());
+        let unpinned = RefCell::new(Vec::new());
+        forall(num::<u64>())
+            .ensure(|x| {
+                pinned.borrow_mut().push(x);
+                property::equal(x, x)
+            })
+            .with_seed(pinned_seed)
+            .run(&mut ctx);
+        forall(num::<u64>())
+            .ensure(|x| {
+                unpinned.borrow_mut().push(x);
+                property::equal(x, x)
+            })
+            .run(&mut ctx);
+
+        assert_eq!(pinned.into_inner(), expected(pinned_seed));
+        assert_eq!(unpinned.into_inner(), expected(Seed::from(1)));
+    }
+
+    #[test]
+    fn ensure_indexed() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(25);
+        let indices = RefCell::new(Vec::new());
+        let results = forall(num::<u8>())
+            .ensure_indexed(|idx, _| {
+                indices.borrow_mut().push(idx);
+                property::less(idx, 25)
+            })
+            .test(&ctx);
+        assert_eq!(results.nb_success, 25);
+        assert_eq!(indices.into_inner(), (0..25).collect::<Vec<u64>>());
+
+        // the same inputs as `ensure` for the same seed
+        let inputs = RefCell::new(Vec::new());
+        forall(num::<u8>())
+            .ensure(|n| {
+                inputs.borrow_mut().push(n);
+                property::equal(0, 0)
+            })
+            .test(&ctx);
+        let indexed_inputs = RefCell::new(Vec::new());
+        forall(num::<u8>())
+            .ensure_indexed(|_, n| {
+                indexed_inputs.borrow_mut().push(n);
+                property::equal(0, 0)
+            })
+            .test(&ctx);
+        assert_eq!(inputs.into_inner(), indexed_inputs.into_inner());
+    }
+
+    #[test]
+    fn discarded_iterations_are_skipped() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(200);
+        let results = forall(range(0u32..9))
+            .ensure(|n| property::equal(n % 2, 0).when(n % 2 == 0))
+            .test(&ctx);
+        assert_eq!(results.nb_tests, 200);
+        assert_eq!(results.nb_failed, 0);
+        assert!(results.nb_skipped > 0 && results.nb_success > 0);
+        assert_eq!(results.nb_skipped + results.nb_success, 200);
+        assert_eq!(results.to_status(), TestRunStatus::Passed);
+    }
+
+    #[test]
+    fn ensure_destructured() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(50);
+        let results = forall(crate::generator::tuple2(range(0u32..10), range(0u32..10)))
+            .ensure2(|a, b| property::equal(a + b, b + a))
+            .test(&ctx);
+        assert_eq!(results.nb_success, 50);
+
+        let results = forall(crate::generator::tuple3(
+            range(0u32..10),
+            range(0u32..10),
+            range(21u32..30),
+        ))
+        .ensure3(|a, b, c| property::greater(a + b, *c))
+        .test(&ctx);
+        assert_eq!(results.nb_failed, 50);
+        assert!(results.failures[0].message.starts_with("input = ("));
+    }
+
+    #[test]
+    fn such_that_exhaustion_discarded() {
+        use crate::Generator;
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        // only 0 is accepted, so most iterations exhaust the retries
+        let results = forall(num::<u64>().such_that(|n| *n == 0))
+            .ensure(|n| property::equal(n, 0))
+            .test(&ctx);
+        assert_eq!(results.nb_tests, 100);
+        assert_eq!(results.nb_skipped, 100);
+        assert_eq!(results.to_status(), TestRunStatus::Skipped);
+
+        let results = forall(range(0u8..3).such_that(|n| *n == 0))
+            .ensure(|n| property::equal(n, 0))
+            .test(&ctx);
+        assert_eq!(results.nb_success, 100);
+        assert_eq!(results.to_status(), TestRunStatus::Passed);
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of tests must be greater than 0")]
+    fn zero_tests_rejected() {
+        Context::new().set_nb_tests(0)
+    }
+
+    #[test]
+    fn all_discarded_is_skipped() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(30);
+        let results = forall(num::<u32>())
+            .ensure(|n| property::equal(n, n + 1).when(false))
+            .test(&ctx);
+        assert_eq!(results.nb_skipped, 30);
+        assert_eq!(results.nb_failed, 0);
+        assert_eq!(results.to_status(), TestRunStatus::Skipped);
+        assert_eq!(
+            results.to_string(),
+            format!(
+                "30 tests: 0 passed (0.0%), 0 failed, 30 skipped in {:.2}s",
+                results.duration.as_secs_f64()
+            )
+        );
+    }
+
+    #[test]
+    fn coverage_requirement() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(500);
+        let results = forall(range(0u32..999))
+            .ensure(|n| {
+                property::less(n, 1000)
+                    .cover("even", n % 2 == 0, 30.0)
+                    .and(property::equal(n, n).cover("zero", n == 0, 10.0))
+            })
+            .test(&ctx);
+        assert_eq!(results.nb_success, 500);
+        assert_eq!(results.nb_failed, 1);
+        // the coverage requirement is not a test of its own
+        assert_eq!(results.nb_tests, 500);
+        assert_eq!(results.coverage.len(), 2);
+        assert_eq!(results.coverage[0].label, "even");
+        assert_eq!(results.coverage[0].total, 500);
+        assert!(results.coverage[0].percent() >= 30.0);
+        let message = &results.failures[0].message;
+        assert!(message.starts_with("insufficient coverage of \"zero\": required 10.0%, observed"));
+    }
+
+    #[test]
+    fn discard_reasons_recorded() {
+        struct NotApplicable;
+
+        impl Property for NotApplicable {
+            fn result(&self) -> property::Outcome {
+                property::Outcome::Discarded
+            }
+
+            fn discard_reason(&self) -> Option<String> {
+                Some("not applicable".to_string())
             }
-            panic!(
-                "\n{:?} tests failed / {:?} tests runned",
-                tr.nb_failed, tr.nb_tests
-            );
         }
+
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(3);
+        let results = forall(num::<u8>())
+            .ensure_named("noted", |n| {
+                NotApplicable.label("l").and(property::equal(n, n))
+            })
+            .test(&ctx);
+        assert_eq!((results.nb_tests, results.nb_skipped), (3, 3));
+        assert_eq!(results.skips.len(), 3);
+        assert_eq!(
+            results.skips[0].to_string(),
+            "skipped: noted: not applicable"
+        );
+    }
+
+    #[test]
+    fn size_strategy_grows_sizes() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(200);
+        ctx.set_size_strategy(SizeStrategy::Linear { start: 0, end: 100 });
+        let sizes = RefCell::new(Vec::new());
+        forall(vector(range(0usize..100), num::<u8>()))
+            .ensure(|v| {
+                sizes.borrow_mut().push(v.len());
+                property::less_equal(v.len(), 100)
+            })
+            .test(&ctx);
+        let sizes = sizes.into_inner();
+        let early: usize = sizes[..100].iter().sum();
+        let late: usize = sizes[100..].iter().sum();
+        assert!(early < late, "early {} late {}", early, late);
+        assert_eq!(sizes[0], 0);
+
+        ctx.set_size_strategy(SizeStrategy::Constant(10));
+        let results = forall(vector(range(0usize..100), num::<u8>()).scale(|s| s / 2))
+            .ensure(|v| property::less_equal(v.len(), 5))
+            .test(&ctx);
+        assert_eq!(results.nb_success, 200);
+    }
+
+    #[test]
+    fn input_stats() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        let results = forall(range(10u32..20))
+            .ensure_named("bounded", |n| property::less_equal(n, 20))
+            .with_input_stats()
+            .test(&ctx);
+        assert_eq!(results.input_stats.len(), 1);
+        let stats = &results.input_stats[0];
+        assert_eq!(stats.property.as_deref(), Some("bounded"));
+        let values = stats.values.unwrap();
+        assert_eq!(values.count, 100);
+        assert!(values.min >= 10.0 && values.max <= 20.0);
+        assert!(values.mean() > 10.0 && values.mean() < 20.0);
+
+        let results = forall(vector(range(0usize..8), num::<u8>()))
+            .ensure(|v| property::less_equal(v.len(), 8))
+            .with_input_stats()
+            .test(&ctx);
+        assert_eq!(results.input_stats[0].lengths.unwrap().count, 100);
+
+        let results = forall(num::<u8>())
+            .ensure(|_| property::equal(1, 1))
+            .test(&ctx);
+        assert!(results.input_stats.is_empty());
+    }
+
+    /// Serialize the tests calling `run`, as they install a process-wide panic hook
+    static RUN_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn run_returns_results() {
+        let _lock = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let results = run(|ctx| {
+            forall(num::<u8>())
+                .ensure(|n| property::equal(n, n))
+                .run(ctx);
+            forall(num::<u16>())
+                .ensure(|n| property::equal(n, n))
+                .run(ctx);
+        });
+        assert!(results.nb_tests > 0);
+        assert_eq!(results.nb_success, results.nb_tests);
+    }
+
+    #[test]
+    fn run_wall_clock() {
+        let _lock = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let results = run(|ctx| {
+            for _ in 0..3 {
+                forall(num::<u8>())
+                    .ensure(|n| {
+                        std::thread::sleep(Duration::from_micros(50));
+                        property::equal(n, n)
+                    })
+                    .run(ctx);
+            }
+        });
+        // the properties run one after the other, within the wall clock
+        let wall_clock = results.wall_clock.unwrap();
+        assert!(results.duration > Duration::ZERO);
+        assert!(results.duration <= wall_clock);
+        assert!(results
+            .to_string()
+            .contains(&format!("{:.2}s wall clock", wall_clock.as_secs_f64())));
+    }
+
+    #[test]
+    fn env_read_once() {
+        use crate::config::{ENV_NO_PANIC_CATCH, ENV_READS};
+
+        ENV_READS.with(|reads| reads.borrow_mut().clear());
+        let ctx = Context::new();
+        forall(num::<u32>())
+            .ensure(|n| property::equal(n, n))
+            .test(&ctx);
+        let reads = ENV_READS.with(|reads| reads.borrow().clone());
+        assert_eq!(reads.iter().filter(|r| *r == ENV_NO_PANIC_CATCH).count(), 1);
+    }
+
+    #[test]
+    fn catch_panics_disabled() {
+        let mut ctx = Context::new();
+        ctx.set_catch_panics(false);
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            forall(num::<u32>())
+                .ensure(|_| -> property::RelationEq<u32> { panic!("uncaught") })
+                .test(&ctx)
+        }));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn skip_if() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(10);
+        forall(num::<u32>())
+            .ensure_named("skipped", |n| property::equal(n, n + 1))
+            .skip_if(true, "not on this platform")
+            .run(&mut ctx);
+        forall(num::<u32>())
+            .ensure(|n| property::equal(n, n))
+            .skip_if(false, "never skipped")
+            .run(&mut ctx);
+        let results = &ctx.test_results;
+        assert_eq!(results.nb_tests, 10);
+        assert_eq!(results.nb_success, 10);
+        assert_eq!(results.to_status(), TestRunStatus::Passed);
+        assert_eq!(results.skips.len(), 1);
+        assert!(results
+            .to_string()
+            .ends_with("\nskipped: skipped: not on this platform"));
+    }
+
+    #[test]
+    fn progress_report() {
+        assert_eq!(
+            progress(500, 1000, Duration::from_secs(25)),
+            "iteration 500 of 1000 after 25.0 seconds (~20 cases/sec)"
+        );
+        assert!(Heartbeat::EveryIterations(100).is_due(200, Duration::ZERO));
+        assert!(!Heartbeat::EveryIterations(100).is_due(150, Duration::ZERO));
+        assert!(Heartbeat::Every(Duration::from_secs(10)).is_due(1, Duration::from_secs(11)));
+        assert!(!Heartbeat::Never.is_due(100, Duration::from_secs(3600)));
+
+        // fast runs are not reporting their progress in failures
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(10);
+        ctx.set_heartbeat(Heartbeat::EveryIterations(5));
+        let results = forall(num::<u32>())
+            .ensure(|n| property::equal(n, n.wrapping_add(1)))
+            .test(&ctx);
+        assert!(results
+            .failures
+            .iter()
+            .all(|f| !f.message.contains("failed at")));
+    }
+
+    #[test]
+    fn iteration_durations_sum_to_total() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(20);
+        let start = Instant::now();
+        let results = forall(vector(range(0..100), range(0u32..1000)))
+            .ensure(|v| {
+                std::thread::sleep(Duration::from_millis(1));
+                property::less_equal(v.len(), 100)
+            })
+            .test(&ctx);
+        let elapsed = start.elapsed();
+        // the iterations are timed individually, within the duration of the whole run
+        assert!(results.duration >= Duration::from_millis(20));
+        assert!(results.duration <= elapsed);
+        let summary = results.timing_summary().expect("iterations timed");
+        assert_eq!(summary.iterations, 20);
+        assert!(summary.min >= Duration::from_millis(1));
+        assert!(summary.min <= summary.mean && summary.mean <= summary.max);
+        assert!(summary.p95 <= summary.max);
+        let (slowest, _) = results.slowest_input.as_ref().expect("slowest input");
+        assert_eq!(*slowest, summary.max);
+
+        let report = timing_report(&results).unwrap();
+        assert!(report.starts_with("timing: 20 iterations"));
+        assert!(report.contains("slowest input"));
+    }
+
+    #[test]
+    fn examples_first() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(10);
+        let inputs = RefCell::new(Vec::new());
+        forall(
+            num::<u32>()
+                .with_examples(vec![0, 1, u32::MAX])
+                .map(|n| n / 2),
+        )
+        .ensure_indexed(|_, n| {
+            inputs.borrow_mut().push(n);
+            property::equal(n, n)
+        })
+        .test(&ctx);
+        let without_examples = RefCell::new(Vec::new());
+        forall(num::<u32>().map(|n| n / 2))
+            .ensure_indexed(|_, n| {
+                without_examples.borrow_mut().push(n);
+                property::equal(n, n)
+            })
+            .test(&ctx);
+        let inputs = inputs.into_inner();
+        assert_eq!(inputs[..3], [0, 0, u32::MAX / 2]);
+        assert_eq!(inputs[3..], without_examples.into_inner()[3..]);
+
+        let results = forall(num::<u32>().with_examples(vec![0, 7]))
+            .ensure(|n| property::equal(n, 0))
+            .test(&ctx);
+        assert!(results.failures[0].message.starts_with("input = 7\n"));
+    }
+
+    /// The first inputs of a test, through `ensure_indexed`
+    fn first_inputs<G>(g: G, n: u64) -> Vec<G::Item>
+    where
+        G: Generator,
+        G::Item: fmt::Debug + 'static,
+    {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(n);
+        let inputs = RefCell::new(Vec::new());
+        forall(g)
+            .ensure_indexed(|_, x| {
+                inputs.borrow_mut().push(x);
+                property::equal(0, 0)
+            })
+            .test(&ctx);
+        inputs.into_inner()
+    }
+
+    #[test]
+    fn examples_through_combinators() {
+        use crate::generator::{constant, frequency, tuple2, vector};
+
+        let examples = || num::<u32>().with_examples(vec![0, u32::MAX]);
+        let pairs = first_inputs(tuple2(examples(), range(1u8..3)), 10);
+        assert_eq!((pairs[0].0, pairs[1].0), (0, u32::MAX));
+        assert!(pairs.iter().all(|(_, b)| (1..=3).contains(b)));
+
+        let even = first_inputs(examples().such_that(|n| n % 2 == 0), 10);
+        assert_eq!(even[0], 0);
+        assert!(even.iter().all(|n| n % 2 == 0));
+
+        let vectors = first_inputs(vector(constant(2), examples()), 3);
+        assert_eq!(vectors[..2], [vec![0, 0], vec![u32::MAX, u32::MAX]]);
+
+        let chosen = first_inputs(frequency(vec![(1, Box::new(examples()))]).map(|n| n / 2), 3);
+        assert_eq!(chosen[..2], [0, u32::MAX / 2]);
+    }
+
+    #[test]
+    fn soak_passes() {
+        let properties = |ctx: &mut Context| {
+            forall(num::<u8>())
+                .ensure_named("even", |n| property::equal(n % 2, 0))
+                .run(ctx);
+        };
+        let mut ctx = Context::new();
+        ctx.set_seed(Seed::from(0x50a4));
+        ctx.set_nb_tests(20);
+        assert!(execute(&mut ctx, &properties, 1).is_empty());
+        let single_pass = ctx.test_results.nb_tests;
+
+        let mut ctx = Context::new();
+        ctx.set_seed(Seed::from(0x50a4));
+        ctx.set_nb_tests(20);
+        let passes = execute(&mut ctx, &properties, 3);
+        assert_eq!(ctx.seed(), Seed::from(0x50a4));
+        assert_eq!(ctx.test_results.nb_tests, 3 * single_pass);
+
+        let seeds: Vec<Seed> = passes.iter().map(|(seed, _)| *seed).collect();
+        let expected: Vec<Seed> = (0..3).map(|n| Seed::from(0x50a4).derive(n)).collect();
+        assert_eq!(seeds, expected);
+        assert!(seeds[0] != seeds[1] && seeds[1] != seeds[2]);
+        for (seed, pass) in passes.iter() {
+            assert!(pass.nb_failed > 0);
+            let suffix = format!("seed: {}\n", seed);
+            assert!(pass.failures.iter().all(|f| f.message.ends_with(&suffix)));
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_properties() {
+        use crate::executor::{block_on, Executor};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counting(AtomicUsize);
+
+        async fn panicking(_: u32) -> property::RelationEq<u32> {
+            panic!("in future")
+        }
+
+        impl Executor for Counting {
+            fn block_on<F: Future>(&self, future: F) -> F::Output {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                block_on(future)
+            }
+        }
+
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(10);
+        let results = forall(num::<u32>())
+            .ensure_async(|n| async move { property::equal(n, n) })
+            .test(&ctx);
+        assert_eq!(results.nb_success, 10);
+
+        let executor = Counting(AtomicUsize::new(0));
+        let results = forall(num::<u32>())
+            .ensure_async(|n| async move { property::equal(n, n.wrapping_add(1)) })
+            .with_executor(&executor)
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 10);
+        assert_eq!(executor.0.load(Ordering::Relaxed), 10);
+
+        let results = forall(num::<u32>()).ensure_async(panicking).test(&ctx);
+        assert!(results.failures[0].message.contains("panic: \"in future\""));
+
+        ctx.set_nb_tests(2);
+        let results = forall(num::<u32>())
+            .ensure_async(|_| std::future::pending::<property::RelationEq<u32>>())
+            .with_timeout(Duration::from_millis(10))
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 2);
+        assert!(results.failures[0].message.ends_with("timeout: 10ms\n"));
+
+        let failing = |ctx: &Context| {
+            forall(num::<u32>())
+                .ensure_async(|n| async move { property::equal(n, n.wrapping_add(1)) })
+                .named("async successor")
+                .with_seed(Seed::from(0x5eed))
+                .with_formatter(|n: &u32| format!("n={:#x}", n))
+                .test(ctx)
+        };
+        let results = failing(&ctx);
+        assert!(results.failures.iter().all(|f| {
+            f.property.as_deref() == Some("async successor")
+                && f.message.starts_with("input = n=0x")
+        }));
+        ctx.set_seed(Seed::from(0xdead));
+        let reseeded = failing(&ctx);
+        let messages = |results: &TestResults| {
+            results
+                .failures
+                .iter()
+                .map(|f| f.message.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(messages(&results), messages(&reseeded));
+
+        // the same inputs as `ensure` for the same seed
+        let inputs = RefCell::new(Vec::new());
+        forall(num::<u32>())
+            .ensure(|n| {
+                inputs.borrow_mut().push(n);
+                property::equal(0, 0)
+            })
+            .test(&ctx);
+        let async_inputs = RefCell::new(Vec::new());
+        forall(num::<u32>())
+            .ensure_async(|n| {
+                async_inputs.borrow_mut().push(n);
+                async { property::equal(0, 0) }
+            })
+            .test(&ctx);
+        assert_eq!(inputs.into_inner(), async_inputs.into_inner());
+
+        ctx.set_nb_tests(1);
+        ctx.set_emit_repro(true);
+        let results = failing(&ctx);
+        assert!(
+            results.failures[0]
+                .message
+                .contains("reproduction:\n#[test]\nfn async_successor_repro() {\n    // input: "),
+            "{}",
+            results.failures[0].message
+        );
+    }
+
+    #[test]
+    fn custom_formatter() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(10);
+        let results = forall(num::<u32>())
+            .ensure(|n| property::equal(n, n.wrapping_add(1)))
+            .with_formatter(|n: &u32| format!("n={:#x}", n))
+            .test(&ctx);
+        assert_eq!(results.failures.len(), 10);
+        assert!(results
+            .failures
+            .iter()
+            .all(|f| f.message.starts_with("input = n=0x")));
+    }
+
+    #[test]
+    fn non_clone_inputs() {
+        #[derive(Debug)]
+        struct Handle(u32);
+
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(20);
+        let handles = || num::<u32>().map(Handle);
+        let results = forall(handles())
+            .ensure(|h| property::equal(h.0 % 2, 2))
+            .with_formatter(|h: &Handle| format!("handle {:#x}", h.0))
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 20);
+        assert!(results.failures[0].message.starts_with("input = handle 0x"));
+        assert!(results.slowest_input.unwrap().1.starts_with("Handle("));
+
+        let results = forall(handles())
+            .ensure_indexed(|idx, h| property::equal(u64::from(h.0), idx))
+            .with_formatter(|h: &Handle| format!("handle {:#x}", h.0))
+            .test(&ctx);
+        assert!(results.nb_failed > 0);
+        assert!(results.failures[0].message.starts_with("input = handle 0x"));
+        assert!(results.slowest_input.unwrap().1.starts_with("Handle("));
+    }
+
+    #[test]
+    fn capped_failures_not_formatted() {
+        let formatted = std::rc::Rc::new(Cell::new(0));
+        let counter = formatted.clone();
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        ctx.set_max_failures(Some(3));
+        let results = forall(num::<u32>())
+            .ensure(|n| property::equal(n, n.wrapping_add(1)))
+            .with_formatter(move |n: &u32| {
+                counter.set(counter.get() + 1);
+                format!("{:?}", n)
+            })
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 100);
+        assert_eq!(results.failures.len(), 3);
+        assert_eq!(formatted.get(), 3);
+    }
+
+    #[test]
+    fn registered_generators() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        ctx.register::<u8>(range(10..20).into_shared());
+        ctx.register::<String>(string::ascii(range(1..4)).into_shared());
+        let results = forall(ctx.generator::<u8>())
+            .ensure(|n| property::less(n, 21))
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 0);
+        let results = forall(ctx.generator::<String>())
+            .ensure(|s| property::greater(s.len(), 0))
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 0);
+
+        let unregistered = catch_unwind(AssertUnwindSafe(|| ctx.generator::<u16>()));
+        let message = unregistered.err().unwrap();
+        assert_eq!(
+            message.downcast_ref::<String>().unwrap(),
+            "no generator registered for u16"
+        );
+    }
+
+    #[test]
+    fn fallible_generation_failure() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(10);
+        let words = crate::generator::try_from_fn(|_| {
+            std::fs::read_to_string("/nonexistent/smoke/words").map(|list| list.len())
+        })
+        .assert_ok();
+        let results = forall(words).ensure(|n| property::equal(n, n)).test(&ctx);
+        assert_eq!(results.nb_failed, 10);
+        let message = &results.failures[0].message;
+        assert!(
+            message.starts_with("input: <discarded during generation>\ngeneration failed: Os {")
+        );
+        assert!(message.contains("NotFound"), "{}", message);
+    }
+
+    #[test]
+    fn fallible_property_failure() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        let results = forall(range(0u32..9))
+            .ensure_try(|n| {
+                let word = std::fs::read_to_string(format!("/nonexistent/smoke/{}", n))?;
+                Ok::<_, std::io::Error>(property::equal(word.len(), 0))
+            })
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 100);
+        let message = &results.failures[0].message;
+        assert!(
+            message.contains("property construction failed: Os {"),
+            "{}",
+            message
+        );
+        let results = forall(range(0u32..9))
+            .ensure_try(|n| Ok::<_, std::io::Error>(property::less(n, 10)))
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 0);
+    }
+
+    #[test]
+    fn reference_check() {
+        use std::rc::Rc;
+
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        let checked = Rc::new(RefCell::new(Vec::new()));
+        let run = |ctx: &Context| {
+            let record = checked.clone();
+            forall(num::<u32>())
+                .ensure(|n| property::equal(n, n))
+                .with_reference_check(
+                    7,
+                    |n: &u32| n.count_ones(),
+                    move |n: &u32| {
+                        record.borrow_mut().push(*n);
+                        (0..32).filter(|i| n & (1 << i) != 0).count() as u32
+                    },
+                )
+                .test(ctx)
+        };
+        let results = run(&ctx);
+        assert_eq!(results.nb_failed, 0);
+        assert_eq!(results.nb_reference_checks, 15);
+        assert!(results
+            .to_string()
+            .contains(", 15 checked against the reference"));
+        let first = checked.replace(Vec::new());
+        assert_eq!(first.len(), 15);
+        run(&ctx);
+        assert_eq!(*checked.borrow(), first);
+
+        let results = forall(num::<u32>())
+            .ensure(|n| property::equal(n, n))
+            .with_reference_check(1, |n: &u32| n.count_ones(), |n: &u32| n.count_zeros())
+            .test(&ctx);
+        assert!(results.nb_failed > 0);
+        assert!(results.failures[0].message.contains("reference check"));
+    }
+
+    #[test]
+    fn ensure_result_message() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(50);
+        let results = forall(range(0u32..10))
+            .ensure_result(|n| {
+                if n < 5 {
+                    Ok(())
+                } else {
+                    Err(format!("{} is too large", n))
+                }
+            })
+            .test(&ctx);
+        assert!(results.nb_success > 0);
+        assert!(results.nb_failed > 0);
+        let failure = &results.failures[0].message;
+        assert!(failure.contains("check failed: "), "{}", failure);
+        assert!(failure.contains(" is too large"), "{}", failure);
+    }
+
+    #[test]
+    fn triviality_check() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(100);
+        let results = forall(num::<u32>())
+            .ensure(|n| match n {
+                0 => property::equal(n, 0),
+                // the bug: the other inputs aren't checked
+                _ => property::equal(0, 0),
+            })
+            .with_triviality_check()
+            .test(&ctx);
+        assert_eq!(results.nb_failed, 0);
+        assert_eq!(results.warnings.len(), 1);
+        assert_eq!(
+            results.warnings[0].message,
+            "property appears input-independent, all the tests checked:\n  ==: \n    left: 0\n    right: 0\n"
+        );
+        assert!(results
+            .to_string()
+            .contains("\nwarning: property appears input-independent"));
+
+        let results = forall(num::<u32>())
+            .ensure_named("rotate", |n| {
+                property::equal(n.rotate_left(3).rotate_right(3), n)
+            })
+            .with_triviality_check()
+            .test(&ctx);
+        assert!(results.warnings.is_empty());
+
+        // constant inputs don't make the property trivial
+        let results = forall(crate::generator::constant(4u8))
+            .ensure(|n| property::less(n, 5))
+            .with_triviality_check()
+            .test(&ctx);
+        assert!(results.warnings.is_empty());
+    }
+
+    #[test]
+    fn seed_printed() {
+        let mut ctx = Context::new();
+        ctx.set_seed(Seed::from(0x5eed));
+        let mut out = Vec::new();
+        ctx.set_print_seed(false);
+        assert!(!write_seed(&mut out, &ctx));
+        assert!(out.is_empty());
+        ctx.set_print_seed(true);
+        assert!(write_seed(&mut out, &ctx));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "seed: 00000000-00000000-00000000-00005EED (generation version {})\n",
+                GENERATION_VERSION
+            )
+        );
+
+        // a failed run prints the seed line once, whether printed beforehand or not
+        for print_seed in [false, true] {
+            ctx.set_print_seed(print_seed);
+            let mut out = Vec::new();
+            let seed_printed = write_seed(&mut out, &ctx);
+            write_failure_seed(&mut out, ctx.seed, seed_printed);
+            let out = String::from_utf8(out).unwrap();
+            assert_eq!(out.matches("seed: ").count(), 1, "{}", out);
+        }
+    }
+
+    #[test]
+    fn repro_emitted() {
+        let mut ctx = Context::new();
+        ctx.set_nb_tests(1);
+        let property = || {
+            forall(range(3u8..3).and(string::ascii(range(1..1))))
+                .ensure_named("Short pair", |(n, s)| property::equal(s.len(), n as usize))
+        };
+        let results = property().test(&ctx);
+        assert!(!results.failures[0].message.contains("reproduction"));
+
+        ctx.set_emit_repro(true);
+        let results = property().with_rust_literal().test(&ctx);
+        let message = &results.failures[0].message;
+        let (_, repro) = message.split_once("reproduction:\n").unwrap();
+        assert!(repro.starts_with(
+            "#[test]\nfn short_pair_repro() {\n    let input = (3u8, String::from(\""
+        ));
+        assert!(repro.ends_with("\"));\n    short_pair(input);\n}\n"));
+
+        let results = property().test(&ctx);
+        let message = &results.failures[0].message;
+        assert!(message.contains("    // input: (3, \""), "{}", message);
+
+        let results = forall(vector(range(2..2), num::<u8>()))
+            .ensure_in_place(|v| property::equal(v.len(), 3))
+            .named("triple")
+            .test(&ctx);
+        let message = &results.failures[0].message;
+        assert!(
+            message.contains("reproduction:\n#[test]\nfn triple_repro() {\n    // input: ["),
+            "{}",
+            message
+        );
+
+        // the index of the iteration isn't reproduced
+        let results = forall(num::<u8>())
+            .ensure_indexed(|idx, n| property::equal(idx, u64::from(n) + 1000))
+            .test(&ctx);
+        assert!(!results.failures[0].message.contains("reproduction"));
+    }
+
+    #[test]
+    fn panic_location_reported() {
+        let _lock = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let failures = RefCell::new(Vec::new());
+        run(|ctx| {
+            ctx.set_nb_tests(1);
+            let results = forall(num::<u32>())
+                .ensure(|_| -> property::RelationEq<u32> { panic!("located") })
+                .test(ctx);
+            failures.borrow_mut().extend(results.failures);
+        });
+        let failures = failures.into_inner();
+        let message = &failures[0].message;
+        assert!(message.contains("panic: \"located\"\nlocation: "));
+        assert!(message.contains(&format!("location: {}:", file!())));
+    }
+
+    thread_local! {
+        static USER_HOOK_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn user_hook_calls() -> usize {
+        USER_HOOK_CALLS.with(Cell::get)
+    }
+
+    #[test]
+    fn user_panic_hook_restored() {
+        let _lock = RUN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let default_hook = take_hook();
+        set_hook(Box::new(|_| USER_HOOK_CALLS.with(|c| c.set(c.get() + 1))));
+
+        // the panics caught by smoke are silenced, but not the final report
+        let failed = catch_unwind(|| {
+            run(|ctx| {
+                ctx.set_nb_tests(10);
+                forall(num::<u32>())
+                    .ensure(|_| -> property::RelationEq<u32> { panic!("caught") })
+                    .run(ctx);
+            })
+        });
+        assert!(failed.is_err());
+        assert_eq!(user_hook_calls(), 1);
+
+        // the uncaught panics are reported to the hook of the user
+        let failed = catch_unwind(|| {
+            run(|ctx| {
+                ctx.set_catch_panics(false);
+                forall(num::<u32>())
+                    .ensure(|_| -> property::RelationEq<u32> { panic!("uncaught") })
+                    .run(ctx);
+            })
+        });
+        assert!(failed.is_err());
+        assert_eq!(user_hook_calls(), 2);
+
+        // the hook of the user is still installed after run
+        let _ = catch_unwind(|| panic!("after run"));
+        assert_eq!(user_hook_calls(), 3);
+
+        set_hook(default_hook);
     }
 }
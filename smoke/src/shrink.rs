@@ -0,0 +1,209 @@
+//! Shrinking of failing inputs
+//!
+//! A failing input is often large and noisy, shrinking tries simpler candidates
+//! of the input, keeping the ones that still fail, to find a minimal failing input.
+
+/// Values that can produce simpler candidates of themselves
+pub trait Shrink: Sized {
+    /// Simpler candidates of this value, from the most to the least aggressive simplification
+    ///
+    /// The value itself must not be part of the candidates
+    fn shrink(&self) -> Vec<Self>;
+}
+
+/// Maximum number of successful shrinking steps of `minimize`
+const MAX_SHRINK_STEPS: usize = 1000;
+
+/// Shrink `value` as long as a simpler candidate still `fails`, and return the
+/// simplest failing value found
+///
+/// ```
+/// use smoke::shrink::minimize;
+///
+/// let minimal = minimize(vec![3u32, 17, 4, 42], |v| v.iter().any(|n| *n > 10));
+/// assert_eq!(minimal, vec![11]);
+/// ```
+pub fn minimize<T, F>(value: T, fails: F) -> T
+where
+    T: Shrink,
+    F: Fn(&T) -> bool,
+{
+    let mut current = value;
+    for _ in 0..MAX_SHRINK_STEPS {
+        match current
+            .shrink()
+            .into_iter()
+            .find(|candidate| fails(candidate))
+        {
+            None => break,
+            Some(simpler) => current = simpler,
+        }
+    }
+    current
+}
+
+macro_rules! define_Shrink_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Shrink for $t {
+                fn shrink(&self) -> Vec<Self> {
+                    let mut candidates = Vec::new();
+                    for candidate in [0, *self / 2, self.saturating_sub(1)] {
+                        if candidate < *self && !candidates.contains(&candidate) {
+                            candidates.push(candidate)
+                        }
+                    }
+                    candidates
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! define_Shrink_signed {
+    ($($t:ty),*) => {
+        $(
+            impl Shrink for $t {
+                fn shrink(&self) -> Vec<Self> {
+                    let mut candidates = Vec::new();
+                    let toward_zero = *self - self.signum();
+                    for candidate in [0, self.saturating_neg(), *self / 2, toward_zero] {
+                        let simpler = candidate.unsigned_abs() < self.unsigned_abs()
+                            || (candidate == self.saturating_neg() && *self < 0);
+                        if simpler && candidate != *self && !candidates.contains(&candidate) {
+                            candidates.push(candidate)
+                        }
+                    }
+                    candidates
+                }
+            }
+        )*
+    };
+}
+
+define_Shrink_unsigned!(u8, u16, u32, u64, u128, usize);
+define_Shrink_signed!(i8, i16, i32, i64, i128, isize);
+
+impl Shrink for bool {
+    fn shrink(&self) -> Vec<Self> {
+        if *self {
+            vec![false]
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// Characters are simplified toward '0' for digits, and toward 'a' otherwise,
+/// going through the lowercase and ASCII versions of the character
+impl Shrink for char {
+    fn shrink(&self) -> Vec<Self> {
+        let c = *self;
+        if c.is_ascii_digit() {
+            return if c == '0' { vec![] } else { vec!['0'] };
+        }
+        let mut candidates = Vec::new();
+        if c != 'a' {
+            candidates.push('a')
+        }
+        if c.is_ascii_uppercase() {
+            candidates.push(c.to_ascii_lowercase())
+        }
+        candidates
+    }
+}
+
+/// Candidates of removing chunks of elements, from the largest chunks (half the elements)
+/// to single elements
+fn remove_chunks<T: Clone>(elements: &[T]) -> Vec<Vec<T>> {
+    let mut candidates = Vec::new();
+    let len = elements.len();
+    let mut chunk = len;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < len {
+            let end = (start + chunk).min(len);
+            let mut candidate = Vec::with_capacity(len - (end - start));
+            candidate.extend_from_slice(&elements[..start]);
+            candidate.extend_from_slice(&elements[end..]);
+            candidates.push(candidate);
+            start += chunk;
+        }
+        chunk /= 2;
+    }
+    candidates
+}
+
+/// Candidates of shrinking a single element
+fn shrink_elements<T: Shrink + Clone>(elements: &[T]) -> Vec<Vec<T>> {
+    let mut candidates = Vec::new();
+    for (i, element) in elements.iter().enumerate() {
+        for simpler in element.shrink() {
+            let mut candidate = elements.to_vec();
+            candidate[i] = simpler;
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Vectors are shrunk by removing chunks of elements, then by shrinking individual elements
+impl<T: Shrink + Clone> Shrink for Vec<T> {
+    fn shrink(&self) -> Vec<Self> {
+        let mut candidates = remove_chunks(self);
+        candidates.extend(shrink_elements(self));
+        candidates
+    }
+}
+
+/// Strings are shrunk by removing chunks of characters, then by simplifying individual characters
+impl Shrink for String {
+    fn shrink(&self) -> Vec<Self> {
+        let chars: Vec<char> = self.chars().collect();
+        let mut candidates = remove_chunks(&chars);
+        candidates.extend(shrink_elements(&chars));
+        candidates
+            .into_iter()
+            .map(|chars| chars.into_iter().collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(minimize(1000u32, |n| *n >= 37), 37);
+        assert_eq!(minimize(-1000i32, |n| *n <= -37), -37);
+        assert_eq!(minimize(1000i64, |n| *n != 0), 1);
+        assert_eq!(minimize(i8::MIN, |n| *n < 0), -1);
+        assert!(0u8.shrink().is_empty());
+    }
+
+    #[test]
+    fn vec_minimal_subsequence() {
+        let v = vec![1u32, 50, 3, 4, 99, 7, 12, 0, 8];
+        let fails = |v: &Vec<u32>| v.iter().filter(|n| **n >= 10).count() >= 2;
+        assert_eq!(minimize(v, fails), vec![10, 10]);
+
+        let sorted_fails = |v: &Vec<u8>| v.windows(2).any(|w| w[0] > w[1]);
+        assert_eq!(
+            minimize(vec![5, 9, 13, 2, 40, 41], sorted_fails),
+            vec![1, 0]
+        );
+    }
+
+    #[test]
+    fn string_simplification() {
+        let s = "Hé, 42 ünïcödé!".to_string();
+        assert_eq!(minimize(s.clone(), |s| s.chars().count() >= 3), "aaa");
+        assert_eq!(
+            minimize(s.clone(), |s| s.chars().any(|c| c.is_numeric())),
+            "0"
+        );
+        assert_eq!(minimize(s, |s| !s.is_ascii()), "é");
+        assert_eq!('Q'.shrink(), vec!['a', 'q']);
+    }
+}
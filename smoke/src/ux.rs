@@ -1,10 +1,11 @@
+use std::convert::TryFrom;
 use std::time::Duration;
 
 /// A key-value pair reporting element
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Element(String, Value);
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Elements(Vec<Element>);
 
 fn display_element(output: &mut String, indent: usize, element: &Element) {
@@ -13,27 +14,63 @@ fn display_element(output: &mut String, indent: usize, element: &Element) {
     for _ in 0..indent {
         output.push(' ');
     }
-    output.push_str(&k);
+    output.push_str(k);
     output.push_str(": ");
     match v {
         Value::Tree(tree) => {
             // replace recursion instead of smashing the stack
-            output.push_str("\n");
+            output.push('\n');
             for el in tree.0.iter() {
                 display_element(output, indent + 2, el)
             }
         }
         Value::Str(s) => {
             output.push_str(s);
-            output.push_str("\n");
+            output.push('\n');
+        }
+        Value::Int(i) => {
+            output.push_str(&i.to_string());
+            output.push('\n');
+        }
+        Value::Bool(b) => {
+            output.push_str(if *b { "true" } else { "false" });
+            output.push('\n');
         }
     }
 }
 
-impl Default for Elements {
-    fn default() -> Self {
-        Elements(Vec::new())
+fn json_string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn json_elements(output: &mut String, elements: &Elements) {
+    output.push('{');
+    for (i, Element(k, v)) in elements.0.iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        json_string(output, k);
+        output.push(':');
+        match v {
+            Value::Tree(tree) => json_elements(output, tree),
+            Value::Str(s) => json_string(output, s),
+            Value::Int(i) => output.push_str(&i.to_string()),
+            Value::Bool(b) => output.push_str(if *b { "true" } else { "false" }),
+        }
     }
+    output.push('}');
 }
 
 impl Elements {
@@ -52,6 +89,53 @@ impl Elements {
         }
         output
     }
+
+    /// Iterate over the elements
+    pub fn iter(&self) -> impl Iterator<Item = &Element> {
+        self.0.iter()
+    }
+
+    /// Get the value of the first element with the key `key`
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.iter().find(|e| e.0 == key).map(|e| &e.1)
+    }
+
+    /// Get the value at the end of a path of keys, where each key is looked up
+    /// in the tree value of the previous key
+    ///
+    /// ```
+    /// use smoke::ux::{Elements, Value};
+    ///
+    /// let mut cmp = Elements::new();
+    /// cmp.append("left", 1u32.into());
+    /// let mut and = Elements::new();
+    /// and.append("left", Value::Tree(cmp));
+    /// let mut elements = Elements::new();
+    /// elements.append("and", and.into());
+    ///
+    /// assert_eq!(elements.find_path(&["and", "left", "left"]), Some(&Value::Int(1)));
+    /// assert_eq!(elements.find_path(&["and", "right"]), None);
+    /// ```
+    pub fn find_path(&self, path: &[&str]) -> Option<&Value> {
+        let (first, rest) = path.split_first()?;
+        let value = self.get(first)?;
+        if rest.is_empty() {
+            Some(value)
+        } else {
+            match value {
+                Value::Tree(tree) => tree.find_path(rest),
+                _ => None,
+            }
+        }
+    }
+
+    /// Serialize the elements as a JSON object, where each element is a key of the object,
+    /// preserving the elements order
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        json_elements(&mut output, self);
+        output
+    }
 }
 
 impl Element {
@@ -64,6 +148,16 @@ impl Element {
         display_element(&mut output, indent, self);
         output
     }
+
+    /// Key of this element
+    pub fn key(&self) -> &str {
+        &self.0
+    }
+
+    /// Value of this element
+    pub fn value(&self) -> &Value {
+        &self.1
+    }
 }
 
 impl std::fmt::Display for Elements {
@@ -78,6 +172,8 @@ impl std::fmt::Display for Elements {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
     Str(String),
+    Int(i128),
+    Bool(bool),
     Tree(Elements),
 }
 
@@ -85,6 +181,14 @@ impl Value {
     pub fn sub(element: Element) -> Self {
         Value::Tree(Elements(vec![element]))
     }
+
+    /// Create a value from the debug representation of `t`, always stored as a `Str`
+    ///
+    /// Use the `From` conversions to store the integers and booleans as `Int` and `Bool`,
+    /// or `IntoValue` to store the integers of a generic type as `Int`
+    pub fn from_debug<T: std::fmt::Debug + ?Sized>(t: &T) -> Self {
+        Value::Str(format!("{:?}", t))
+    }
 }
 
 impl From<&str> for Value {
@@ -99,12 +203,69 @@ impl From<String> for Value {
     }
 }
 
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+macro_rules! define_Value_from_int {
+    ($($int_ty:ty),*) => {
+        $(
+            impl From<$int_ty> for Value {
+                fn from(i: $int_ty) -> Self {
+                    Value::Int(i128::from(i))
+                }
+            }
+        )*
+    };
+}
+
+define_Value_from_int!(u8, u16, u32, u64, i8, i16, i32, i64, i128);
+
+impl From<usize> for Value {
+    fn from(i: usize) -> Self {
+        Value::Int(i as i128)
+    }
+}
+
+impl From<isize> for Value {
+    fn from(i: isize) -> Self {
+        Value::Int(i as i128)
+    }
+}
+
 impl From<Elements> for Value {
     fn from(elements: Elements) -> Self {
         Value::Tree(elements)
     }
 }
 
+/// Conversion of the values compared by the properties to a `Value`
+///
+/// The primitive integers, and the references to them, are stored as `Int` with
+/// their `From` conversion, any other value falls back to `Value::from_debug`.
+pub trait IntoValue {
+    fn to_value(&self) -> Value;
+}
+
+impl<T: std::fmt::Debug + ?Sized> IntoValue for T {
+    fn to_value(&self) -> Value {
+        let debug = format!("{:?}", self);
+        let type_name = std::any::type_name::<T>().trim_start_matches('&');
+        macro_rules! parse_int {
+            ($($int_ty:ty),*) => {
+                match type_name {
+                    $(stringify!($int_ty) => debug.parse::<$int_ty>().ok().map(Value::from),)*
+                    _ => None,
+                }
+            };
+        }
+        let int = parse_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, i128, isize);
+        int.unwrap_or(Value::Str(debug))
+    }
+}
+
 /*
 impl From<Element> for Value {
     fn from(b: Element) -> Self {
@@ -122,7 +283,7 @@ pub enum TestRunStatus {
 }
 
 /// A more detailed status of a test run
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct TestResults {
     /// Total inner tests
     pub nb_tests: usize,
@@ -132,10 +293,430 @@ pub struct TestResults {
     pub nb_failed: usize,
     /// Total number of skipped tests
     pub nb_skipped: usize,
+    /// Number of tests also compared with a reference implementation
+    pub nb_reference_checks: usize,
     /// Failures
-    pub failures: Vec<String>,
-    /// Duration for this overall tests
+    pub failures: Vec<Failure>,
+    /// Duration for this overall tests, summed over the tests
     pub duration: Duration,
+    /// Elapsed time of the whole run, measured by `run`, including the time spent
+    /// outside of the tests
+    pub wall_clock: Option<Duration>,
+    /// Distribution of the duration of each individual iteration
+    pub iteration_durations: DurationHistogram,
+    /// Duration and debug representation of the slowest input
+    pub slowest_input: Option<(Duration, String)>,
+    /// Coverage of the labels with a coverage requirement
+    pub coverage: Vec<Coverage>,
+    /// Summary of the generated inputs, for the properties collecting them
+    pub input_stats: Vec<InputStats>,
+    /// Properties skipped as a whole, and tests discarded for a reason (see
+    /// `Property::discard_reason`)
+    pub skips: Vec<Skip>,
+    /// Suspicious behaviors of the properties, not failing the run
+    pub warnings: Vec<Warning>,
+}
+
+/// A property skipped as a whole, without running any test, or a test discarded
+/// for a reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skip {
+    /// Name of the skipped property, if the property is named
+    pub property: Option<String>,
+    /// Reason of the skip
+    pub reason: String,
+}
+
+impl std::fmt::Display for Skip {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.property {
+            None => write!(f, "skipped: {}", self.reason),
+            Some(property) => write!(f, "skipped: {}: {}", property, self.reason),
+        }
+    }
+}
+
+/// A suspicious behavior of a property, reported without failing the run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Name of the property, if the property is named
+    pub property: Option<String>,
+    /// Description of the suspicious behavior
+    pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.property {
+            None => write!(f, "warning: {}", self.message),
+            Some(property) => write!(f, "warning: {}: {}", property, self.message),
+        }
+    }
+}
+
+/// Number of tests meeting the condition of a coverage label
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    /// Label of the coverage condition
+    pub label: String,
+    /// Number of tests meeting the condition
+    pub hits: usize,
+    /// Number of tests where the condition was evaluated
+    pub total: usize,
+    /// Minimum percentage of tests required to meet the condition
+    pub required_percent: f64,
+}
+
+impl Coverage {
+    /// Percentage of the tests meeting the condition
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.hits as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// Summary statistics of a series of numbers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericSummary {
+    /// Number of recorded numbers
+    pub count: u64,
+    /// Smallest recorded number
+    pub min: f64,
+    /// Largest recorded number
+    pub max: f64,
+    /// Sum of the recorded numbers
+    pub sum: f64,
+}
+
+impl NumericSummary {
+    fn new(v: f64) -> Self {
+        NumericSummary {
+            count: 1,
+            min: v,
+            max: v,
+            sum: v,
+        }
+    }
+
+    fn add(&mut self, v: f64) {
+        self.count += 1;
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        self.sum += v;
+    }
+
+    /// Average of the recorded numbers
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+impl std::fmt::Display for NumericSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "count {}, min {}, max {}, mean {:.2}",
+            self.count,
+            self.min,
+            self.max,
+            self.mean()
+        )
+    }
+}
+
+/// Summary of the inputs generated for a property
+///
+/// Each input adds itself to the summary through the `Describe` trait
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputStats {
+    /// Name of the property, if the property is named
+    pub property: Option<String>,
+    /// Summary of the numeric values
+    pub values: Option<NumericSummary>,
+    /// Summary of the lengths of collections and strings
+    pub lengths: Option<NumericSummary>,
+    /// Number of occurences of each variant
+    pub variants: Vec<(String, u64)>,
+}
+
+impl InputStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a numeric value
+    pub fn add_value(&mut self, v: f64) {
+        match &mut self.values {
+            None => self.values = Some(NumericSummary::new(v)),
+            Some(summary) => summary.add(v),
+        }
+    }
+
+    /// Record the length of a collection or a string
+    pub fn add_length(&mut self, len: usize) {
+        match &mut self.lengths {
+            None => self.lengths = Some(NumericSummary::new(len as f64)),
+            Some(summary) => summary.add(len as f64),
+        }
+    }
+
+    /// Record an occurence of the variant `name`
+    pub fn add_variant(&mut self, name: &str) {
+        match self.variants.iter_mut().find(|(n, _)| n == name) {
+            Some((_, count)) => *count += 1,
+            None => self.variants.push((name.to_string(), 1)),
+        }
+    }
+}
+
+impl std::fmt::Display for InputStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.property {
+            None => writeln!(f, "input stats:")?,
+            Some(property) => writeln!(f, "input stats of {}:", property)?,
+        }
+        if let Some(values) = &self.values {
+            writeln!(f, "  values: {}", values)?;
+        }
+        if let Some(lengths) = &self.lengths {
+            writeln!(f, "  lengths: {}", lengths)?;
+        }
+        for (name, count) in self.variants.iter() {
+            writeln!(f, "  variant {}: {}", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Items able to describe themselves into the summary of the generated inputs
+///
+/// ```
+/// use smoke::ux::{Describe, InputStats};
+///
+/// enum Shape { Circle(u32), Square(u32) }
+///
+/// impl Describe for Shape {
+///     fn describe(&self, stats: &mut InputStats) {
+///         match self {
+///             Shape::Circle(r) => { stats.add_variant("circle"); r.describe(stats) }
+///             Shape::Square(s) => { stats.add_variant("square"); s.describe(stats) }
+///         }
+///     }
+/// }
+/// ```
+pub trait Describe {
+    /// Add this item to the summary statistics
+    fn describe(&self, stats: &mut InputStats);
+}
+
+macro_rules! define_Describe_int {
+    ($($t:ty),*) => {
+        $(
+            impl Describe for $t {
+                fn describe(&self, stats: &mut InputStats) {
+                    stats.add_value(*self as f64)
+                }
+            }
+        )*
+    };
+}
+
+define_Describe_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Describe for String {
+    fn describe(&self, stats: &mut InputStats) {
+        stats.add_length(self.len())
+    }
+}
+
+impl<T> Describe for Vec<T> {
+    fn describe(&self, stats: &mut InputStats) {
+        stats.add_length(self.len())
+    }
+}
+
+/// A failure recorded in a test run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Name of the property that failed, if the property is named
+    pub property: Option<String>,
+    /// Description of the failure
+    pub message: String,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(property) = &self.property {
+            writeln!(f, "property: {}", property)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Representation of the inputs of the failed tests in the report
+///
+/// The inputs are only formatted for the failures kept in the results, so that
+/// the failures dropped by `Context::set_max_failures` are never formatted.
+/// Any `Fn(&T) -> String` closure is a formatter:
+///
+/// ```
+/// use smoke::{generator::num, property::equal, forall};
+///
+/// let property = forall(num::<u32>())
+///     .ensure(|x| equal(x.rotate_left(8).rotate_right(8), x))
+///     .with_formatter(|x: &u32| format!("{:#010x}", x));
+/// ```
+pub trait FailureFormatter<T: ?Sized> {
+    /// Format the input of a failed test
+    fn format_input(&self, input: &T) -> String;
+}
+
+/// The default formatter, using the `Debug` representation of the inputs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugFormatter;
+
+impl<T: std::fmt::Debug + ?Sized> FailureFormatter<T> for DebugFormatter {
+    fn format_input(&self, input: &T) -> String {
+        format!("{:?}", input)
+    }
+}
+
+impl<T: ?Sized, F> FailureFormatter<T> for F
+where
+    F: Fn(&T) -> String,
+{
+    fn format_input(&self, input: &T) -> String {
+        self(input)
+    }
+}
+
+const HISTOGRAM_BUCKETS: usize = 64;
+
+/// Histogram of durations, in log2 scaled buckets of nanoseconds
+///
+/// The bucket `i` count the durations in the `[2^i, 2^(i+1))` nanoseconds range,
+/// so that the percentiles are an approximation within a factor 2 of the real
+/// value; the minimum, maximum and total are exact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurationHistogram {
+    buckets: [u64; HISTOGRAM_BUCKETS],
+    count: u64,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl Default for DurationHistogram {
+    fn default() -> Self {
+        DurationHistogram {
+            buckets: [0; HISTOGRAM_BUCKETS],
+            count: 0,
+            total: Duration::default(),
+            min: Duration::default(),
+            max: Duration::default(),
+        }
+    }
+}
+
+impl DurationHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bucket_index(d: Duration) -> usize {
+        let nanos = u64::try_from(d.as_nanos()).unwrap_or(u64::MAX);
+        if nanos == 0 {
+            0
+        } else {
+            63 - nanos.leading_zeros() as usize
+        }
+    }
+
+    /// Record a duration
+    pub fn add(&mut self, d: Duration) {
+        self.buckets[Self::bucket_index(d)] += 1;
+        if self.count == 0 || d < self.min {
+            self.min = d
+        }
+        if d > self.max {
+            self.max = d
+        }
+        self.count += 1;
+        self.total += d;
+    }
+
+    /// Merge the other histogram into this one, bucket-wise
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        for (bucket, other_bucket) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *bucket += other_bucket
+        }
+        if self.count == 0 || other.min < self.min {
+            self.min = other.min
+        }
+        if other.max > self.max {
+            self.max = other.max
+        }
+        self.count += other.count;
+        self.total += other.total;
+    }
+
+    /// Number of recorded durations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of all the recorded durations
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Approximation of the duration at the given percentile (between 0 and 100)
+    ///
+    /// Return None if no durations has been recorded
+    pub fn percentile(&self, percent: u32) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        } else if percent == 0 {
+            return Some(self.min);
+        }
+        let target = (self.count * u64::from(percent)).div_ceil(100);
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket;
+            if cumulative >= target {
+                let upper = Duration::from_nanos(((1u128 << (i + 1)) - 1) as u64);
+                return Some(upper.clamp(self.min, self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+/// Statistics over the duration of each iteration of a test run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingSummary {
+    /// Number of timed iterations
+    pub iterations: u64,
+    /// Sum of all the iterations duration
+    pub total: Duration,
+    /// Fastest iteration
+    pub min: Duration,
+    /// Slowest iteration
+    pub max: Duration,
+    /// Average iteration duration
+    pub mean: Duration,
+    /// Median iteration duration (approximated)
+    pub median: Duration,
+    /// 95th percentile of iteration duration (approximated)
+    pub p95: Duration,
+    /// 99th percentile of iteration duration (approximated)
+    pub p99: Duration,
 }
 
 impl TestResults {
@@ -160,30 +741,221 @@ impl TestResults {
         self.nb_success += 1;
     }
 
-    /*
     pub fn add_skipped(&mut self) {
         self.nb_tests += 1;
         self.nb_skipped += 1;
     }
-    */
 
     pub fn add_failed(&mut self, reason: String) {
         self.nb_tests += 1;
         self.nb_failed += 1;
-        self.failures.push(reason);
+        self.failures.push(Failure {
+            property: None,
+            message: reason,
+        });
+    }
+
+    /// Record a failed test, only rendering its reason if the failure is kept,
+    /// that is when less than `max_failures` failures are already kept
+    pub fn add_failed_capped<F>(&mut self, max_failures: Option<usize>, reason: F)
+    where
+        F: FnOnce() -> String,
+    {
+        match max_failures {
+            Some(max) if self.failures.len() >= max => {
+                self.nb_tests += 1;
+                self.nb_failed += 1;
+            }
+            _ => self.add_failed(reason()),
+        }
+    }
+
+    /// Record the property as skipped as a whole, for the given reason
+    pub fn add_skip(&mut self, reason: &str) {
+        self.skips.push(Skip {
+            property: None,
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Record a suspicious behavior of the property, reported without failing the run
+    pub fn add_warning(&mut self, message: &str) {
+        self.warnings.push(Warning {
+            property: None,
+            message: message.to_string(),
+        })
+    }
+
+    /// Attribute all the unattributed failures to the property `name`
+    pub fn set_property_name(&mut self, name: &str) {
+        for failure in self.failures.iter_mut() {
+            if failure.property.is_none() {
+                failure.property = Some(name.to_string())
+            }
+        }
+        for stats in self.input_stats.iter_mut() {
+            if stats.property.is_none() {
+                stats.property = Some(name.to_string())
+            }
+        }
+        for skip in self.skips.iter_mut() {
+            if skip.property.is_none() {
+                skip.property = Some(name.to_string())
+            }
+        }
+        for warning in self.warnings.iter_mut() {
+            if warning.property.is_none() {
+                warning.property = Some(name.to_string())
+            }
+        }
+    }
+
+    /// Record whether the condition of a coverage label is met by a test
+    pub fn add_coverage(&mut self, label: &str, hit: bool, required_percent: f64) {
+        let coverage = match self.coverage.iter_mut().position(|c| c.label == label) {
+            Some(i) => &mut self.coverage[i],
+            None => {
+                self.coverage.push(Coverage {
+                    label: label.to_string(),
+                    hits: 0,
+                    total: 0,
+                    required_percent,
+                });
+                self.coverage.last_mut().unwrap()
+            }
+        };
+        coverage.total += 1;
+        if hit {
+            coverage.hits += 1
+        }
+    }
+
+    /// Add a failure for each coverage label not meeting its requirement
+    pub fn check_coverage(&mut self) {
+        let insufficient: Vec<String> = self
+            .coverage
+            .iter()
+            .filter(|c| c.percent() < c.required_percent)
+            .map(|c| {
+                format!(
+                    "insufficient coverage of \"{}\": required {:.1}%, observed {:.1}% ({} / {})\n",
+                    c.label,
+                    c.required_percent,
+                    c.percent(),
+                    c.hits,
+                    c.total
+                )
+            })
+            .collect();
+        for reason in insufficient {
+            self.add_coverage_failure(reason)
+        }
+    }
+
+    /// Record the failure of a coverage requirement, which is not a test of its own
+    fn add_coverage_failure(&mut self, reason: String) {
+        self.nb_failed += 1;
+        self.failures.push(Failure {
+            property: None,
+            message: reason,
+        });
     }
 
     pub fn set_duration(&mut self, d: Duration) {
         self.duration = d
     }
 
+    /// Record the duration of a single iteration, accounting it in the overall duration
+    pub fn add_iteration_duration(&mut self, d: Duration) {
+        self.iteration_durations.add(d);
+        self.duration += d
+    }
+
+    /// Record the duration of a single iteration along with the debug representation
+    /// of its input, which is kept if this is the slowest iteration so far
+    pub fn add_iteration(&mut self, d: Duration, input: String) {
+        self.add_iteration_with(d, || input)
+    }
+
+    /// Similar to `add_iteration`, but the input is only rendered if this is
+    /// the slowest iteration so far
+    pub fn add_iteration_with<F>(&mut self, d: Duration, input: F)
+    where
+        F: FnOnce() -> String,
+    {
+        self.add_iteration_duration(d);
+        self.set_slowest_input(d, input)
+    }
+
+    fn set_slowest_input<F>(&mut self, d: Duration, input: F)
+    where
+        F: FnOnce() -> String,
+    {
+        match &self.slowest_input {
+            Some((slowest, _)) if *slowest >= d => {}
+            _ => self.slowest_input = Some((d, input())),
+        }
+    }
+
+    /// Statistics over the recorded iterations duration, or None if no iterations were timed
+    pub fn timing_summary(&self) -> Option<TimingSummary> {
+        let histogram = &self.iteration_durations;
+        let iterations = histogram.count();
+        if iterations == 0 {
+            return None;
+        }
+        let total = histogram.total();
+        let mean = Duration::from_nanos((total.as_nanos() / u128::from(iterations)) as u64);
+        Some(TimingSummary {
+            iterations,
+            total,
+            min: histogram.min,
+            max: histogram.max,
+            mean,
+            median: histogram.percentile(50)?,
+            p95: histogram.percentile(95)?,
+            p99: histogram.percentile(99)?,
+        })
+    }
+
     pub fn add_subtests(&mut self, sub_tests: &Self) {
         self.nb_tests += sub_tests.nb_tests;
         self.nb_success += sub_tests.nb_success;
         self.nb_failed += sub_tests.nb_failed;
         self.nb_skipped += sub_tests.nb_skipped;
+        self.nb_reference_checks += sub_tests.nb_reference_checks;
         self.failures.extend_from_slice(&sub_tests.failures);
         self.duration += sub_tests.duration;
+        self.wall_clock = match (self.wall_clock, sub_tests.wall_clock) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.iteration_durations
+            .merge(&sub_tests.iteration_durations);
+        self.coverage.extend_from_slice(&sub_tests.coverage);
+        self.input_stats.extend_from_slice(&sub_tests.input_stats);
+        self.skips.extend_from_slice(&sub_tests.skips);
+        self.warnings.extend_from_slice(&sub_tests.warnings);
+        if let Some((d, input)) = &sub_tests.slowest_input {
+            self.set_slowest_input(*d, || input.clone())
+        }
+    }
+
+    /// Combination of the results `a` and `b`, e.g. of separate test suites
+    ///
+    /// ```
+    /// use smoke::ux::TestResults;
+    ///
+    /// let mut a = TestResults::new();
+    /// a.add_success();
+    /// let mut b = TestResults::new();
+    /// b.add_failed("wrong".to_string());
+    /// let all = [a, b].iter().fold(TestResults::default(), TestResults::merge);
+    /// assert_eq!((all.nb_tests, all.nb_failed), (2, 1));
+    /// ```
+    pub fn merge(mut a: Self, b: &Self) -> Self {
+        a.add_subtests(b);
+        a
     }
 
     /*
@@ -192,3 +964,257 @@ impl TestResults {
     }
     */
 }
+
+/// One line summary of the results followed by the skipped properties, or with
+/// the alternate flag (`{:#}`), also followed by all the failures
+impl std::fmt::Display for TestResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} tests: {} passed", self.nb_tests, self.nb_success)?;
+        if self.nb_tests > 0 {
+            let percent = self.nb_success as f64 * 100.0 / self.nb_tests as f64;
+            write!(f, " ({:.1}%)", percent)?;
+        }
+        write!(
+            f,
+            ", {} failed, {} skipped in {:.2}s",
+            self.nb_failed,
+            self.nb_skipped,
+            self.duration.as_secs_f64()
+        )?;
+        if let Some(wall_clock) = self.wall_clock {
+            write!(
+                f,
+                " of total test time (summed), {:.2}s wall clock",
+                wall_clock.as_secs_f64()
+            )?;
+        }
+        if self.nb_reference_checks > 0 {
+            write!(
+                f,
+                ", {} checked against the reference",
+                self.nb_reference_checks
+            )?;
+        }
+        for skip in self.skips.iter() {
+            write!(f, "\n{}", skip)?;
+        }
+        for warning in self.warnings.iter() {
+            write!(f, "\n{}", warning)?;
+        }
+        if f.alternate() {
+            writeln!(f)?;
+            for (i, failure) in self.failures.iter().enumerate() {
+                writeln!(f, "# Failure {}\n{}", i, failure)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_summary() {
+        let mut results = TestResults::new();
+        assert_eq!(results.timing_summary(), None);
+        for ms in 1..=100 {
+            results.add_iteration_duration(Duration::from_millis(ms));
+        }
+        let summary = results.timing_summary().unwrap();
+        assert_eq!(summary.iterations, 100);
+        assert_eq!(summary.min, Duration::from_millis(1));
+        assert_eq!(summary.max, Duration::from_millis(100));
+        assert!(summary.p95 >= Duration::from_millis(95) && summary.p95 <= summary.max);
+        assert!(summary.median >= Duration::from_millis(50) && summary.median < summary.p95);
+        assert_eq!(summary.mean, Duration::from_micros(50_500));
+        assert_eq!(summary.total, results.duration);
+    }
+
+    #[test]
+    fn value_from_debug() {
+        assert_eq!(Value::from_debug(&-42i64), Value::Str("-42".to_string()));
+        assert_eq!(Value::from_debug(&true), Value::Str("true".to_string()));
+        assert_eq!(Value::from(-42i64), Value::Int(-42));
+        assert_eq!(Value::from(u64::MAX), Value::Int(u64::MAX as i128));
+        assert_eq!(Value::from(usize::MAX), Value::Int(usize::MAX as i128));
+        assert_eq!(Value::from(true), Value::Bool(true));
+        assert_eq!(Value::from_debug("12"), Value::Str("\"12\"".to_string()));
+        assert_eq!(
+            Value::from_debug(&vec![1, 2]),
+            Value::Str("[1, 2]".to_string())
+        );
+    }
+
+    #[test]
+    fn into_value() {
+        assert_eq!(3u32.to_value(), Value::Int(3));
+        assert_eq!(<&i64>::to_value(&&-42), Value::Int(-42));
+        assert_eq!(usize::MAX.to_value(), Value::Int(usize::MAX as i128));
+        assert_eq!(true.to_value(), Value::Str("true".to_string()));
+        assert_eq!(1.5f64.to_value(), Value::Str("1.5".to_string()));
+        assert_eq!("12".to_value(), Value::Str("\"12\"".to_string()));
+        assert_eq!(Some(3u8).to_value(), Value::Str("Some(3)".to_string()));
+    }
+
+    #[test]
+    fn elements_json() {
+        let mut inner = Elements::new();
+        inner.append("left", 12u32.into());
+        inner.append("right", (-3i64).into());
+        inner.append("ok", false.into());
+        let mut elements = Elements::new();
+        elements.append("==", inner.into());
+        elements.append("message \"quoted\"", "line\n\\\u{1}".into());
+        assert_eq!(
+            elements.to_json(),
+            r#"{"==":{"left":12,"right":-3,"ok":false},"message \"quoted\"":"line\n\\\u0001"}"#
+        );
+        assert_eq!(
+            elements.display(0),
+            "==: \n  left: 12\n  right: -3\n  ok: false\nmessage \"quoted\": line\n\\\u{1}\n"
+        );
+    }
+
+    #[test]
+    fn elements_lookup() {
+        let mut cmp = Elements::new();
+        cmp.append("left", "20".into());
+        cmp.append("right", "10".into());
+        let mut and = Elements::new();
+        and.append("left", Value::sub(Element::new("<=", cmp.clone().into())));
+        and.append("right", "passed".into());
+        and.append("right", "shadowed".into());
+        let mut elements = Elements::new();
+        elements.append("and", and.clone().into());
+
+        let keys: Vec<&str> = and.iter().map(|e| e.key()).collect();
+        assert_eq!(keys, vec!["left", "right", "right"]);
+        assert_eq!(and.get("right"), Some(&"passed".into()));
+        assert_eq!(and.get("missing"), None);
+        assert_eq!(
+            elements.find_path(&["and", "left", "<="]),
+            Some(&Value::Tree(cmp))
+        );
+        assert_eq!(
+            elements.find_path(&["and", "left", "<=", "right"]),
+            Some(&"10".into())
+        );
+        assert_eq!(elements.find_path(&["and", "right", "left"]), None);
+        assert_eq!(elements.find_path(&[]), None);
+        let first = elements.iter().next().unwrap();
+        assert_eq!(first.value(), &Value::Tree(and));
+    }
+
+    #[test]
+    fn display_summary() {
+        let mut results = TestResults::new();
+        for _ in 0..998 {
+            results.add_success();
+        }
+        results.add_failed("input = 1\n".to_string());
+        results.add_failed("input = 2\n".to_string());
+        results.set_duration(Duration::from_millis(1240));
+        assert_eq!(
+            results.to_string(),
+            "1000 tests: 998 passed (99.8%), 2 failed, 0 skipped in 1.24s"
+        );
+        assert_eq!(
+            format!("{:#}", results),
+            "1000 tests: 998 passed (99.8%), 2 failed, 0 skipped in 1.24s\n\
+             # Failure 0\ninput = 1\n\n\
+             # Failure 1\ninput = 2\n\n"
+        );
+    }
+
+    #[test]
+    fn display_summary_no_tests() {
+        let results = TestResults::new();
+        assert_eq!(
+            results.to_string(),
+            "0 tests: 0 passed, 0 failed, 0 skipped in 0.00s"
+        );
+        let skipped = TestResults {
+            nb_tests: 4,
+            nb_skipped: 4,
+            ..TestResults::new()
+        };
+        assert_eq!(
+            format!("{:#}", skipped),
+            "4 tests: 0 passed (0.0%), 0 failed, 4 skipped in 0.00s\n"
+        );
+    }
+
+    #[test]
+    fn histogram_merge() {
+        let mut a = DurationHistogram::new();
+        let mut b = DurationHistogram::new();
+        let mut all = DurationHistogram::new();
+        for us in 1..=50 {
+            a.add(Duration::from_micros(us));
+            all.add(Duration::from_micros(us));
+        }
+        for ms in 1..=50 {
+            b.add(Duration::from_millis(ms));
+            all.add(Duration::from_millis(ms));
+        }
+        let mut merged = DurationHistogram::new();
+        merged.merge(&a);
+        merged.merge(&b);
+        merged.merge(&DurationHistogram::new());
+        assert_eq!(merged, all);
+        assert_eq!(merged.count(), 100);
+        assert_eq!(merged.percentile(0), Some(Duration::from_micros(1)));
+        assert_eq!(merged.percentile(100), Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn merge_results() {
+        let mut a = TestResults::new();
+        a.add_iteration(Duration::from_millis(5), "5".to_string());
+        a.add_success();
+        a.add_skipped();
+        a.add_failed("a failed".to_string());
+        a.add_skip("not here");
+        let mut b = TestResults::new();
+        b.add_iteration(Duration::from_millis(7), "7".to_string());
+        b.add_success();
+        b.add_failed("b failed".to_string());
+        b.add_warning("suspicious");
+        b.nb_reference_checks = 2;
+
+        let merged = TestResults::merge(a.clone(), &b);
+        assert_eq!(merged.nb_tests, a.nb_tests + b.nb_tests);
+        assert_eq!(merged.nb_success, 2);
+        assert_eq!(merged.nb_failed, 2);
+        assert_eq!(merged.nb_skipped, 1);
+        assert_eq!(merged.nb_reference_checks, 2);
+        assert_eq!(merged.duration, Duration::from_millis(12));
+        assert_eq!(merged.iteration_durations.count(), 2);
+        let failures: Vec<&str> = merged.failures.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(failures, vec!["a failed", "b failed"]);
+        assert_eq!(merged.skips.len(), 1);
+        assert_eq!(merged.warnings.len(), 1);
+        assert_eq!(
+            merged.slowest_input,
+            Some((Duration::from_millis(7), "7".to_string()))
+        );
+        assert_eq!(TestResults::merge(TestResults::default(), &b), b);
+    }
+
+    #[test]
+    fn slowest_input() {
+        let mut a = TestResults::new();
+        a.add_iteration(Duration::from_millis(2), "2".to_string());
+        a.add_iteration(Duration::from_millis(30), "30".to_string());
+        a.add_iteration(Duration::from_millis(4), "4".to_string());
+        let mut b = TestResults::new();
+        b.add_iteration(Duration::from_millis(10), "10".to_string());
+        b.add_subtests(&a);
+        assert_eq!(
+            b.slowest_input,
+            Some((Duration::from_millis(30), "30".to_string()))
+        );
+    }
+}